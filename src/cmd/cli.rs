@@ -1,4 +1,10 @@
-use clap::{ArgGroup, Parser, Subcommand, command};
+use crate::pixels::lsb::LsbMode;
+use crate::png::alpha::AlphaMode;
+use crate::png::idat::IdatMode;
+use crate::polyglot::Polyglot;
+use crate::steganalysis::ReportFormat;
+use crate::text::TextMode;
+use clap::{ArgGroup, Parser, Subcommand};
 
 #[derive(Debug, Parser)]
 #[command(name = "veil")]
@@ -15,32 +21,185 @@ pub enum Commands {
     Check {
         #[arg(short = 'f', long = "file")]
         file_path: String,
+
+        /// Also run statistical steganalysis on the decoded pixels
+        #[arg(long = "analyze")]
+        analyze: bool,
+
+        /// Output format of the analysis report
+        #[arg(long = "format", value_enum, default_value_t = ReportFormat::Text, requires = "analyze")]
+        format: ReportFormat,
     },
     /// Hide data inside a file
     #[command(arg_required_else_help = true)]
     #[command(group(
         ArgGroup::new("input")
-            .args(["data_path", "message"])
+            .args(["data_paths", "message"])
     ))]
     Hide {
-        #[arg(short = 'f', long = "file")]
-        file_path: String,
+        /// Carrier file, or several with --shares or --stripe
+        #[arg(short = 'f', long = "file", num_args = 1.., required = true)]
+        file_paths: Vec<String>,
 
-        #[arg(short = 'd', long = "data", group = "input")]
-        data_path: Option<String>,
+        #[arg(short = 'd', long = "data", group = "input", num_args = 1..)]
+        data_paths: Vec<String>,
 
         #[arg(short = 'm', long = "message", group = "input")]
         message: Option<String>,
 
         #[arg(short = 'o', long = "output")]
         output_path: Option<String>,
+
+        /// Encoding used when the carrier is a text file
+        #[arg(long = "text-mode", value_enum, default_value_t = TextMode::ZeroWidth)]
+        text_mode: TextMode,
+
+        /// Also make the output a valid file of another format
+        #[arg(long = "polyglot", value_enum)]
+        polyglot: Option<Polyglot>,
+
+        /// Hide the data inside the PNG image data stream instead of a chunk
+        #[arg(long = "idat", value_enum, conflicts_with = "polyglot")]
+        idat: Option<IdatMode>,
+
+        /// Hide the data in the palette order of an indexed-color PNG
+        #[arg(long = "palette", conflicts_with_all = ["polyglot", "idat"])]
+        palette: bool,
+
+        /// Hide the data in transparent pixels or alpha LSBs of a PNG
+        #[arg(long = "alpha", value_enum, conflicts_with_all = ["polyglot", "idat", "palette"])]
+        alpha: Option<AlphaMode>,
+
+        /// How sample LSBs are changed by pixel-domain embedding
+        #[arg(long = "lsb-mode", value_enum, default_value_t = LsbMode::Replace)]
+        lsb_mode: LsbMode,
+
+        /// Store the data in a PNG chunk of this type, e.g. `prVt` to survive image edits
+        #[arg(long = "chunk-type", value_name = "TYPE",
+              conflicts_with_all = ["polyglot", "idat", "palette", "alpha", "adaptive"])]
+        chunk_type: Option<String>,
+
+        /// Embed only in textured regions of the pixels, chosen by the key
+        #[arg(long = "adaptive", conflicts_with_all = ["polyglot", "idat", "palette", "alpha"])]
+        adaptive: bool,
+
+        /// Key that selects the samples used by adaptive embedding
+        #[arg(long = "key", requires = "adaptive")]
+        key: Option<String>,
+
+        /// Protect the data with this many Reed-Solomon check bytes per block
+        #[arg(long = "fec", value_name = "PARITY", conflicts_with = "polyglot",
+              value_parser = clap::value_parser!(u8).range(2..=128))]
+        fec: Option<u8>,
+
+        /// Split the data into this many secret shares, one per carrier file
+        #[arg(long = "shares", conflicts_with = "polyglot",
+              value_parser = clap::value_parser!(u8).range(2..))]
+        shares: Option<u8>,
+
+        /// Encrypt the data so that only this passphrase unlocks it
+        #[arg(long = "passphrase", conflicts_with = "polyglot")]
+        passphrase: Option<String>,
+
+        /// File to encrypt as a decoy next to the data, unlocked by --decoy-passphrase
+        #[arg(long = "decoy", requires_all = ["passphrase", "decoy_passphrase"])]
+        decoy: Option<String>,
+
+        /// Passphrase that reveals the decoy instead of the data
+        #[arg(long = "decoy-passphrase", requires = "decoy")]
+        decoy_passphrase: Option<String>,
+
+        /// Spread the data over all carrier files in proportion to their capacity
+        #[arg(long = "stripe", conflicts_with_all = ["polyglot", "shares"])]
+        stripe: bool,
+
+        /// Report PSNR, SSIM, changed samples and changed chunks of every carrier
+        #[arg(long = "metrics")]
+        metrics: bool,
+
+        /// Number of shares needed to rebuild the data (defaults to all of them)
+        #[arg(long = "threshold", requires = "shares",
+              value_parser = clap::value_parser!(u8).range(2..))]
+        threshold: Option<u8>,
     },
-    /// Extract all hidden data from a file
+    /// List the chunks of a PNG file
     #[command(arg_required_else_help = true)]
-    Extract {
+    Inspect {
         #[arg(short = 'f', long = "file")]
         file_path: String,
+
+        /// Show entropy, byte histograms and compression ratios, and flag anomalies
+        #[arg(long = "stats")]
+        stats: bool,
+    },
+    /// Check a PNG against the PNG specification
+    #[command(arg_required_else_help = true)]
+    Lint {
+        #[arg(short = 'f', long = "file")]
+        file_path: String,
+    },
+    /// Render bit planes or a difference map of an image as a black-and-white PNG
+    #[command(arg_required_else_help = true)]
+    Visualize {
+        #[arg(short = 'f', long = "file")]
+        file_path: String,
+
+        /// Bit plane to render, such as R0 or Y1; several planes are XORed
+        #[arg(long = "plane", num_args = 1.., required_unless_present = "diff")]
+        planes: Vec<String>,
+
+        /// Cover image to compare with; pixels that differ are drawn white
+        #[arg(long = "diff", value_name = "COVER", conflicts_with = "planes")]
+        diff: Option<String>,
+
+        #[arg(short = 'o', long = "output")]
+        output_path: String,
+    },
+    /// Measure how much a stego file differs from its cover
+    #[command(arg_required_else_help = true)]
+    Compare {
+        /// The original file
+        cover_path: String,
+
+        /// The file with hidden data
+        stego_path: String,
+    },
+    /// Show the chunks added, removed, reordered or modified between two PNGs
+    #[command(arg_required_else_help = true)]
+    Diff {
+        /// The original PNG
+        old_path: String,
+
+        /// The changed PNG
+        new_path: String,
+
+        /// Also show a hex diff of the data of modified chunks
+        #[arg(long = "hex")]
+        hex: bool,
+    },
+    /// Extract all hidden data from a file
+    #[command(arg_required_else_help = true)]
+    Extract {
+        /// File to extract from, or several holding secret shares or stripes
+        #[arg(short = 'f', long = "file", num_args = 1.., required = true)]
+        file_paths: Vec<String>,
         #[arg(short = 'o', long = "output")]
         output_dir: Option<String>,
+
+        /// Key used when the data was hidden with adaptive embedding
+        #[arg(long = "key")]
+        key: Option<String>,
+
+        /// Extract the data appended after the end of the image instead
+        #[arg(long = "trailing", conflicts_with = "key")]
+        trailing: bool,
+
+        /// Extract the data from the PNG chunk of this type
+        #[arg(long = "chunk-type", value_name = "TYPE", conflicts_with_all = ["key", "trailing"])]
+        chunk_type: Option<String>,
+
+        /// Passphrase that unlocks one of the encrypted payloads
+        #[arg(long = "passphrase")]
+        passphrase: Option<String>,
     },
 }
@@ -0,0 +1,148 @@
+//! Comparing a cover file with its stego version.
+//!
+//! Decoded images are compared sample by sample with the metrics of
+//! [`pixels::quality`], and PNG files additionally chunk by chunk with
+//! [`png::diff`], since chunk embedding leaves the pixels untouched.
+
+use crate::pixels::quality::{self, Quality};
+use crate::png::diff::{self, Change, ChangeKind};
+use crate::{Result, SteganographyFile};
+
+/// Everything that differs between a cover and a stego file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comparison {
+    /// Pixel metrics, if both files are images.
+    pub quality: Option<Quality>,
+    /// Chunk changes, if both files are PNGs.
+    pub chunks: Option<Vec<Change>>,
+}
+
+impl Comparison {
+    /// Renders the metrics and the changed chunks as text.
+    pub fn to_text(&self) -> String {
+        let mut lines = Vec::new();
+        if let Some(q) = &self.quality {
+            lines.push(format!("PSNR: {:.2} dB", q.psnr));
+            lines.push(format!("MSE: {:.4}", q.mse));
+            lines.push(format!("SSIM: {:.5}", q.ssim));
+            lines.push(format!(
+                "Changed samples: {} of {} ({:.2}%)",
+                q.changed_samples,
+                q.total_samples,
+                q.changed_fraction() * 100.0
+            ));
+        }
+        if let Some(chunks) = &self.chunks {
+            let changed: Vec<&Change> = chunks
+                .iter()
+                .filter(|c| c.kind != ChangeKind::Unchanged)
+                .collect();
+            if changed.is_empty() {
+                lines.push("Chunks: unchanged".into());
+            } else {
+                lines.push("Chunks:".into());
+                lines.extend(changed.iter().map(|c| format!("  {}", c.to_text())));
+            }
+        }
+        if lines.is_empty() {
+            lines.push("Nothing to compare for these file formats".into());
+        }
+        lines.join("\n")
+    }
+}
+
+/// Compares the displayed pixels and, for PNGs, the chunks of two files.
+pub fn compare(cover: &SteganographyFile, stego: &SteganographyFile) -> Result<Comparison> {
+    let quality = match (cover.decode_colors()?, stego.decode_colors()?) {
+        (Some(a), Some(b)) => Some(quality::measure(&a, &b)?),
+        _ => None,
+    };
+    let chunks = match (cover, stego) {
+        (SteganographyFile::Png(a), SteganographyFile::Png(b)) => Some(diff::diff(a, b)),
+        _ => None,
+    };
+    Ok(Comparison { quality, chunks })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Steganography;
+    use crate::pixels::PixelBuffer;
+    use crate::png::raster;
+
+    fn image() -> SteganographyFile {
+        let samples = (0..16 * 16 * 3).map(|i| (i * 7 % 256) as u16).collect();
+        let pixels = PixelBuffer::new(16, 16, 3, 255, samples).unwrap();
+        SteganographyFile::Png(raster::to_png(&pixels).unwrap())
+    }
+
+    #[test]
+    fn test_chunk_embedding_keeps_pixels() {
+        let mut stego = image();
+        stego.hide_data(b"payload").unwrap();
+
+        let comparison = compare(&image(), &stego).unwrap();
+        assert_eq!(comparison.quality.unwrap().changed_samples, 0);
+        assert!(
+            comparison
+                .to_text()
+                .ends_with("Chunks:\n  + veIl at 159 (7 bytes)")
+        );
+    }
+
+    #[test]
+    fn test_palette_embedding_keeps_colors() {
+        use crate::png::{Chunk, ChunkType, ImageHeader, Png, palette};
+        use std::str::FromStr;
+
+        let header = ImageHeader {
+            width: 16,
+            height: 16,
+            bit_depth: 8,
+            color_type: ImageHeader::COLOR_INDEXED,
+            compression_method: 0,
+            filter_method: 0,
+            interlace_method: 0,
+        };
+        let chunk = |t: &str, d: &[u8]| Chunk::new(ChunkType::from_str(t).unwrap(), d.to_vec());
+        let colors: Vec<u8> = (0..128 * 3).map(|i| (i * 17 % 256) as u8).collect();
+        let mut cover = Png::from_chunks(vec![
+            chunk("IHDR", &header.as_bytes()),
+            chunk("PLTE", &colors),
+            chunk("IDAT", b""),
+            chunk("IEND", b""),
+        ]);
+        let indices = (0..256).map(|i| i % 128).collect();
+        let pixels = PixelBuffer::new(16, 16, 1, 255, indices).unwrap();
+        raster::encode(&mut cover, &pixels).unwrap();
+
+        let mut stego = cover.clone();
+        palette::hide(&mut stego, b"payload").unwrap();
+
+        let comparison = compare(
+            &SteganographyFile::Png(cover),
+            &SteganographyFile::Png(stego),
+        )
+        .unwrap();
+        let quality = comparison.quality.unwrap();
+        assert_eq!(quality.changed_samples, 0);
+        assert_eq!(quality.total_samples, 16 * 16 * 4);
+    }
+
+    #[test]
+    fn test_pixel_changes() {
+        let cover = image();
+        let SteganographyFile::Png(mut png) = image() else {
+            unreachable!()
+        };
+        let mut pixels = raster::decode(&png).unwrap();
+        pixels.samples_mut()[0] ^= 1;
+        raster::encode(&mut png, &pixels).unwrap();
+
+        let comparison = compare(&cover, &SteganographyFile::Png(png)).unwrap();
+        let text = comparison.to_text();
+        assert!(text.contains("Changed samples: 1 of 768 (0.13%)"));
+        assert!(text.contains("~ IDAT"));
+    }
+}
@@ -0,0 +1,176 @@
+//! Encrypted containers with room for a decoy payload.
+//!
+//! A container always holds [`SLOTS`] equally sized slots after a random salt.
+//! Each payload is encrypted with ChaCha20-Poly1305 under a key derived from
+//! its own passphrase with Argon2id, padded with random bytes to the slot size
+//! and placed in a random slot. Unused slots are filled with random bytes,
+//! which cannot be told apart from ciphertext without the right key. So a
+//! container with a decoy and a hidden payload looks exactly like one with a
+//! single payload, and revealing the decoy passphrase under coercion shows
+//! nothing about the other slot.
+
+use crate::Result;
+use argon2::Argon2;
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use rand::Rng;
+use rand::seq::SliceRandom;
+
+/// Number of slots in every container.
+pub const SLOTS: usize = 2;
+
+/// Size of the key derivation salt in bytes.
+pub const SALT_LEN: usize = 16;
+
+const NONCE_LEN: usize = 12;
+const TAG_LEN: usize = 16;
+const LENGTH_LEN: usize = 4;
+
+/// Slot capacities are rounded up to a multiple of this, so the container
+/// size only reveals a rough upper bound of the payload sizes.
+const SLOT_GRANULARITY: usize = 256;
+
+/// A payload together with the passphrase that unlocks it.
+#[derive(Debug, Clone, Copy)]
+pub struct Payload<'a> {
+    pub data: &'a [u8],
+    pub passphrase: &'a [u8],
+}
+
+/// Returns the slot capacity needed for payloads of up to `len` bytes.
+pub fn slot_capacity(len: usize) -> usize {
+    len.max(1).div_ceil(SLOT_GRANULARITY) * SLOT_GRANULARITY
+}
+
+/// Encrypts up to [`SLOTS`] payloads into a container.
+pub fn seal(payloads: &[Payload]) -> Result<Vec<u8>> {
+    if payloads.is_empty() || payloads.len() > SLOTS {
+        return Err(format!("A container holds between 1 and {} payloads", SLOTS).into());
+    }
+    for (i, payload) in payloads.iter().enumerate() {
+        if payload.passphrase.is_empty() {
+            return Err("Passphrases must not be empty".into());
+        }
+        if payloads[..i]
+            .iter()
+            .any(|p| p.passphrase == payload.passphrase)
+        {
+            return Err("Every payload needs a different passphrase".into());
+        }
+    }
+
+    let capacity = slot_capacity(payloads.iter().map(|p| p.data.len()).max().unwrap_or(0));
+    let slot_len = NONCE_LEN + LENGTH_LEN + capacity + TAG_LEN;
+    let mut rng = rand::rng();
+
+    let mut container = vec![0u8; SALT_LEN + SLOTS * slot_len];
+    rng.fill(&mut container[..]);
+    let salt = container[..SALT_LEN].to_vec();
+
+    let mut slots: Vec<usize> = (0..SLOTS).collect();
+    slots.shuffle(&mut rng);
+    for (payload, slot) in payloads.iter().zip(slots) {
+        let mut plaintext = vec![0u8; LENGTH_LEN + capacity];
+        rng.fill(&mut plaintext[..]);
+        plaintext[..LENGTH_LEN].copy_from_slice(&(payload.data.len() as u32).to_be_bytes());
+        plaintext[LENGTH_LEN..LENGTH_LEN + payload.data.len()].copy_from_slice(payload.data);
+
+        let start = SALT_LEN + slot * slot_len;
+        let nonce = &container[start..start + NONCE_LEN];
+        let ciphertext = cipher(payload.passphrase, &salt)?
+            .encrypt(Nonce::from_slice(nonce), plaintext.as_slice())
+            .map_err(|_| "Encryption failed")?;
+        container[start + NONCE_LEN..start + slot_len].copy_from_slice(&ciphertext);
+    }
+    Ok(container)
+}
+
+/// Decrypts whichever payload of a container the passphrase unlocks.
+pub fn open(container: &[u8], passphrase: &[u8]) -> Result<Vec<u8>> {
+    let body = container
+        .get(SALT_LEN..)
+        .filter(|body| !body.is_empty() && body.len() % SLOTS == 0)
+        .ok_or("Data is not an encrypted container")?;
+    let slot_len = body.len() / SLOTS;
+    if slot_len <= NONCE_LEN + LENGTH_LEN + TAG_LEN {
+        return Err("Data is not an encrypted container".into());
+    }
+
+    let cipher = cipher(passphrase, &container[..SALT_LEN])?;
+    for slot in body.chunks_exact(slot_len) {
+        let (nonce, ciphertext) = slot.split_at(NONCE_LEN);
+        if let Ok(plaintext) = cipher.decrypt(Nonce::from_slice(nonce), ciphertext) {
+            let length = u32::from_be_bytes(plaintext[..LENGTH_LEN].try_into()?) as usize;
+            return plaintext
+                .get(LENGTH_LEN..LENGTH_LEN + length)
+                .map(|data| data.to_vec())
+                .ok_or_else(|| "Encrypted payload is truncated".into());
+        }
+    }
+    Err("The passphrase does not unlock any payload".into())
+}
+
+/// Derives the slot cipher for a passphrase.
+fn cipher(passphrase: &[u8], salt: &[u8]) -> Result<ChaCha20Poly1305> {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(passphrase, salt, &mut key)
+        .map_err(|e| format!("Key derivation failed: {}", e))?;
+    Ok(ChaCha20Poly1305::new(Key::from_slice(&key)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn payload<'a>(data: &'a [u8], passphrase: &'a [u8]) -> Payload<'a> {
+        Payload { data, passphrase }
+    }
+
+    #[test]
+    fn test_each_passphrase_opens_its_payload() {
+        let container = seal(&[
+            payload(b"the real plans", b"correct horse"),
+            payload(b"grocery list", b"battery staple"),
+        ])
+        .unwrap();
+
+        assert_eq!(
+            open(&container, b"correct horse").unwrap(),
+            b"the real plans"
+        );
+        assert_eq!(
+            open(&container, b"battery staple").unwrap(),
+            b"grocery list"
+        );
+        assert!(open(&container, b"wrong").is_err());
+    }
+
+    #[test]
+    fn test_size_does_not_reveal_payload_count() {
+        let single = seal(&[payload(b"only one", b"a")]).unwrap();
+        let dual = seal(&[payload(b"first", b"a"), payload(b"second", b"b")]).unwrap();
+
+        assert_eq!(single.len(), dual.len());
+        assert_eq!(
+            single.len(),
+            SALT_LEN + SLOTS * (NONCE_LEN + LENGTH_LEN + 256 + TAG_LEN)
+        );
+        assert_eq!(open(&single, b"a").unwrap(), b"only one");
+    }
+
+    #[test]
+    fn test_invalid_payloads() {
+        assert!(seal(&[]).is_err());
+        assert!(seal(&[payload(b"x", b"same"), payload(b"y", b"same")]).is_err());
+        assert!(seal(&[payload(b"x", b"")]).is_err());
+        assert!(open(b"too short", b"a").is_err());
+    }
+
+    #[test]
+    fn test_slot_capacity() {
+        assert_eq!(slot_capacity(0), 256);
+        assert_eq!(slot_capacity(256), 256);
+        assert_eq!(slot_capacity(257), 512);
+    }
+}
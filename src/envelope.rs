@@ -0,0 +1,109 @@
+//! Framing for payloads hidden in carriers without a container structure.
+//!
+//! Chunk-based carriers know where their payload starts and ends. Carriers
+//! that spread bits over samples or characters do not, so their payload is
+//! sealed into an envelope: the `vEiL` magic, the payload length as a
+//! big-endian `u32`, then the payload itself.
+//!
+//! When the payload is a [`fec`] frame, its protected header is trusted over
+//! the envelope's length, so a few flipped bits in the envelope header do not
+//! lose a payload that could otherwise be corrected.
+
+use crate::{Result, fec};
+
+/// Marker written in front of every sealed payload.
+pub const MAGIC: [u8; 4] = *b"vEiL";
+
+/// Size of the magic and length prefix in bytes.
+pub const HEADER_LEN: usize = 8;
+
+/// Number of leading bytes [`message_length`] needs to recognise an FEC frame.
+pub const PROBE_LEN: usize = HEADER_LEN + fec::HEADER_LEN;
+
+/// Wraps a payload in an envelope.
+pub fn seal(data: &[u8]) -> Vec<u8> {
+    let mut message = Vec::with_capacity(HEADER_LEN + data.len());
+    message.extend_from_slice(&MAGIC);
+    message.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    message.extend_from_slice(data);
+    message
+}
+
+/// Validates an envelope header and returns the length of the payload that follows.
+pub fn payload_length(header: &[u8]) -> Result<usize> {
+    if header.len() < HEADER_LEN || header[..4] != MAGIC {
+        return Err("No hidden data found".into());
+    }
+    Ok(u32::from_be_bytes(header[4..8].try_into()?) as usize)
+}
+
+/// Returns the length of the payload of the envelope starting with `probe`,
+/// preferring the length of an FEC frame found after the header.
+pub fn message_length(probe: &[u8]) -> Result<usize> {
+    match probe.get(HEADER_LEN..).map(fec::frame_length) {
+        Some(Ok(length)) => Ok(length),
+        _ => payload_length(probe),
+    }
+}
+
+/// Unwraps an envelope, ignoring any bytes after the payload.
+pub fn open(message: &[u8]) -> Result<Vec<u8>> {
+    let length = message_length(message)?;
+    message
+        .get(HEADER_LEN..HEADER_LEN + length)
+        .map(|payload| payload.to_vec())
+        .ok_or_else(|| "Hidden data is truncated".into())
+}
+
+/// Iterates over the bits of the given bytes, most significant bit first.
+pub fn bits(bytes: &[u8]) -> impl Iterator<Item = u8> + '_ {
+    bytes
+        .iter()
+        .flat_map(|byte| (0..8).rev().map(move |i| (byte >> i) & 1))
+}
+
+/// Packs bits (most significant first) into bytes, dropping an incomplete last byte.
+pub fn pack_bits<I: IntoIterator<Item = u8>>(bits: I) -> Vec<u8> {
+    let bits: Vec<u8> = bits.into_iter().collect();
+    bits.chunks_exact(8)
+        .map(|chunk| chunk.iter().fold(0u8, |acc, bit| (acc << 1) | (bit & 1)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seal_open_roundtrip() {
+        let sealed = seal(b"payload");
+        assert_eq!(sealed.len(), HEADER_LEN + 7);
+        assert_eq!(open(&sealed).unwrap(), b"payload");
+    }
+
+    #[test]
+    fn test_open_rejects_bad_magic_and_truncation() {
+        let mut sealed = seal(b"payload");
+        assert!(open(&sealed[..10]).is_err());
+        sealed[0] = b'x';
+        assert!(open(&sealed).is_err());
+    }
+
+    #[test]
+    fn test_fec_frame_survives_damaged_header() {
+        let frame = fec::encode(b"payload", 8).unwrap();
+        let mut sealed = seal(&frame);
+        sealed[1] ^= 0x20;
+        sealed[6] ^= 0x01;
+        assert_eq!(message_length(&sealed).unwrap(), frame.len());
+        assert_eq!(open(&sealed).unwrap(), frame);
+    }
+
+    #[test]
+    fn test_bits_roundtrip() {
+        let bytes = [0b1010_0001, 0xff, 0];
+        let bits: Vec<u8> = bits(&bytes).collect();
+        assert_eq!(&bits[..8], &[1, 0, 1, 0, 0, 0, 0, 1]);
+        assert_eq!(pack_bits(bits), bytes);
+    }
+}
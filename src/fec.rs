@@ -0,0 +1,327 @@
+//! Reed-Solomon forward error correction for hidden payloads.
+//!
+//! A protected payload is split into equally sized blocks, and each block is
+//! extended with `parity` check bytes of a Reed-Solomon code over GF(2^8).
+//! A block can then recover from up to `parity / 2` damaged bytes. The bytes
+//! of all blocks are interleaved, so that a run of damaged bytes, such as a
+//! corrupted region of pixels, is spread over many blocks.
+//!
+//! The frame starts with its own header (magic, parity and payload length),
+//! which is protected by a fixed code of [`HEADER_PARITY`] bytes. This makes
+//! the frame self-delimiting even when the surrounding envelope is damaged.
+
+use crate::{Result, gf};
+
+/// Marker at the start of every frame header.
+pub const MAGIC: [u8; 4] = *b"vFEC";
+
+/// Number of check bytes protecting the frame header.
+pub const HEADER_PARITY: usize = 16;
+
+/// Size of the protected frame header in bytes.
+pub const HEADER_LEN: usize = 9 + HEADER_PARITY;
+
+/// Smallest and largest number of check bytes per block.
+pub const PARITY_RANGE: std::ops::RangeInclusive<usize> = 2..=128;
+
+/// Length of a full Reed-Solomon codeword over GF(2^8).
+const CODEWORD_LEN: usize = 255;
+
+/// A payload recovered from a frame.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Decoded {
+    pub data: Vec<u8>,
+    /// Number of bytes (code symbols) that had to be corrected.
+    pub corrected: usize,
+}
+
+/// Protects data with `parity` check bytes per block of at most `255 - parity` bytes.
+pub fn encode(data: &[u8], parity: usize) -> Result<Vec<u8>> {
+    if !PARITY_RANGE.contains(&parity) {
+        return Err(format!(
+            "FEC parity must be between {} and {} bytes per block",
+            PARITY_RANGE.start(),
+            PARITY_RANGE.end()
+        )
+        .into());
+    }
+    let length = u32::try_from(data.len()).map_err(|_| "Payload too large for FEC")?;
+
+    let mut header = MAGIC.to_vec();
+    header.push(parity as u8);
+    header.extend_from_slice(&length.to_be_bytes());
+    let mut frame = encode_block(&header, HEADER_PARITY);
+
+    let (blocks, block_len) = layout(data.len(), parity);
+    let codewords: Vec<Vec<u8>> = (0..blocks)
+        .map(|b| {
+            let mut block = vec![0u8; block_len];
+            let start = (b * block_len).min(data.len());
+            let end = ((b + 1) * block_len).min(data.len());
+            block[..end - start].copy_from_slice(&data[start..end]);
+            encode_block(&block, parity)
+        })
+        .collect();
+    for i in 0..block_len + parity {
+        frame.extend(codewords.iter().map(|codeword| codeword[i]));
+    }
+    Ok(frame)
+}
+
+/// Returns the total length of the frame starting with the given header.
+pub fn frame_length(header: &[u8]) -> Result<usize> {
+    let (parity, length, _) = read_header(header)?;
+    Ok(encoded_length(length, parity))
+}
+
+/// Returns the length of the frame that [`encode`] makes of `length` bytes.
+pub fn encoded_length(length: usize, parity: usize) -> usize {
+    let (blocks, block_len) = layout(length, parity);
+    HEADER_LEN + blocks * (block_len + parity)
+}
+
+/// Returns true if the data starts with a valid frame header.
+pub fn is_encoded(data: &[u8]) -> bool {
+    frame_length(data).is_ok()
+}
+
+/// Recovers the payload of a frame, correcting errors where possible.
+pub fn decode(frame: &[u8]) -> Result<Decoded> {
+    let (parity, length, mut corrected) = read_header(frame)?;
+    let (blocks, block_len) = layout(length, parity);
+    let codeword_len = block_len + parity;
+    let body = frame
+        .get(HEADER_LEN..HEADER_LEN + blocks * codeword_len)
+        .ok_or("FEC frame is truncated")?;
+
+    let mut data = Vec::with_capacity(blocks * block_len);
+    for b in 0..blocks {
+        let mut codeword: Vec<u8> = body.iter().skip(b).step_by(blocks).copied().collect();
+        corrected += decode_block(&mut codeword, parity)
+            .map_err(|e| format!("{} in FEC block {} of {}", e, b + 1, blocks))?;
+        data.extend_from_slice(&codeword[..block_len]);
+    }
+    data.truncate(length);
+    Ok(Decoded { data, corrected })
+}
+
+/// Decodes the frame header, returning the parity, payload length and corrections.
+fn read_header(frame: &[u8]) -> Result<(usize, usize, usize)> {
+    let mut header = frame
+        .get(..HEADER_LEN)
+        .ok_or("Data is too short for an FEC header")?
+        .to_vec();
+    let corrected = decode_block(&mut header, HEADER_PARITY)?;
+    if header[..4] != MAGIC {
+        return Err("No FEC header found".into());
+    }
+
+    let parity = header[4] as usize;
+    if !PARITY_RANGE.contains(&parity) {
+        return Err("Invalid FEC parity in header".into());
+    }
+    let length = u32::from_be_bytes(header[5..9].try_into()?) as usize;
+    Ok((parity, length, corrected))
+}
+
+/// Returns the number of blocks and the data bytes per block for a payload.
+fn layout(length: usize, parity: usize) -> (usize, usize) {
+    let blocks = length.div_ceil(CODEWORD_LEN - parity).max(1);
+    (blocks, length.div_ceil(blocks))
+}
+
+/// Appends `parity` check bytes to a block (systematic encoding).
+fn encode_block(data: &[u8], parity: usize) -> Vec<u8> {
+    let generator = generator(parity);
+    let mut codeword = data.to_vec();
+    codeword.resize(data.len() + parity, 0);
+
+    // Polynomial long division by the generator leaves the check bytes
+    for i in 0..data.len() {
+        let coefficient = codeword[i];
+        if coefficient != 0 {
+            for (j, &g) in generator.iter().enumerate().skip(1) {
+                codeword[i + j] ^= gf::mul(g, coefficient);
+            }
+        }
+    }
+    codeword[..data.len()].copy_from_slice(data);
+    codeword
+}
+
+/// Corrects a codeword in place and returns the number of corrected bytes.
+///
+/// Uses Berlekamp-Massey to find the error locator, a Chien search for the
+/// error positions and Forney's algorithm for their magnitudes.
+fn decode_block(codeword: &mut [u8], parity: usize) -> Result<usize> {
+    if codeword.len() > CODEWORD_LEN {
+        return Err("Reed-Solomon codeword too long".into());
+    }
+    let syndromes = syndromes(codeword, parity);
+    if syndromes.iter().all(|&s| s == 0) {
+        return Ok(0);
+    }
+
+    let locator = error_locator(&syndromes, parity)?;
+    let errors = locator.len() - 1;
+
+    // Chien search: roots of the reversed locator give the error positions
+    let reversed: Vec<u8> = locator.iter().rev().copied().collect();
+    let positions: Vec<usize> = (0..codeword.len())
+        .filter(|&i| gf::poly_eval(&reversed, gf::exp(i)) == 0)
+        .map(|i| codeword.len() - 1 - i)
+        .collect();
+    if positions.len() != errors {
+        return Err("Too many errors to correct".into());
+    }
+
+    correct(codeword, &syndromes, &positions);
+    if syndromes_of(codeword, parity).iter().any(|&s| s != 0) {
+        return Err("Too many errors to correct".into());
+    }
+    Ok(errors)
+}
+
+/// Returns the syndromes, padded with a leading zero as the decoder expects.
+fn syndromes(codeword: &[u8], parity: usize) -> Vec<u8> {
+    std::iter::once(0)
+        .chain(syndromes_of(codeword, parity))
+        .collect()
+}
+
+fn syndromes_of(codeword: &[u8], parity: usize) -> Vec<u8> {
+    (0..parity)
+        .map(|i| gf::poly_eval(codeword, gf::exp(i)))
+        .collect()
+}
+
+/// Finds the error locator polynomial with the Berlekamp-Massey algorithm.
+fn error_locator(syndromes: &[u8], parity: usize) -> Result<Vec<u8>> {
+    let mut locator = vec![1u8];
+    let mut previous = vec![1u8];
+    let shift = syndromes.len() - parity;
+
+    for i in 0..parity {
+        let k = i + shift;
+        let mut delta = syndromes[k];
+        for j in 1..locator.len() {
+            delta ^= gf::mul(locator[locator.len() - 1 - j], syndromes[k - j]);
+        }
+        previous.push(0);
+        if delta != 0 {
+            if previous.len() > locator.len() {
+                let next = gf::poly_scale(&previous, delta);
+                previous = gf::poly_scale(&locator, gf::inverse(delta));
+                locator = next;
+            }
+            locator = gf::poly_add(&locator, &gf::poly_scale(&previous, delta));
+        }
+    }
+
+    let leading = locator.iter().take_while(|&&c| c == 0).count();
+    locator.drain(..leading);
+    if locator.is_empty() || (locator.len() - 1) * 2 > parity {
+        return Err("Too many errors to correct".into());
+    }
+    Ok(locator)
+}
+
+/// Applies Forney's algorithm to fix the bytes at the given positions.
+fn correct(codeword: &mut [u8], syndromes: &[u8], positions: &[usize]) {
+    let powers: Vec<usize> = positions.iter().map(|&p| codeword.len() - 1 - p).collect();
+
+    // Locator built from the known positions, and the matching evaluator
+    let mut locator = vec![1u8];
+    for &power in &powers {
+        locator = gf::poly_mul(&locator, &[gf::exp(power), 1]);
+    }
+    let reversed_syndromes: Vec<u8> = syndromes.iter().rev().copied().collect();
+    let product = gf::poly_mul(&reversed_syndromes, &locator);
+    let evaluator: Vec<u8> = product[product.len() - locator.len()..].to_vec();
+
+    let roots: Vec<u8> = powers.iter().map(|&power| gf::exp(power)).collect();
+    for (i, &root) in roots.iter().enumerate() {
+        let inverse = gf::inverse(root);
+        let derivative = roots
+            .iter()
+            .enumerate()
+            .filter(|&(j, _)| j != i)
+            .fold(1, |acc, (_, &other)| {
+                gf::mul(acc, 1 ^ gf::mul(inverse, other))
+            });
+        let value = gf::mul(root, gf::poly_eval(&evaluator, inverse));
+        codeword[positions[i]] ^= gf::div(value, derivative);
+    }
+}
+
+/// Returns the generator polynomial with roots alpha^0 .. alpha^(parity - 1).
+fn generator(parity: usize) -> Vec<u8> {
+    (0..parity).fold(vec![1], |g, i| gf::poly_mul(&g, &[1, gf::exp(i)]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn payload(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i * 31 + 7) as u8).collect()
+    }
+
+    #[test]
+    fn test_roundtrip_without_errors() {
+        for len in [0, 1, 100, 239, 240, 1000] {
+            let frame = encode(&payload(len), 16).unwrap();
+            assert_eq!(frame.len(), frame_length(&frame).unwrap());
+            assert_eq!(frame.len(), encoded_length(len, 16));
+            let decoded = decode(&frame).unwrap();
+            assert_eq!(decoded.data, payload(len));
+            assert_eq!(decoded.corrected, 0);
+        }
+    }
+
+    #[test]
+    fn test_corrects_scattered_errors() {
+        let data = payload(600);
+        let mut frame = encode(&data, 20).unwrap();
+
+        // Three blocks of 200 bytes can each absorb 10 errors
+        for i in (HEADER_LEN..frame.len()).step_by(22).take(30) {
+            frame[i] ^= 0x5a;
+        }
+        frame[0] ^= 0xff;
+        frame[7] ^= 0x01;
+
+        let decoded = decode(&frame).unwrap();
+        assert_eq!(decoded.data, data);
+        assert_eq!(decoded.corrected, 32);
+    }
+
+    #[test]
+    fn test_interleaving_spreads_bursts() {
+        let data = payload(2000);
+        let mut frame = encode(&data, 8).unwrap();
+
+        // Nine blocks with 4 correctable errors each survive a 36-byte burst
+        for byte in &mut frame[HEADER_LEN + 500..HEADER_LEN + 536] {
+            *byte = !*byte;
+        }
+        assert_eq!(decode(&frame).unwrap().data, data);
+    }
+
+    #[test]
+    fn test_too_many_errors() {
+        let mut frame = encode(b"short payload", 4).unwrap();
+        for byte in &mut frame[HEADER_LEN..HEADER_LEN + 3] {
+            *byte ^= 0x10;
+        }
+        assert!(decode(&frame).is_err());
+    }
+
+    #[test]
+    fn test_invalid_parity_and_missing_header() {
+        assert!(encode(b"x", 1).is_err());
+        assert!(encode(b"x", 200).is_err());
+        assert!(!is_encoded(&[0u8; HEADER_LEN]));
+        assert!(!is_encoded(b"vEiL"));
+    }
+}
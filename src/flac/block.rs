@@ -0,0 +1,163 @@
+use crate::{Error, Result};
+use std::fmt;
+
+/// The type of a FLAC metadata block, as stored in the low seven bits of its header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockType {
+    StreamInfo,
+    Padding,
+    Application,
+    SeekTable,
+    VorbisComment,
+    CueSheet,
+    Picture,
+    /// Any type not defined by the specification (7..=126).
+    Reserved(u8),
+}
+
+impl BlockType {
+    /// Returns the numeric block type.
+    pub fn as_u8(&self) -> u8 {
+        match self {
+            BlockType::StreamInfo => 0,
+            BlockType::Padding => 1,
+            BlockType::Application => 2,
+            BlockType::SeekTable => 3,
+            BlockType::VorbisComment => 4,
+            BlockType::CueSheet => 5,
+            BlockType::Picture => 6,
+            BlockType::Reserved(value) => *value,
+        }
+    }
+}
+
+/// Implements conversion from the numeric block type, rejecting the invalid value 127.
+impl TryFrom<u8> for BlockType {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<Self> {
+        Ok(match value {
+            0 => BlockType::StreamInfo,
+            1 => BlockType::Padding,
+            2 => BlockType::Application,
+            3 => BlockType::SeekTable,
+            4 => BlockType::VorbisComment,
+            5 => BlockType::CueSheet,
+            6 => BlockType::Picture,
+            7..=126 => BlockType::Reserved(value),
+            _ => return Err("Invalid FLAC metadata block type".into()),
+        })
+    }
+}
+
+/// Represents a FLAC metadata block.
+///
+/// The "last block" flag is not stored; it is derived from the block's
+/// position when the stream is serialized.
+#[derive(Debug, Clone)]
+pub struct MetadataBlock {
+    block_type: BlockType,
+    data: Vec<u8>,
+}
+
+impl MetadataBlock {
+    /// Largest data length representable in the 24-bit length field.
+    pub const MAX_LENGTH: usize = (1 << 24) - 1;
+
+    /// Creates a new block with the given type and data.
+    pub fn new(block_type: BlockType, data: Vec<u8>) -> Result<Self> {
+        if data.len() > Self::MAX_LENGTH {
+            return Err("FLAC metadata block exceeds 16 MiB".into());
+        }
+        Ok(Self { block_type, data })
+    }
+
+    /// Creates an `APPLICATION` block with the given registered application ID.
+    pub fn application(id: [u8; 4], payload: &[u8]) -> Result<Self> {
+        let data = id.iter().chain(payload.iter()).copied().collect();
+        Self::new(BlockType::Application, data)
+    }
+
+    /// Creates a `PADDING` block of the given length.
+    pub fn padding(length: usize) -> Result<Self> {
+        Self::new(BlockType::Padding, vec![0; length])
+    }
+
+    /// Returns the type of the block.
+    pub fn block_type(&self) -> BlockType {
+        self.block_type
+    }
+
+    /// Returns the length of the block data.
+    pub fn length(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Returns a slice of the block data bytes.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Returns the application ID and payload of an `APPLICATION` block.
+    pub fn application_data(&self) -> Option<([u8; 4], &[u8])> {
+        if self.block_type != BlockType::Application || self.data.len() < 4 {
+            return None;
+        }
+        Some((self.data[..4].try_into().ok()?, &self.data[4..]))
+    }
+
+    /// Returns the number of bytes the block occupies when serialized.
+    pub fn size(&self) -> usize {
+        4 + self.data.len()
+    }
+
+    /// Serializes the block, setting the "last block" flag if requested.
+    pub fn as_bytes(&self, is_last: bool) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.size());
+        bytes.push(self.block_type.as_u8() | if is_last { 0x80 } else { 0 });
+        bytes.extend_from_slice(&(self.data.len() as u32).to_be_bytes()[1..]);
+        bytes.extend_from_slice(&self.data);
+        bytes
+    }
+}
+
+impl fmt::Display for MetadataBlock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} ({} bytes)", self.block_type, self.data.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_block_type_roundtrip() {
+        for value in 0..127 {
+            assert_eq!(BlockType::try_from(value).unwrap().as_u8(), value);
+        }
+        assert!(BlockType::try_from(127).is_err());
+    }
+
+    #[test]
+    fn test_block_as_bytes() {
+        let block = MetadataBlock::application(*b"vEiL", b"abc").unwrap();
+        assert_eq!(
+            block.as_bytes(true),
+            vec![0x82, 0, 0, 7, b'v', b'E', b'i', b'L', b'a', b'b', b'c']
+        );
+        assert_eq!(block.as_bytes(false)[0], 0x02);
+    }
+
+    #[test]
+    fn test_application_data() {
+        let block = MetadataBlock::application(*b"vEiL", b"abc").unwrap();
+        assert_eq!(block.application_data(), Some((*b"vEiL", &b"abc"[..])));
+        assert!(
+            MetadataBlock::padding(8)
+                .unwrap()
+                .application_data()
+                .is_none()
+        );
+    }
+}
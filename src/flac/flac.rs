@@ -0,0 +1,264 @@
+use crate::flac::{BlockType, MetadataBlock};
+use crate::{Error, Result};
+use std::fmt;
+
+/// Represents a FLAC stream as its metadata blocks followed by the raw audio frames.
+#[derive(Debug, Clone)]
+pub struct Flac {
+    blocks: Vec<MetadataBlock>,
+    frames: Vec<u8>,
+}
+
+impl Flac {
+    pub const MARKER: [u8; 4] = *b"fLaC";
+
+    /// Reads a FLAC stream from a file at the given path.
+    pub fn from_file<P: AsRef<std::path::Path>>(path: P) -> Result<Self> {
+        let data = std::fs::read(path)?;
+        Self::try_from(data.as_slice())
+    }
+
+    /// Returns a slice of all metadata blocks.
+    pub fn blocks(&self) -> &[MetadataBlock] {
+        &self.blocks
+    }
+
+    /// Returns the audio frames following the metadata.
+    pub fn frames(&self) -> &[u8] {
+        &self.frames
+    }
+
+    /// Returns the payload of the first `APPLICATION` block with the given ID.
+    pub fn application_data(&self, id: [u8; 4]) -> Option<&[u8]> {
+        self.blocks
+            .iter()
+            .filter_map(MetadataBlock::application_data)
+            .find(|(block_id, _)| *block_id == id)
+            .map(|(_, payload)| payload)
+    }
+
+    /// Stores a payload in an `APPLICATION` block with the given ID.
+    ///
+    /// Existing blocks with the same ID are turned into padding first. If a
+    /// `PADDING` block is large enough, the new block takes its place (and any
+    /// remainder stays padding), so the audio frames do not move; otherwise the
+    /// block is added after the last non-padding block.
+    pub fn set_application_data(&mut self, id: [u8; 4], payload: &[u8]) -> Result<()> {
+        for block in &mut self.blocks {
+            if block
+                .application_data()
+                .is_some_and(|(block_id, _)| block_id == id)
+            {
+                *block = MetadataBlock::padding(block.length())?;
+            }
+        }
+        self.merge_padding()?;
+
+        let block = MetadataBlock::application(id, payload)?;
+        let needed = block.size();
+
+        // A leftover smaller than a block header cannot be expressed as padding
+        let slot = self.blocks.iter().position(|b| {
+            b.block_type() == BlockType::Padding && (b.size() == needed || b.size() >= needed + 4)
+        });
+
+        match slot {
+            Some(index) => {
+                let leftover = self.blocks[index].size() - needed;
+                self.blocks[index] = block;
+                if leftover > 0 {
+                    self.blocks
+                        .insert(index + 1, MetadataBlock::padding(leftover - 4)?);
+                }
+            }
+            None => {
+                let index = self
+                    .blocks
+                    .iter()
+                    .rposition(|b| b.block_type() != BlockType::Padding)
+                    .map_or(self.blocks.len(), |i| i + 1);
+                self.blocks.insert(index, block);
+            }
+        }
+        Ok(())
+    }
+
+    /// Serializes the stream to a vector of bytes.
+    pub fn as_bytes(&self) -> Vec<u8> {
+        let mut result = Self::MARKER.to_vec();
+        let last = self.blocks.len().saturating_sub(1);
+        for (i, block) in self.blocks.iter().enumerate() {
+            result.extend(block.as_bytes(i == last));
+        }
+        result.extend_from_slice(&self.frames);
+        result
+    }
+
+    /// Writes the stream to a file at the given path.
+    pub fn to_file<P: AsRef<std::path::Path>>(&self, path: P) -> Result<()> {
+        std::fs::write(path, self.as_bytes())?;
+        Ok(())
+    }
+
+    /// Joins adjacent padding blocks into one.
+    fn merge_padding(&mut self) -> Result<()> {
+        let mut merged: Vec<MetadataBlock> = Vec::with_capacity(self.blocks.len());
+        for block in self.blocks.drain(..) {
+            match merged.last_mut() {
+                Some(previous)
+                    if previous.block_type() == BlockType::Padding
+                        && block.block_type() == BlockType::Padding =>
+                {
+                    *previous = MetadataBlock::padding(previous.size() + block.length())?;
+                }
+                _ => merged.push(block),
+            }
+        }
+        self.blocks = merged;
+        Ok(())
+    }
+}
+
+/// Implements conversion from a byte slice to a FLAC stream, parsing all metadata blocks.
+impl TryFrom<&[u8]> for Flac {
+    type Error = Error;
+
+    fn try_from(value: &[u8]) -> Result<Self> {
+        if value.len() < 4 || value[..4] != Self::MARKER {
+            return Err("Invalid FLAC marker".into());
+        }
+
+        let mut blocks = Vec::new();
+        let mut offset = 4;
+        loop {
+            let header = value
+                .get(offset..offset + 4)
+                .ok_or("FLAC metadata block header is truncated")?;
+            let is_last = header[0] & 0x80 != 0;
+            let block_type = BlockType::try_from(header[0] & 0x7f)?;
+            let length = u32::from_be_bytes([0, header[1], header[2], header[3]]) as usize;
+
+            let data = value
+                .get(offset + 4..offset + 4 + length)
+                .ok_or("FLAC metadata block is truncated")?;
+            blocks.push(MetadataBlock::new(block_type, data.to_vec())?);
+            offset += 4 + length;
+
+            if is_last {
+                break;
+            }
+        }
+
+        if blocks[0].block_type() != BlockType::StreamInfo {
+            return Err("FLAC stream must start with a STREAMINFO block".into());
+        }
+
+        Ok(Self {
+            blocks,
+            frames: value[offset..].to_vec(),
+        })
+    }
+}
+
+impl fmt::Display for Flac {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "FLAC {{")?;
+        writeln!(f, "  Blocks: {} blocks", self.blocks.len())?;
+        for (i, block) in self.blocks.iter().enumerate() {
+            writeln!(f, "    Block {}: {}", i, block)?;
+        }
+        writeln!(f, "  Frames: {} bytes", self.frames.len())?;
+        writeln!(f, "}}")?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRAMES: &[u8] = &[0xff, 0xf8, 0x69, 0x08, 0x00, 0x00];
+
+    fn testing_flac(padding: usize) -> Vec<u8> {
+        let mut bytes = Flac::MARKER.to_vec();
+        bytes.extend(
+            MetadataBlock::new(BlockType::StreamInfo, vec![0; 34])
+                .unwrap()
+                .as_bytes(false),
+        );
+        bytes.extend(
+            MetadataBlock::new(BlockType::VorbisComment, vec![1; 10])
+                .unwrap()
+                .as_bytes(false),
+        );
+        bytes.extend(MetadataBlock::padding(padding).unwrap().as_bytes(true));
+        bytes.extend_from_slice(FRAMES);
+        bytes
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let bytes = testing_flac(100);
+        let flac = Flac::try_from(bytes.as_slice()).unwrap();
+
+        assert_eq!(flac.blocks().len(), 3);
+        assert_eq!(flac.frames(), FRAMES);
+        assert_eq!(flac.as_bytes(), bytes);
+    }
+
+    #[test]
+    fn test_missing_streaminfo() {
+        let mut bytes = Flac::MARKER.to_vec();
+        bytes.extend(MetadataBlock::padding(4).unwrap().as_bytes(true));
+        assert!(Flac::try_from(bytes.as_slice()).is_err());
+    }
+
+    #[test]
+    fn test_payload_replaces_padding() {
+        let bytes = testing_flac(100);
+        let mut flac = Flac::try_from(bytes.as_slice()).unwrap();
+
+        flac.set_application_data(*b"vEiL", b"secret").unwrap();
+        let out = flac.as_bytes();
+
+        // The audio frames stay at the same offset
+        assert_eq!(out.len(), bytes.len());
+        assert_eq!(flac.blocks()[2].block_type(), BlockType::Application);
+        assert_eq!(flac.blocks()[3].block_type(), BlockType::Padding);
+
+        let parsed = Flac::try_from(out.as_slice()).unwrap();
+        assert_eq!(parsed.application_data(*b"vEiL"), Some(&b"secret"[..]));
+        assert_eq!(parsed.frames(), FRAMES);
+    }
+
+    #[test]
+    fn test_payload_without_enough_padding() {
+        let bytes = testing_flac(2);
+        let mut flac = Flac::try_from(bytes.as_slice()).unwrap();
+
+        flac.set_application_data(*b"vEiL", b"secret").unwrap();
+
+        assert_eq!(flac.blocks()[2].block_type(), BlockType::Application);
+        assert_eq!(flac.blocks()[3].block_type(), BlockType::Padding);
+        let parsed = Flac::try_from(flac.as_bytes().as_slice()).unwrap();
+        assert_eq!(parsed.application_data(*b"vEiL"), Some(&b"secret"[..]));
+    }
+
+    #[test]
+    fn test_payload_is_replaced() {
+        let bytes = testing_flac(0);
+        let mut flac = Flac::try_from(bytes.as_slice()).unwrap();
+
+        flac.set_application_data(*b"vEiL", b"first payload")
+            .unwrap();
+        flac.set_application_data(*b"vEiL", b"second").unwrap();
+
+        let applications = flac
+            .blocks()
+            .iter()
+            .filter(|b| b.block_type() == BlockType::Application)
+            .count();
+        assert_eq!(applications, 1);
+        assert_eq!(flac.application_data(*b"vEiL"), Some(&b"second"[..]));
+    }
+}
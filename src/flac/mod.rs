@@ -0,0 +1,6 @@
+mod block;
+#[allow(clippy::module_inception)]
+mod flac;
+
+pub use block::{BlockType, MetadataBlock};
+pub use flac::Flac;
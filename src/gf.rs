@@ -0,0 +1,100 @@
+//! Arithmetic in GF(2^8) with the primitive polynomial x^8 + x^4 + x^3 + x^2 + 1.
+//!
+//! Shared by the Reed-Solomon code in [`fec`](crate::fec) and the secret
+//! sharing in [`shamir`](crate::shamir). Addition is XOR.
+
+const PRIMITIVE: u16 = 0x11d;
+
+const TABLES: ([u8; 512], [u8; 256]) = {
+    let mut exp = [0u8; 512];
+    let mut log = [0u8; 256];
+    let mut x: u16 = 1;
+    let mut i = 0;
+    while i < 255 {
+        exp[i] = x as u8;
+        log[x as usize] = i as u8;
+        x <<= 1;
+        if x & 0x100 != 0 {
+            x ^= PRIMITIVE;
+        }
+        i += 1;
+    }
+    while i < 512 {
+        exp[i] = exp[i - 255];
+        i += 1;
+    }
+    (exp, log)
+};
+
+/// Returns alpha^power.
+pub fn exp(power: usize) -> u8 {
+    TABLES.0[power % 255]
+}
+
+pub fn mul(a: u8, b: u8) -> u8 {
+    if a == 0 || b == 0 {
+        return 0;
+    }
+    TABLES.0[TABLES.1[a as usize] as usize + TABLES.1[b as usize] as usize]
+}
+
+pub fn div(a: u8, b: u8) -> u8 {
+    if a == 0 {
+        return 0;
+    }
+    TABLES.0[(TABLES.1[a as usize] as usize + 255 - TABLES.1[b as usize] as usize) % 255]
+}
+
+pub fn inverse(a: u8) -> u8 {
+    div(1, a)
+}
+
+// Polynomials are stored with the highest-degree coefficient first
+
+pub fn poly_scale(p: &[u8], x: u8) -> Vec<u8> {
+    p.iter().map(|&c| mul(c, x)).collect()
+}
+
+pub fn poly_add(p: &[u8], q: &[u8]) -> Vec<u8> {
+    let len = p.len().max(q.len());
+    let mut sum = vec![0u8; len];
+    sum[len - p.len()..].copy_from_slice(p);
+    for (i, &c) in q.iter().enumerate() {
+        sum[len - q.len() + i] ^= c;
+    }
+    sum
+}
+
+pub fn poly_mul(p: &[u8], q: &[u8]) -> Vec<u8> {
+    let mut product = vec![0u8; p.len() + q.len() - 1];
+    for (i, &a) in p.iter().enumerate() {
+        for (j, &b) in q.iter().enumerate() {
+            product[i + j] ^= mul(a, b);
+        }
+    }
+    product
+}
+
+pub fn poly_eval(p: &[u8], x: u8) -> u8 {
+    p.iter().fold(0, |acc, &c| mul(acc, x) ^ c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_division_inverts_multiplication() {
+        for a in 1..=255u8 {
+            assert_eq!(mul(a, inverse(a)), 1);
+            assert_eq!(div(mul(a, 0x53), 0x53), a);
+        }
+    }
+
+    #[test]
+    fn test_poly_eval() {
+        // x^2 + 1 at x = 2 is 4 ^ 1
+        assert_eq!(poly_eval(&[1, 0, 1], 2), 5);
+        assert_eq!(poly_mul(&[1, 1], &[1, 1]), [1, 0, 1]);
+    }
+}
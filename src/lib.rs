@@ -6,7 +6,23 @@
 use std::str::FromStr;
 
 pub mod cmd;
+pub mod compare;
+pub mod deniable;
+pub mod envelope;
+pub mod fec;
+pub mod flac;
+mod gf;
+pub mod netpbm;
+pub mod ogg;
+pub mod pixels;
 pub mod png;
+pub mod polyglot;
+pub mod qoi;
+pub mod shamir;
+pub mod steganalysis;
+pub mod stripe;
+pub mod text;
+pub mod webp;
 
 pub use cmd::{Cli, Commands};
 
@@ -29,9 +45,15 @@ pub trait Steganography {
 }
 
 /// Enum representing different file formats that support steganography
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum SteganographyFile {
     Png(png::Png),
+    Webp(webp::Webp),
+    Netpbm(netpbm::Netpbm),
+    Qoi(qoi::Qoi),
+    Flac(flac::Flac),
+    Ogg(ogg::Ogg),
+    Text(text::Text),
     // Future formats will be added here:
     // Jpeg(jpeg::Jpeg),
     // Pdf(pdf::Pdf),
@@ -48,62 +70,192 @@ impl SteganographyFile {
                     let png = png::Png::from_file(path)?;
                     Ok(SteganographyFile::Png(png))
                 }
+                "webp" => {
+                    let webp = webp::Webp::from_file(path)?;
+                    Ok(SteganographyFile::Webp(webp))
+                }
+                "pbm" | "pgm" | "ppm" | "pnm" => {
+                    let netpbm = netpbm::Netpbm::from_file(path)?;
+                    Ok(SteganographyFile::Netpbm(netpbm))
+                }
+                "qoi" => {
+                    let qoi = qoi::Qoi::from_file(path)?;
+                    Ok(SteganographyFile::Qoi(qoi))
+                }
+                "flac" => {
+                    let flac = flac::Flac::from_file(path)?;
+                    Ok(SteganographyFile::Flac(flac))
+                }
+                "ogg" | "oga" | "opus" => {
+                    let ogg = ogg::Ogg::from_file(path)?;
+                    Ok(SteganographyFile::Ogg(ogg))
+                }
+                "txt" | "text" | "md" => {
+                    let text = text::Text::from_file(path)?;
+                    Ok(SteganographyFile::Text(text))
+                }
                 _ => Err(format!("Unsupported file format: {:?}", extension).into()),
             }
         } else {
             Err("Could not determine file format - no extension found".into())
         }
     }
+
+    /// Decodes the pixels of any image format, for analysis.
+    ///
+    /// Returns `None` for formats without a raster. PNG images are decoded
+    /// from their image data; indexed PNGs yield palette indices.
+    pub fn decode_pixels(&self) -> Result<Option<pixels::PixelBuffer>> {
+        match self {
+            SteganographyFile::Png(png) => png::raster::decode(png).map(Some),
+            _ => Ok(self.pixels().cloned()),
+        }
+    }
+
+    /// Decodes the pixels of any image format as they are displayed, for comparison.
+    ///
+    /// Like [`Self::decode_pixels`], but indexed PNGs yield RGBA colors.
+    pub fn decode_colors(&self) -> Result<Option<pixels::PixelBuffer>> {
+        match self {
+            SteganographyFile::Png(png) => png::raster::decode_colors(png).map(Some),
+            _ => Ok(self.pixels().cloned()),
+        }
+    }
+
+    /// Returns the bytes appended after the end of the file format, for formats
+    /// that can tell them apart from the file itself.
+    pub fn trailing_data(&self) -> Option<&[u8]> {
+        match self {
+            SteganographyFile::Png(png) => Some(png.trailing_data()),
+            _ => None,
+        }
+    }
+
+    /// Returns the decoded pixels of formats that embed in the pixel domain.
+    pub fn pixels(&self) -> Option<&pixels::PixelBuffer> {
+        match self {
+            SteganographyFile::Netpbm(netpbm) => Some(netpbm.pixels()),
+            SteganographyFile::Qoi(qoi) => Some(qoi.pixels()),
+            _ => None,
+        }
+    }
+
+    /// Returns the decoded pixels of formats that embed in the pixel domain, mutably.
+    pub fn pixels_mut(&mut self) -> Option<&mut pixels::PixelBuffer> {
+        match self {
+            SteganographyFile::Netpbm(netpbm) => Some(netpbm.pixels_mut()),
+            SteganographyFile::Qoi(qoi) => Some(qoi.pixels_mut()),
+            _ => None,
+        }
+    }
 }
 
 impl Steganography for SteganographyFile {
     fn hide_data(&mut self, data: &[u8]) -> Result<()> {
         match self {
             SteganographyFile::Png(png) => {
-                let chunk_type = png::ChunkType::from_str("vEiL")?;
-                let chunk = png::Chunk::new(chunk_type, data.to_vec());
+                let chunk_type = png::ChunkType::from_str(png::payload::DEFAULT_CHUNK_TYPE)?;
+                png::payload::hide(png, data, chunk_type)
+            }
+            SteganographyFile::Webp(webp) => {
+                let fourcc = webp::FourCC::from_str("vEiL")?;
+                let chunk = webp::Chunk::new(fourcc, data.to_vec());
 
-                png.append_chunk(chunk);
+                // Extra chunks are only allowed in the extended (VP8X) layout
+                webp.ensure_extended()?;
+                while webp.remove_first_chunk("vEiL").is_ok() {}
+                webp.append_chunk(chunk);
                 Ok(())
             }
+            SteganographyFile::Netpbm(_) | SteganographyFile::Qoi(_) => {
+                let pixels = self.pixels_mut().ok_or("No pixels to embed into")?;
+                pixels::lsb::embed(pixels, data, pixels::lsb::LsbMode::default())?;
+                Ok(())
+            }
+            SteganographyFile::Flac(flac) => flac.set_application_data(*b"vEiL", data),
+            SteganographyFile::Ogg(ogg) => {
+                let mut comments = ogg.comments()?;
+                comments.set_binary("VEIL", data);
+                ogg.set_comments(&comments)
+            }
+            SteganographyFile::Text(text) => text.hide(data),
         }
     }
 
     fn extract_data(&self) -> Result<Vec<u8>> {
         match self {
             SteganographyFile::Png(png) => {
-                let custom_chunks = png.custom_chunks();
+                let chunk_type = png::ChunkType::from_str(png::payload::DEFAULT_CHUNK_TYPE)?;
+                let legacy = png::ChunkType::from_str(png::payload::LEGACY_CHUNK_TYPE)?;
 
-                let veil_chunks: Vec<_> = custom_chunks
-                    .into_iter()
-                    .filter(|chunk| chunk.chunk_type().to_string() == "vEiL")
-                    .collect();
-
-                if veil_chunks.is_empty() {
-                    return Err("No hidden data found".into());
+                match png::payload::extract(png, &chunk_type)
+                    .or_else(|| png::payload::extract(png, &legacy))
+                {
+                    Some(data) => Ok(data.to_vec()),
+                    // Fall back to data hidden in the image data stream, palette or pixels
+                    None => png::idat::extract(png)
+                        .or_else(|_| png::palette::extract(png))
+                        .or_else(|_| png::alpha::extract(png))
+                        .map_err(|_| "No hidden data found".into()),
                 }
-
-                // TODO: For now, extract data from the first vEiL chunk
-                // In the future, we could concatenate multiple chunks
-                let data = veil_chunks[0].data().to_vec();
-                Ok(data)
             }
+            SteganographyFile::Webp(webp) => webp
+                .chunk_by_fourcc("vEiL")
+                .map(|chunk| chunk.data().to_vec())
+                .ok_or_else(|| "No hidden data found".into()),
+            SteganographyFile::Netpbm(_) | SteganographyFile::Qoi(_) => {
+                // Fall back to adaptive embedding without a key
+                let pixels = self.pixels().ok_or("No pixels to extract from")?;
+                pixels::lsb::extract(pixels)
+                    .or_else(|_| pixels::adaptive::extract(pixels, b""))
+                    .map_err(|_| "No hidden data found".into())
+            }
+            SteganographyFile::Flac(flac) => flac
+                .application_data(*b"vEiL")
+                .map(|data| data.to_vec())
+                .ok_or_else(|| "No hidden data found".into()),
+            SteganographyFile::Ogg(ogg) => ogg
+                .comments()?
+                .binary("VEIL")
+                .ok_or_else(|| "No hidden data found".into()),
+            SteganographyFile::Text(text) => text.extract(),
         }
     }
 
     fn has_hidden_data(&self) -> bool {
         match self {
             SteganographyFile::Png(png) => {
-                png.custom_chunks()
-                    .iter()
-                    .any(|chunk| chunk.chunk_type().to_string() == "vEiL")
+                png.chunk_by_type(png::payload::DEFAULT_CHUNK_TYPE)
+                    .or_else(|| png.chunk_by_type(png::payload::LEGACY_CHUNK_TYPE))
+                    .is_some()
+                    || png::idat::has_hidden_data(png)
+                    || png::palette::has_hidden_data(png)
+                    || png::alpha::has_hidden_data(png)
             }
+            SteganographyFile::Webp(webp) => webp.chunk_by_fourcc("vEiL").is_some(),
+            SteganographyFile::Netpbm(_) | SteganographyFile::Qoi(_) => {
+                self.pixels().is_some_and(|pixels| {
+                    pixels::lsb::has_hidden_data(pixels)
+                        || pixels::adaptive::has_hidden_data(pixels, b"")
+                })
+            }
+            SteganographyFile::Flac(flac) => flac.application_data(*b"vEiL").is_some(),
+            SteganographyFile::Ogg(ogg) => ogg
+                .comments()
+                .is_ok_and(|comments| comments.binary("VEIL").is_some()),
+            SteganographyFile::Text(text) => text.extract().is_ok(),
         }
     }
 
     fn save_to_file<P: AsRef<std::path::Path>>(&self, path: P) -> Result<()> {
         match self {
             SteganographyFile::Png(png) => png.to_file(path),
+            SteganographyFile::Webp(webp) => webp.to_file(path),
+            SteganographyFile::Netpbm(netpbm) => netpbm.to_file(path),
+            SteganographyFile::Qoi(qoi) => qoi.to_file(path),
+            SteganographyFile::Flac(flac) => flac.to_file(path),
+            SteganographyFile::Ogg(ogg) => ogg.to_file(path),
+            SteganographyFile::Text(text) => text.to_file(path),
         }
     }
 }
@@ -132,6 +284,74 @@ mod tests {
 
         let extracted = stego_file.extract_data().unwrap();
         assert_eq!(extracted, secret_message);
+
+        let SteganographyFile::Png(png) = stego_file else {
+            unreachable!()
+        };
+        let reloaded = png::Png::try_from(png.as_bytes().as_slice()).unwrap();
+        assert_eq!(
+            reloaded.chunks().last().unwrap().chunk_type().to_string(),
+            "IEND"
+        );
+        let reloaded = SteganographyFile::Png(reloaded);
+        assert_eq!(reloaded.extract_data().unwrap(), secret_message);
+    }
+
+    #[test]
+    fn test_fec_repairs_damaged_chunk() {
+        let chunks = vec![
+            test_chunk("IHDR", b"fake header data"),
+            test_chunk("IDAT", b"fake image data"),
+            test_chunk("IEND", b""),
+        ];
+        let mut stego_file = SteganographyFile::Png(png::Png::from_chunks(chunks));
+        let secret_message = b"This is a secret message!";
+        stego_file
+            .hide_data(&fec::encode(secret_message, 16).unwrap())
+            .unwrap();
+
+        let SteganographyFile::Png(png) = stego_file else {
+            unreachable!()
+        };
+        let mut bytes = png.as_bytes();
+        let payload = png.chunk_offsets()[2] + 8;
+        bytes[payload + 10] ^= 0x55;
+
+        let damaged = png::Png::try_from(bytes.as_slice()).unwrap();
+        assert_eq!(damaged.damaged_chunks().len(), 1);
+        let extracted = SteganographyFile::Png(damaged).extract_data().unwrap();
+        let decoded = fec::decode(&extracted).unwrap();
+        assert_eq!(decoded.data, secret_message);
+        assert_eq!(decoded.corrected, 1);
+    }
+
+    #[test]
+    fn test_legacy_layout_extracts() {
+        // Files made by older versions carry the payload chunk after IEND
+        let chunks = vec![
+            test_chunk("IHDR", b"fake header data"),
+            test_chunk("IDAT", b"fake image data"),
+            test_chunk("IEND", b""),
+            test_chunk("vEiL", b"legacy secret"),
+        ];
+        let bytes = png::Png::from_chunks(chunks).as_bytes();
+
+        let legacy = SteganographyFile::Png(png::Png::try_from(bytes.as_slice()).unwrap());
+        assert!(legacy.has_hidden_data());
+        assert_eq!(legacy.extract_data().unwrap(), b"legacy secret");
+    }
+
+    #[test]
+    fn test_chunk_output_passes_validation() {
+        let samples = (0..4 * 4 * 3).map(|i| (i * 5 % 256) as u16).collect();
+        let pixels = pixels::PixelBuffer::new(4, 4, 3, 255, samples).unwrap();
+        let mut stego_file = SteganographyFile::Png(png::raster::to_png(&pixels).unwrap());
+        stego_file.hide_data(b"secret").unwrap();
+
+        let SteganographyFile::Png(png) = stego_file else {
+            unreachable!()
+        };
+        assert_eq!(png.validate(), []);
     }
 
     #[test]
@@ -144,6 +364,51 @@ mod tests {
         assert!(!stego_file.has_hidden_data());
     }
 
+    #[test]
+    fn test_webp_steganography_end_to_end() {
+        let vp8l = webp::Chunk::new(
+            webp::FourCC::from_str("VP8L").unwrap(),
+            vec![47, 0, 0, 0, 16, 7, 16, 17, 17, 136, 136, 254, 7],
+        );
+        let mut stego_file = SteganographyFile::Webp(webp::Webp::from_chunks(vec![vp8l]));
+
+        assert!(!stego_file.has_hidden_data());
+
+        stego_file.hide_data(b"secret").unwrap();
+
+        match &stego_file {
+            SteganographyFile::Webp(webp) => {
+                assert_eq!(webp.kind().unwrap(), webp::WebpKind::Extended)
+            }
+            _ => unreachable!(),
+        }
+        assert_eq!(stego_file.extract_data().unwrap(), b"secret");
+
+        // Hiding again replaces the earlier payload
+        stego_file.hide_data(b"second").unwrap();
+        assert_eq!(stego_file.extract_data().unwrap(), b"second");
+    }
+
+    #[test]
+    fn test_pixel_carriers_end_to_end() {
+        let pixels = pixels::PixelBuffer::new(16, 16, 3, 255, vec![200; 768]).unwrap();
+        let carriers = vec![
+            SteganographyFile::Netpbm(
+                netpbm::Netpbm::from_pixels(netpbm::NetpbmFormat::Pixmap, pixels.clone()).unwrap(),
+            ),
+            SteganographyFile::Qoi(qoi::Qoi::from_pixels(pixels, 0).unwrap()),
+        ];
+
+        for mut stego_file in carriers {
+            assert!(!stego_file.has_hidden_data());
+
+            stego_file.hide_data(b"pixel secret").unwrap();
+
+            assert!(stego_file.has_hidden_data());
+            assert_eq!(stego_file.extract_data().unwrap(), b"pixel secret");
+        }
+    }
+
     fn test_chunk(chunk_type: &str, data: &[u8]) -> png::Chunk {
         use std::str::FromStr;
         let chunk_type = png::ChunkType::from_str(chunk_type).unwrap();
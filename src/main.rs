@@ -1,74 +1,616 @@
 use clap::Parser;
+use std::str::FromStr;
 use std::{io::Read, path::Path};
-use veil::{Cli, Commands, Steganography, SteganographyFile};
+use veil::envelope;
+use veil::pixels::PixelBuffer;
+use veil::pixels::adaptive;
+use veil::pixels::lsb::{self, EmbedStats, LsbMode};
+use veil::pixels::planes::{self, Plane};
+use veil::png::alpha::{self, AlphaMode};
+use veil::png::diff::{self, ChangeKind};
+use veil::png::idat::{self, IdatMode};
+use veil::png::validate::{self, Severity};
+use veil::png::{ChunkType, palette, payload, raster};
+use veil::polyglot::{self, Polyglot, ZipLocation, zip};
+use veil::steganalysis::signatures::{Scanner, Target};
+use veil::steganalysis::{self, ReportFormat};
+use veil::{
+    Cli, Commands, Steganography, SteganographyFile, compare, deniable, fec, shamir, stripe,
+};
 
 fn main() -> veil::Result<()> {
     let args = Cli::parse();
 
     match args.command {
-        Commands::Check { file_path } => {
+        Commands::Check {
+            file_path,
+            analyze,
+            format,
+        } => {
             // Load the file and check if it contains hidden data
             let file = SteganographyFile::from_file(&file_path)?;
 
+            // A JSON report stands alone so that it can be piped into other tools
+            if analyze && format == ReportFormat::Json {
+                let pixels = file
+                    .decode_pixels()?
+                    .ok_or("Analysis needs an image with decoded pixels")?;
+                println!("{}", steganalysis::analyze(&pixels).to_json());
+                return Ok(());
+            }
+
             if file.has_hidden_data() {
                 println!("✓ Hidden data found in {}", file_path);
             } else {
                 println!("✗ No hidden data found in {}", file_path);
             }
+
+            // A ZIP archive may be merged into the file regardless of payload chunks
+            let bytes = std::fs::read(&file_path)?;
+            if let Some(report) = polyglot::detect_zip(&bytes) {
+                let location = match &report.location {
+                    ZipLocation::Chunk(chunk_type) => format!("inside the '{}' chunk", chunk_type),
+                    ZipLocation::Trailing => "after the end of the image".to_string(),
+                    ZipLocation::Unknown => "at an unexpected position".to_string(),
+                };
+                println!("⚠ ZIP polyglot detected {}", location);
+                println!("  Entries: {}", report.archive.names.join(", "));
+                if !report.archive.offsets_valid {
+                    println!("  Offsets are not adjusted for this file; some unzip tools may fail");
+                }
+            }
+
+            // Data appended after IEND is ignored by viewers but easy to find
+            if let Some(trailing) = file.trailing_data().filter(|data| !data.is_empty()) {
+                println!(
+                    "⚠ {} bytes of trailing data after the end of the image",
+                    trailing.len()
+                );
+                println!("  Extract it with --trailing");
+            }
+
+            // Text may carry invisible content from other tools we cannot decode
+            if let SteganographyFile::Text(text) = &file {
+                let report = text.analyze();
+                if report.is_suspicious() {
+                    println!("⚠ Suspicious invisible content:");
+                    println!("  Zero-width characters: {}", report.zero_width_chars);
+                    println!(
+                        "  Lines with trailing whitespace: {}",
+                        report.trailing_whitespace_lines
+                    );
+                }
+            }
+
+            // Other tools leave footprints in chunks, text keywords and bit planes
+            let pixels = file.decode_pixels().ok().flatten();
+            let png = match &file {
+                SteganographyFile::Png(png) => Some(png),
+                _ => None,
+            };
+            let verdict = Scanner::default().scan(&Target {
+                bytes: &bytes,
+                png,
+                pixels: pixels.as_ref(),
+            });
+            println!("{}", verdict.to_text());
+
+            // Statistical analysis also finds LSB data hidden by other tools
+            if analyze {
+                match pixels {
+                    Some(pixels) => println!("{}", steganalysis::analyze(&pixels).to_text()),
+                    None => println!("  Analysis needs an image with decoded pixels"),
+                }
+            }
         }
 
         Commands::Hide {
-            file_path,
-            data_path,
+            file_paths,
+            data_paths,
             message,
             output_path,
+            text_mode,
+            polyglot,
+            idat,
+            palette,
+            alpha,
+            lsb_mode,
+            chunk_type,
+            adaptive,
+            key,
+            fec,
+            shares,
+            threshold,
+            stripe,
+            passphrase,
+            decoy,
+            decoy_passphrase,
+            metrics,
         } => {
-            // Load the host file
-            let mut file = SteganographyFile::from_file(&file_path)?;
-
-            // Determine what data to hide (either from file, message or stdin)
-            let data_to_hide = if let Some(data_path) = data_path {
-                // Hide data from a file
-                std::fs::read(&data_path)?
+            // Determine what data to hide (either from files, message or stdin)
+            let inputs: Vec<zip::Entry> = if !data_paths.is_empty() {
+                // Hide data from files
+                data_paths
+                    .iter()
+                    .map(|data_path| {
+                        let name = Path::new(data_path)
+                            .file_name()
+                            .map_or("data.bin".into(), |n| n.to_string_lossy().into_owned());
+                        Ok(zip::Entry {
+                            name,
+                            data: std::fs::read(data_path)?,
+                        })
+                    })
+                    .collect::<veil::Result<_>>()?
             } else if let Some(message) = message {
                 // Hide a text message
-                message.into_bytes()
+                vec![zip::Entry {
+                    name: "message.txt".into(),
+                    data: message.into_bytes(),
+                }]
             } else {
                 let mut buffer = Vec::new();
                 std::io::stdin().read_to_end(&mut buffer)?;
-                buffer
+                vec![zip::Entry {
+                    name: "data.bin".into(),
+                    data: buffer,
+                }]
             };
+            let hidden_bytes: usize = inputs.iter().map(|entry| entry.data.len()).sum();
 
-            // Hide the data
-            file.hide_data(&data_to_hide)?;
+            // Load the host files
+            let mut files = file_paths
+                .iter()
+                .map(|file_path| {
+                    let mut file = SteganographyFile::from_file(file_path)?;
+                    if let SteganographyFile::Text(text) = &mut file {
+                        text.set_mode(text_mode);
+                    }
+                    Ok(file)
+                })
+                .collect::<veil::Result<Vec<_>>>()?;
+            let options = HideOptions {
+                idat,
+                palette,
+                alpha,
+                lsb_mode,
+                chunk_type: chunk_type.as_deref().map(payload::chunk_type).transpose()?,
+                adaptive,
+                key,
+            };
 
-            // Determine output path (use input path with "_hidden" suffix if not provided)
-            let output_file = match output_path {
-                Some(path) => path,
-                None => {
-                    let input_path = Path::new(&file_path);
-                    let stem = input_path.file_stem().unwrap().to_string_lossy();
-                    let extension = input_path.extension().unwrap().to_string_lossy();
-                    format!("{}_hidden.{}", stem, extension)
+            // Decide what goes into each carrier: the data itself, a share or a stripe of it
+            let payloads: Vec<Vec<u8>> = match (polyglot, shares) {
+                (Some(_), _) => vec![Vec::new()],
+                (None, shares) => {
+                    let [input] = inputs.as_slice() else {
+                        return Err("Hiding several files requires --polyglot zip".into());
+                    };
+
+                    // Encrypt first, next to an optional decoy, so shares and stripes hold ciphertext
+                    let data = match &passphrase {
+                        Some(passphrase) => {
+                            let decoy_data = decoy.as_ref().map(std::fs::read).transpose()?;
+                            let mut payloads = vec![deniable::Payload {
+                                data: &input.data,
+                                passphrase: passphrase.as_bytes(),
+                            }];
+                            if let (Some(data), Some(passphrase)) = (&decoy_data, &decoy_passphrase)
+                            {
+                                payloads.push(deniable::Payload {
+                                    data,
+                                    passphrase: passphrase.as_bytes(),
+                                });
+                            }
+                            deniable::seal(&payloads)?
+                        }
+                        None => input.data.clone(),
+                    };
+
+                    match shares {
+                        Some(count) => {
+                            if file_paths.len() != count as usize {
+                                return Err(format!(
+                                    "--shares {} needs {} carrier files, got {}",
+                                    count,
+                                    count,
+                                    file_paths.len()
+                                )
+                                .into());
+                            }
+                            shamir::split(&data, count, threshold.unwrap_or(count))?
+                                .iter()
+                                .map(shamir::Share::to_bytes)
+                                .collect()
+                        }
+                        None if stripe => {
+                            // Stripes follow the carriers' capacities when all of them are bounded
+                            let capacities: Option<Vec<usize>> = files
+                                .iter()
+                                .map(|file| {
+                                    capacity(file, &options)
+                                        .map(|capacity| stripe_capacity(capacity, fec))
+                                })
+                                .collect();
+                            let weights = capacities.unwrap_or_else(|| vec![1; files.len()]);
+                            stripe::split(&data, &weights)?
+                                .iter()
+                                .map(stripe::Stripe::to_bytes)
+                                .collect()
+                        }
+                        None => vec![data],
+                    }
                 }
             };
+            if payloads.len() != file_paths.len() {
+                return Err("Hiding in several carrier files requires --shares or --stripe".into());
+            }
 
-            // Save to output file
-            file.save_to_file(&output_file)?;
+            // Embed into every carrier before writing any, so that one that is too
+            // small does not leave the others' outputs behind
+            let mut reports = Vec::with_capacity(files.len());
+            for (file, payload) in files.iter_mut().zip(&payloads) {
+                // Keep the untouched carrier, the output may overwrite the input file
+                let cover = metrics.then(|| file.clone());
+
+                // Hide the data
+                let report = match (polyglot, &mut *file) {
+                    (Some(Polyglot::Zip), SteganographyFile::Png(png)) => {
+                        polyglot::embed_zip(png, &inputs)?;
+                        HideReport::default()
+                    }
+                    (Some(Polyglot::Zip), _) => {
+                        return Err("ZIP polyglots are only supported for PNG files".into());
+                    }
+                    (None, _) => {
+                        let payload = match fec {
+                            Some(parity) => fec::encode(payload, parity as usize)?,
+                            None => payload.clone(),
+                        };
+                        hide(file, &payload, &options)?
+                    }
+                };
+                reports.push((report, cover));
+            }
 
-            println!("✓ Data hidden successfully in {}", output_file);
-            println!("  Hidden {} bytes", data_to_hide.len());
+            let output_files = output_files(&file_paths, output_path.as_deref())?;
+            for (i, (((output_file, file), payload), (report, cover))) in output_files
+                .iter()
+                .zip(&files)
+                .zip(&payloads)
+                .zip(&reports)
+                .enumerate()
+            {
+                // Save to output file
+                file.save_to_file(output_file)?;
+
+                println!("✓ Data hidden successfully in {}", output_file);
+                match shares {
+                    None if stripe => println!(
+                        "  Stripe {} of {} ({} of {} bytes)",
+                        i + 1,
+                        payloads.len(),
+                        payload.len() - stripe::HEADER_LEN,
+                        hidden_bytes
+                    ),
+                    Some(count) => println!(
+                        "  Share {} of {} ({} needed) of {} bytes",
+                        i + 1,
+                        count,
+                        threshold.unwrap_or(count),
+                        hidden_bytes
+                    ),
+                    None => println!("  Hidden {} bytes", hidden_bytes),
+                }
+                if passphrase.is_some() {
+                    println!(
+                        "  Encrypted into a container of {} fixed-size slots",
+                        deniable::SLOTS
+                    );
+                }
+                if let Some(parity) = fec {
+                    println!(
+                        "  Reed-Solomon FEC: {} check bytes per block, corrects up to {} per block",
+                        parity,
+                        parity / 2
+                    );
+                }
+                report.print();
+
+                // Compare with the untouched carrier
+                if let Some(cover) = &cover {
+                    for line in compare::compare(cover, file)?.to_text().lines() {
+                        println!("  {}", line);
+                    }
+                }
+            }
         }
 
-        Commands::Extract {
+        Commands::Compare {
+            cover_path,
+            stego_path,
+        } => {
+            let cover = SteganographyFile::from_file(&cover_path)?;
+            let stego = SteganographyFile::from_file(&stego_path)?;
+            println!("{}", compare::compare(&cover, &stego)?.to_text());
+        }
+
+        Commands::Diff {
+            old_path,
+            new_path,
+            hex,
+        } => {
+            let (SteganographyFile::Png(old), SteganographyFile::Png(new)) = (
+                SteganographyFile::from_file(&old_path)?,
+                SteganographyFile::from_file(&new_path)?,
+            ) else {
+                return Err("Diffing is only supported for PNG files".into());
+            };
+
+            let changes = diff::diff(&old, &new);
+            for change in &changes {
+                println!("{}", change.to_text());
+                if let (true, ChangeKind::Modified, Some(i), Some(j)) =
+                    (hex, change.kind, change.old_index, change.new_index)
+                {
+                    let lines = diff::hex_diff(old.chunks()[i].data(), new.chunks()[j].data());
+                    for line in lines {
+                        println!("    {}", line);
+                    }
+                }
+            }
+            if old.trailing_data() != new.trailing_data() {
+                println!(
+                    "~ after IEND ({} -> {} bytes)",
+                    old.trailing_data().len(),
+                    new.trailing_data().len()
+                );
+            }
+
+            let count = |kind| changes.iter().filter(|c| c.kind == kind).count();
+            println!(
+                "\n{} added, {} removed, {} reordered, {} modified",
+                count(ChangeKind::Added),
+                count(ChangeKind::Removed),
+                count(ChangeKind::Reordered),
+                count(ChangeKind::Modified)
+            );
+        }
+
+        Commands::Inspect { file_path, stats } => {
+            let SteganographyFile::Png(png) = SteganographyFile::from_file(&file_path)? else {
+                return Err("Inspecting is only supported for PNG files".into());
+            };
+
+            if stats {
+                println!("{}", steganalysis::stats::analyze(&png).to_text());
+                return Ok(());
+            }
+
+            println!(
+                "{:>8}  {:<4} {:>9}  {:<8}  Properties",
+                "Offset", "Type", "Length", "CRC"
+            );
+            let mut offset = png.header().len();
+            for chunk in png.chunks() {
+                let chunk_type = chunk.chunk_type();
+                let properties = [
+                    if chunk_type.is_critical() {
+                        "critical"
+                    } else {
+                        "ancillary"
+                    },
+                    if chunk_type.is_public() {
+                        "public"
+                    } else {
+                        "private"
+                    },
+                    if chunk_type.is_safe_to_copy() {
+                        "safe to copy"
+                    } else {
+                        "unsafe to copy"
+                    },
+                ];
+                println!(
+                    "{:>8}  {} {:>9}  {:08x}  {}",
+                    offset,
+                    chunk_type,
+                    chunk.length(),
+                    chunk.crc(),
+                    properties.join(", ")
+                );
+                offset += chunk.as_bytes().len();
+            }
+            if !png.trailing_data().is_empty() {
+                println!(
+                    "{:>8}  {} bytes after IEND",
+                    offset,
+                    png.trailing_data().len()
+                );
+            }
+        }
+
+        Commands::Lint { file_path } => {
+            let SteganographyFile::Png(png) = SteganographyFile::from_file(&file_path)? else {
+                return Err("Linting is only supported for PNG files".into());
+            };
+
+            let violations = png.validate();
+            if violations.is_empty() {
+                println!("✓ No violations of the PNG specification");
+                return Ok(());
+            }
+            println!("{}", validate::to_text(&png, &violations));
+
+            let errors = violations
+                .iter()
+                .filter(|v| v.severity == Severity::Error)
+                .count();
+            println!(
+                "\n{} errors, {} warnings",
+                errors,
+                violations.len() - errors
+            );
+            if errors > 0 {
+                return Err("PNG violates the specification".into());
+            }
+        }
+
+        Commands::Visualize {
             file_path,
+            planes,
+            diff,
+            output_path,
+        } => {
+            let decode = |path: &str| -> veil::Result<PixelBuffer> {
+                SteganographyFile::from_file(path)?
+                    .decode_colors()?
+                    .ok_or_else(|| format!("{} is not an image with decoded pixels", path).into())
+            };
+            let pixels = decode(&file_path)?;
+
+            let (image, description) = match diff {
+                Some(cover_path) => {
+                    let map = planes::difference(&decode(&cover_path)?, &pixels)?;
+                    let changed = map.samples().iter().filter(|&&s| s == 1).count();
+                    let description = format!(
+                        "Difference map ({} of {} pixels changed)",
+                        changed,
+                        map.samples().len()
+                    );
+                    (map, description)
+                }
+                None => {
+                    let planes = planes
+                        .iter()
+                        .map(|plane| plane.parse())
+                        .collect::<veil::Result<Vec<Plane>>>()?;
+                    let names: Vec<String> = planes.iter().map(Plane::to_string).collect();
+                    let description = format!("Bit plane {}", names.join(" XOR "));
+                    (planes::render(&pixels, &planes)?, description)
+                }
+            };
+
+            raster::to_png(&image)?.to_file(&output_path)?;
+            println!("✓ {} written to {}", description, output_path);
+        }
+
+        Commands::Extract {
+            file_paths,
             output_dir,
+            key,
+            trailing,
+            chunk_type,
+            passphrase,
         } => {
-            // Load the file
-            let file = SteganographyFile::from_file(&file_path)?;
+            let mut found = Vec::new();
+            for file_path in &file_paths {
+                // Load the file
+                let file = SteganographyFile::from_file(file_path)?;
+                if let SteganographyFile::Png(png) = &file {
+                    for chunk in png.damaged_chunks() {
+                        println!(
+                            "⚠ CRC mismatch in the {} chunk of {}, its data may be damaged",
+                            chunk.chunk_type(),
+                            file_path
+                        );
+                    }
+                }
+
+                // Extract the hidden data, locating adaptively embedded bits with the key
+                let extracted = match (&key, file.pixels()) {
+                    _ if trailing => match file.trailing_data() {
+                        Some([]) => Err("No data after the end of the image".into()),
+                        Some(data) => Ok(data.to_vec()),
+                        None => Err("Trailing data is only detected in PNG files".into()),
+                    },
+                    _ if let Some(name) = &chunk_type => match &file {
+                        SteganographyFile::Png(png) => {
+                            payload::extract(png, &ChunkType::from_str(name)?)
+                                .map(|data| data.to_vec())
+                                .ok_or_else(|| format!("No {} chunk found", name).into())
+                        }
+                        _ => return Err("Chunk types are only used for PNG files".into()),
+                    },
+                    (Some(key), Some(pixels)) => adaptive::extract(pixels, key.as_bytes()),
+                    (Some(_), None) => {
+                        return Err("A key is only used for Netpbm and QOI files".into());
+                    }
+                    (None, _) => file.extract_data(),
+                };
+
+                // Undo error correction, repairing damaged bytes where possible
+                let decoded = extracted.and_then(|data| {
+                    if fec::is_encoded(&data) {
+                        let decoded = fec::decode(&data)?;
+                        Ok((decoded.data, Some(decoded.corrected)))
+                    } else {
+                        Ok((data, None))
+                    }
+                });
+                match decoded {
+                    Ok((data, corrected)) => {
+                        if let Some(corrected) = corrected {
+                            println!(
+                                "  Reed-Solomon FEC corrected {} symbols in {}",
+                                corrected, file_path
+                            );
+                        }
+                        found.push((file_path, data));
+                    }
+                    // A single file must hold the data; several may have a lost share among them
+                    Err(e) if file_paths.len() == 1 => return Err(e),
+                    Err(e) => println!("✗ {}: {}", file_path, e),
+                }
+            }
 
-            // Extract the hidden data
-            let hidden_data = file.extract_data()?;
+            // Combine secret shares, reporting how many were found
+            let hidden_data = if found.iter().any(|(_, data)| shamir::Share::is_share(data)) {
+                let shares: Vec<shamir::Share> = found
+                    .iter()
+                    .filter_map(|(_, data)| shamir::Share::try_from(data.as_slice()).ok())
+                    .collect();
+                let Some(threshold) = shares.first().map(|share| share.threshold) else {
+                    return Err("No valid secret shares found".into());
+                };
+                println!(
+                    "  Secret shares: found {}, need {}",
+                    shares.len(),
+                    threshold
+                );
+                shamir::combine(&shares)?
+            } else if found
+                .iter()
+                .any(|(_, data)| stripe::Stripe::is_stripe(data))
+            {
+                // Reassemble stripes, naming the ones still missing
+                let stripes: Vec<stripe::Stripe> = found
+                    .iter()
+                    .filter_map(|(_, data)| stripe::Stripe::try_from(data.as_slice()).ok())
+                    .collect();
+                let Some(count) = stripes.first().map(|stripe| stripe.count) else {
+                    return Err("No valid payload stripes found".into());
+                };
+                println!("  Payload stripes: found {} of {}", stripes.len(), count);
+                stripe::join(&stripes)?
+            } else {
+                match found.as_slice() {
+                    [(_, data)] => data.clone(),
+                    [] => return Err("No hidden data found".into()),
+                    _ => {
+                        return Err(
+                            "Several files can only be combined when they hold secret shares or stripes"
+                                .into(),
+                        );
+                    }
+                }
+            };
+
+            // Decrypt whichever payload the passphrase unlocks
+            let hidden_data = match passphrase {
+                Some(passphrase) => deniable::open(&hidden_data, passphrase.as_bytes())?,
+                None => hidden_data,
+            };
 
             // Determine output directory (use current directory if not provided)
             let output_directory = output_dir.unwrap_or_else(|| ".".to_string());
@@ -100,3 +642,193 @@ fn main() -> veil::Result<()> {
 
     Ok(())
 }
+
+/// How a payload is embedded into a single carrier.
+struct HideOptions {
+    idat: Option<IdatMode>,
+    palette: bool,
+    alpha: Option<AlphaMode>,
+    lsb_mode: LsbMode,
+    chunk_type: Option<ChunkType>,
+    adaptive: bool,
+    key: Option<String>,
+}
+
+/// What is worth reporting about a finished embedding.
+#[derive(Default)]
+struct HideReport {
+    capacity_bits: Option<usize>,
+    stats: Option<EmbedStats>,
+}
+
+impl HideReport {
+    fn print(&self) {
+        if let Some(bits) = self.capacity_bits {
+            println!("  Palette capacity: {} bits ({} bytes)", bits, bits / 8);
+        }
+        if let Some(stats) = self.stats {
+            if stats.k > 1 {
+                println!(
+                    "  Matrix embedding: (1, {}, {}) Hamming code",
+                    stats.block_len(),
+                    stats.k
+                );
+            }
+            println!(
+                "  Changed {} of {} samples ({:.2}%)",
+                stats.changes,
+                stats.cover_samples,
+                stats.change_rate() * 100.0
+            );
+        }
+    }
+}
+
+/// Hides a payload in a carrier with the selected embedding mode.
+fn hide(
+    file: &mut SteganographyFile,
+    payload: &[u8],
+    options: &HideOptions,
+) -> veil::Result<HideReport> {
+    let mut report = HideReport::default();
+    match (&mut *file, options.idat, options.palette, options.alpha) {
+        (SteganographyFile::Png(png), Some(mode), _, _) => {
+            idat::hide(png, payload, mode)?;
+        }
+        (SteganographyFile::Png(png), None, true, _) => {
+            report.capacity_bits = Some(palette::capacity(png)?);
+            palette::hide(png, payload)?;
+        }
+        (SteganographyFile::Png(png), None, false, Some(mode)) => {
+            report.stats = alpha::hide(png, payload, mode, options.lsb_mode)?;
+        }
+        (SteganographyFile::Png(png), None, false, None)
+            if let Some(chunk_type) = &options.chunk_type =>
+        {
+            payload::hide(png, payload, chunk_type.clone())?;
+        }
+        (_, None, false, None) if options.chunk_type.is_some() => {
+            return Err("Chunk types can only be chosen for PNG files".into());
+        }
+        (_, None, false, None) if options.adaptive => {
+            let pixels = file
+                .pixels_mut()
+                .ok_or("Adaptive embedding is only supported for Netpbm and QOI files")?;
+            let key = options.key.as_deref().unwrap_or_default();
+            report.stats = Some(adaptive::embed(
+                pixels,
+                payload,
+                options.lsb_mode,
+                key.as_bytes(),
+            )?);
+        }
+        (_, None, false, None) => match file.pixels_mut() {
+            Some(pixels) => report.stats = Some(lsb::embed(pixels, payload, options.lsb_mode)?),
+            None => file.hide_data(payload)?,
+        },
+        _ => {
+            return Err(
+                "IDAT, palette and alpha embedding are only supported for PNG files".into(),
+            );
+        }
+    }
+    Ok(report)
+}
+
+/// Returns how many payload bytes a carrier can take in the selected mode,
+/// or `None` if the mode is not bounded by the carrier's content.
+fn capacity(file: &SteganographyFile, options: &HideOptions) -> Option<usize> {
+    match (file, options.idat, options.palette, options.alpha) {
+        (SteganographyFile::Png(_), Some(_), _, _) => None,
+        (SteganographyFile::Png(png), None, true, _) => palette::capacity(png)
+            .ok()
+            .map(|bits| (bits / 8).saturating_sub(envelope::HEADER_LEN)),
+        (SteganographyFile::Png(png), None, false, Some(mode)) => alpha::capacity(png, mode).ok(),
+        _ if options.adaptive => file.pixels().map(|pixels| {
+            let key = options.key.as_deref().unwrap_or_default();
+            adaptive::capacity(pixels, key.as_bytes()).unwrap_or(0)
+        }),
+        _ => file.pixels().map(lsb::capacity),
+    }
+}
+
+/// Returns how many bytes of payload fit in a stripe for a carrier of the
+/// given capacity, after the stripe header and any FEC expansion.
+fn stripe_capacity(capacity: usize, fec: Option<u8>) -> usize {
+    let embedded = |length: usize| {
+        let stripe = stripe::HEADER_LEN + length;
+        match fec {
+            Some(parity) => fec::encoded_length(stripe, parity as usize),
+            None => stripe,
+        }
+    };
+
+    if embedded(0) > capacity {
+        return 0;
+    }
+
+    // The embedded length grows with the stripe length, so search for the largest that fits
+    let (mut low, mut high) = (0, capacity);
+    while low < high {
+        let middle = low + (high - low).div_ceil(2);
+        if embedded(middle) <= capacity {
+            low = middle;
+        } else {
+            high = middle - 1;
+        }
+    }
+    low
+}
+
+/// Returns where to save each carrier: the input file name with a "_hidden"
+/// suffix, placed in the output directory when several carriers are written.
+///
+/// Carriers with the same file name in different directories also get their
+/// position in the list, so that no output overwrites another.
+fn output_files(file_paths: &[String], output_path: Option<&str>) -> veil::Result<Vec<String>> {
+    let several = file_paths.len() > 1;
+    let names: Vec<String> = file_paths
+        .iter()
+        .map(|file_path| output_file(file_path, output_path, several, None))
+        .collect::<veil::Result<_>>()?;
+
+    names
+        .iter()
+        .enumerate()
+        .map(|(i, name)| {
+            if names.iter().filter(|other| *other == name).count() > 1 {
+                output_file(&file_paths[i], output_path, several, Some(i + 1))
+            } else {
+                Ok(name.clone())
+            }
+        })
+        .collect()
+}
+
+/// Returns where to save one carrier, with an optional index after the suffix.
+fn output_file(
+    file_path: &str,
+    output_path: Option<&str>,
+    several: bool,
+    index: Option<usize>,
+) -> veil::Result<String> {
+    let input_path = Path::new(file_path);
+    let stem = input_path.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = input_path.extension().unwrap_or_default().to_string_lossy();
+    let name = match index {
+        Some(index) => format!("{}_hidden_{}.{}", stem, index, extension),
+        None => format!("{}_hidden.{}", stem, extension),
+    };
+
+    match (output_path, several) {
+        (Some(path), false) => Ok(path.to_string()),
+        (Some(directory), true) => {
+            std::fs::create_dir_all(directory)?;
+            Ok(Path::new(directory)
+                .join(name)
+                .to_string_lossy()
+                .into_owned())
+        }
+        (None, _) => Ok(name),
+    }
+}
@@ -0,0 +1,4 @@
+#[allow(clippy::module_inception)]
+mod netpbm;
+
+pub use netpbm::{Netpbm, NetpbmFormat};
@@ -0,0 +1,305 @@
+use crate::pixels::PixelBuffer;
+use crate::{Error, Result};
+use std::fmt;
+
+/// The binary Netpbm variants supported by veil.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NetpbmFormat {
+    /// `P4`: bilevel bitmap, one bit per pixel, packed per row.
+    Bitmap,
+    /// `P5`: single-channel graymap.
+    Graymap,
+    /// `P6`: three-channel RGB pixmap.
+    Pixmap,
+}
+
+impl NetpbmFormat {
+    /// Returns the two-byte magic number of the format.
+    pub fn magic(&self) -> &'static [u8; 2] {
+        match self {
+            NetpbmFormat::Bitmap => b"P4",
+            NetpbmFormat::Graymap => b"P5",
+            NetpbmFormat::Pixmap => b"P6",
+        }
+    }
+
+    /// Returns the number of samples per pixel.
+    pub fn channels(&self) -> u8 {
+        match self {
+            NetpbmFormat::Bitmap | NetpbmFormat::Graymap => 1,
+            NetpbmFormat::Pixmap => 3,
+        }
+    }
+}
+
+/// Represents a binary Netpbm (PBM/PGM/PPM) image.
+///
+/// Comments in the header are not preserved when the image is written back.
+#[derive(Debug, Clone)]
+pub struct Netpbm {
+    format: NetpbmFormat,
+    pixels: PixelBuffer,
+}
+
+impl Netpbm {
+    /// Reads a Netpbm image from a file at the given path.
+    pub fn from_file<P: AsRef<std::path::Path>>(path: P) -> Result<Self> {
+        let data = std::fs::read(path)?;
+        Self::try_from(data.as_slice())
+    }
+
+    /// Creates an image from decoded pixels, checking that they fit the format.
+    pub fn from_pixels(format: NetpbmFormat, pixels: PixelBuffer) -> Result<Self> {
+        if pixels.channels() != format.channels() {
+            return Err("Channel count does not match the Netpbm format".into());
+        }
+        if format == NetpbmFormat::Bitmap && pixels.max_value() != 1 {
+            return Err("Bitmaps must have a maximum value of 1".into());
+        }
+        Ok(Self { format, pixels })
+    }
+
+    /// Returns the Netpbm variant of the image.
+    pub fn format(&self) -> NetpbmFormat {
+        self.format
+    }
+
+    /// Returns the decoded pixels.
+    pub fn pixels(&self) -> &PixelBuffer {
+        &self.pixels
+    }
+
+    /// Returns the decoded pixels for modification.
+    pub fn pixels_mut(&mut self) -> &mut PixelBuffer {
+        &mut self.pixels
+    }
+
+    /// Serializes the image to a vector of bytes.
+    pub fn as_bytes(&self) -> Vec<u8> {
+        let pixels = &self.pixels;
+        let mut result = self.format.magic().to_vec();
+        result.extend(format!("\n{} {}\n", pixels.width(), pixels.height()).bytes());
+
+        match self.format {
+            NetpbmFormat::Bitmap => {
+                for row in pixels.samples().chunks(pixels.width().max(1) as usize) {
+                    for bits in row.chunks(8) {
+                        let byte = bits
+                            .iter()
+                            .enumerate()
+                            .fold(0u8, |acc, (i, &bit)| acc | ((bit as u8) << (7 - i)));
+                        result.push(byte);
+                    }
+                }
+            }
+            NetpbmFormat::Graymap | NetpbmFormat::Pixmap => {
+                result.extend(format!("{}\n", pixels.max_value()).bytes());
+                let wide = pixels.max_value() > 255;
+                for &sample in pixels.samples() {
+                    if wide {
+                        result.extend_from_slice(&sample.to_be_bytes());
+                    } else {
+                        result.push(sample as u8);
+                    }
+                }
+            }
+        }
+        result
+    }
+
+    /// Writes the image to a file at the given path.
+    pub fn to_file<P: AsRef<std::path::Path>>(&self, path: P) -> Result<()> {
+        std::fs::write(path, self.as_bytes())?;
+        Ok(())
+    }
+}
+
+/// Implements conversion from a byte slice to a Netpbm image, parsing the header and raster.
+impl TryFrom<&[u8]> for Netpbm {
+    type Error = Error;
+
+    fn try_from(value: &[u8]) -> Result<Self> {
+        let format = match value.get(..2) {
+            Some(b"P4") => NetpbmFormat::Bitmap,
+            Some(b"P5") => NetpbmFormat::Graymap,
+            Some(b"P6") => NetpbmFormat::Pixmap,
+            _ => return Err("Invalid or unsupported Netpbm header".into()),
+        };
+
+        let mut header = HeaderReader {
+            data: value,
+            offset: 2,
+        };
+        let width = header.next_number()?;
+        let height = header.next_number()?;
+        let max_value = match format {
+            NetpbmFormat::Bitmap => 1,
+            _ => header.next_number()?,
+        };
+        if max_value == 0 || max_value > u16::MAX as u32 {
+            return Err("Netpbm maximum value must be between 1 and 65535".into());
+        }
+
+        // Exactly one whitespace byte separates the header from the raster
+        if !value
+            .get(header.offset)
+            .is_some_and(u8::is_ascii_whitespace)
+        {
+            return Err("Missing whitespace after Netpbm header".into());
+        }
+        let raster = &value[header.offset + 1..];
+
+        // Crafted headers may declare sizes that overflow before the raster is checked
+        let too_large = || "Netpbm image is too large";
+        let channels = format.channels() as usize;
+        let sample_count = (width as usize)
+            .checked_mul(height as usize)
+            .and_then(|count| count.checked_mul(channels))
+            .ok_or_else(too_large)?;
+        let samples: Vec<u16> = match format {
+            NetpbmFormat::Bitmap => {
+                let row_bytes = (width as usize).div_ceil(8);
+                let raster_len = row_bytes
+                    .checked_mul(height as usize)
+                    .ok_or_else(too_large)?;
+                if raster.len() < raster_len {
+                    return Err("Netpbm raster is truncated".into());
+                }
+                (0..height as usize)
+                    .flat_map(|y| {
+                        let row = &raster[y * row_bytes..(y + 1) * row_bytes];
+                        (0..width as usize).map(move |x| ((row[x / 8] >> (7 - x % 8)) & 1) as u16)
+                    })
+                    .collect()
+            }
+            _ if max_value > 255 => {
+                if raster.len() / 2 < sample_count {
+                    return Err("Netpbm raster is truncated".into());
+                }
+                raster[..sample_count * 2]
+                    .chunks_exact(2)
+                    .map(|b| u16::from_be_bytes([b[0], b[1]]))
+                    .collect()
+            }
+            _ => {
+                if raster.len() < sample_count {
+                    return Err("Netpbm raster is truncated".into());
+                }
+                raster[..sample_count].iter().map(|&b| b as u16).collect()
+            }
+        };
+
+        let pixels = PixelBuffer::new(width, height, format.channels(), max_value as u16, samples)?;
+        Self::from_pixels(format, pixels)
+    }
+}
+
+impl fmt::Display for Netpbm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Netpbm {{")?;
+        writeln!(f, "  Format: {:?}", self.format)?;
+        writeln!(
+            f,
+            "  Size: {}x{}",
+            self.pixels.width(),
+            self.pixels.height()
+        )?;
+        writeln!(f, "  Max value: {}", self.pixels.max_value())?;
+        writeln!(f, "}}")?;
+        Ok(())
+    }
+}
+
+/// Tokenizer for the ASCII part of a Netpbm header, skipping whitespace and comments.
+struct HeaderReader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl HeaderReader<'_> {
+    fn next_number(&mut self) -> Result<u32> {
+        loop {
+            match self.data.get(self.offset) {
+                Some(b) if b.is_ascii_whitespace() => self.offset += 1,
+                Some(b'#') => {
+                    while self.data.get(self.offset).is_some_and(|&b| b != b'\n') {
+                        self.offset += 1;
+                    }
+                }
+                Some(b) if b.is_ascii_digit() => break,
+                _ => return Err("Invalid Netpbm header".into()),
+            }
+        }
+
+        let start = self.offset;
+        while self.data.get(self.offset).is_some_and(u8::is_ascii_digit) {
+            self.offset += 1;
+        }
+        std::str::from_utf8(&self.data[start..self.offset])?
+            .parse()
+            .map_err(|_| "Invalid number in Netpbm header".into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_pixmap_with_comment() {
+        let mut bytes = b"P6\n# a comment\n2 1\n255\n".to_vec();
+        bytes.extend_from_slice(&[1, 2, 3, 4, 5, 6]);
+
+        let image = Netpbm::try_from(bytes.as_slice()).unwrap();
+
+        assert_eq!(image.format(), NetpbmFormat::Pixmap);
+        assert_eq!(image.pixels().width(), 2);
+        assert_eq!(image.pixels().pixel(1, 0), &[4, 5, 6]);
+    }
+
+    #[test]
+    fn test_graymap_16_bit_roundtrip() {
+        let mut bytes = b"P5\n2 2\n65535\n".to_vec();
+        for value in [0u16, 1000, 40000, 65535] {
+            bytes.extend_from_slice(&value.to_be_bytes());
+        }
+
+        let image = Netpbm::try_from(bytes.as_slice()).unwrap();
+
+        assert_eq!(image.pixels().samples(), &[0, 1000, 40000, 65535]);
+        assert_eq!(image.as_bytes(), bytes);
+    }
+
+    #[test]
+    fn test_bitmap_roundtrip() {
+        // 10 pixels wide means each row is padded to two bytes
+        let mut bytes = b"P4\n10 2\n".to_vec();
+        bytes.extend_from_slice(&[0b1010_1010, 0b1100_0000, 0b0000_0001, 0b0100_0000]);
+
+        let image = Netpbm::try_from(bytes.as_slice()).unwrap();
+
+        assert_eq!(image.pixels().pixel(0, 0), &[1]);
+        assert_eq!(image.pixels().pixel(9, 0), &[1]);
+        assert_eq!(image.pixels().pixel(7, 1), &[1]);
+        assert_eq!(image.as_bytes(), bytes);
+    }
+
+    #[test]
+    fn test_truncated_raster() {
+        let bytes = b"P6\n2 2\n255\n\x00\x01".to_vec();
+        assert!(Netpbm::try_from(bytes.as_slice()).is_err());
+    }
+
+    #[test]
+    fn test_oversized_header() {
+        let bytes = b"P6\n4000000000 4000000000\n255\n\x00".to_vec();
+        let error = Netpbm::try_from(bytes.as_slice()).unwrap_err();
+        assert_eq!(error.to_string(), "Netpbm image is too large");
+    }
+
+    #[test]
+    fn test_ascii_variants_unsupported() {
+        let bytes = b"P3\n1 1\n255\n0 0 0\n".to_vec();
+        assert!(Netpbm::try_from(bytes.as_slice()).is_err());
+    }
+}
@@ -0,0 +1,265 @@
+use crate::Result;
+
+/// The codec carried by an Ogg logical bitstream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Codec {
+    Vorbis,
+    Opus,
+}
+
+impl Codec {
+    /// Detects the codec from the identification header packet.
+    pub fn detect(packet: &[u8]) -> Result<Self> {
+        if packet.starts_with(b"\x01vorbis") {
+            Ok(Codec::Vorbis)
+        } else if packet.starts_with(b"OpusHead") {
+            Ok(Codec::Opus)
+        } else {
+            Err("Unsupported Ogg codec (expected Vorbis or Opus)".into())
+        }
+    }
+
+    /// Returns the number of header packets at the start of the stream.
+    pub fn header_packets(&self) -> usize {
+        match self {
+            Codec::Vorbis => 3,
+            Codec::Opus => 2,
+        }
+    }
+
+    /// Returns the magic that starts the comment header packet.
+    pub fn comment_magic(&self) -> &'static [u8] {
+        match self {
+            Codec::Vorbis => b"\x03vorbis",
+            Codec::Opus => b"OpusTags",
+        }
+    }
+}
+
+/// A Vorbis-style comment header as used by both Vorbis and Opus.
+///
+/// Comments are kept as raw `KEY=value` bytes so that unknown or oddly encoded
+/// entries survive a rewrite unchanged.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommentHeader {
+    codec: Codec,
+    vendor: Vec<u8>,
+    comments: Vec<Vec<u8>>,
+    trailing: Vec<u8>,
+}
+
+impl CommentHeader {
+    /// Parses the comment header packet of the given codec.
+    pub fn parse(codec: Codec, packet: &[u8]) -> Result<Self> {
+        let magic = codec.comment_magic();
+        if !packet.starts_with(magic) {
+            return Err("Invalid comment header packet".into());
+        }
+
+        let mut offset = magic.len();
+        let field = |offset: &mut usize| -> Result<Vec<u8>> {
+            let length = read_u32(packet, *offset)? as usize;
+            let value = packet
+                .get(*offset + 4..*offset + 4 + length)
+                .ok_or("Comment header is truncated")?;
+            *offset += 4 + length;
+            Ok(value.to_vec())
+        };
+
+        let vendor = field(&mut offset)?;
+        let count = read_u32(packet, offset)?;
+        offset += 4;
+        let comments = (0..count)
+            .map(|_| field(&mut offset))
+            .collect::<Result<Vec<_>>>()?;
+
+        // Vorbis ends with a framing bit; Opus may carry extra binary data
+        let trailing = packet[offset..].to_vec();
+        if codec == Codec::Vorbis && trailing.first().is_none_or(|b| b & 1 == 0) {
+            return Err("Vorbis comment header is missing its framing bit".into());
+        }
+
+        Ok(Self {
+            codec,
+            vendor,
+            comments,
+            trailing,
+        })
+    }
+
+    /// Returns the vendor string.
+    pub fn vendor(&self) -> &[u8] {
+        &self.vendor
+    }
+
+    /// Returns all `KEY=value` comments.
+    pub fn comments(&self) -> &[Vec<u8>] {
+        &self.comments
+    }
+
+    /// Returns the value of the first comment with the given key (case-insensitive).
+    pub fn get(&self, key: &str) -> Option<&[u8]> {
+        self.comments
+            .iter()
+            .find_map(|comment| strip_key(comment, key))
+    }
+
+    /// Removes all comments with the given key and adds a new one.
+    pub fn set(&mut self, key: &str, value: &[u8]) {
+        self.remove(key);
+        let mut comment = key.as_bytes().to_vec();
+        comment.push(b'=');
+        comment.extend_from_slice(value);
+        self.comments.push(comment);
+    }
+
+    /// Removes all comments with the given key.
+    pub fn remove(&mut self, key: &str) {
+        self.comments
+            .retain(|comment| strip_key(comment, key).is_none());
+    }
+
+    /// Stores binary data base64-encoded under the given key.
+    pub fn set_binary(&mut self, key: &str, data: &[u8]) {
+        self.set(key, base64_encode(data).as_bytes());
+    }
+
+    /// Returns the decoded binary data stored under the given key.
+    pub fn binary(&self, key: &str) -> Option<Vec<u8>> {
+        self.get(key).and_then(base64_decode)
+    }
+
+    /// Serializes the header back to a packet.
+    pub fn to_packet(&self) -> Vec<u8> {
+        let mut packet = self.codec.comment_magic().to_vec();
+        packet.extend_from_slice(&(self.vendor.len() as u32).to_le_bytes());
+        packet.extend_from_slice(&self.vendor);
+        packet.extend_from_slice(&(self.comments.len() as u32).to_le_bytes());
+        for comment in &self.comments {
+            packet.extend_from_slice(&(comment.len() as u32).to_le_bytes());
+            packet.extend_from_slice(comment);
+        }
+        packet.extend_from_slice(&self.trailing);
+        packet
+    }
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32> {
+    let bytes = data
+        .get(offset..offset + 4)
+        .ok_or("Comment header is truncated")?;
+    Ok(u32::from_le_bytes(bytes.try_into()?))
+}
+
+fn strip_key<'a>(comment: &'a [u8], key: &str) -> Option<&'a [u8]> {
+    let key = key.as_bytes();
+    (comment.len() > key.len()
+        && comment[key.len()] == b'='
+        && comment[..key.len()].eq_ignore_ascii_case(key))
+    .then(|| &comment[key.len() + 1..])
+}
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn base64_encode(data: &[u8]) -> String {
+    let mut result = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bytes = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let group = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                let index = (group >> (18 - 6 * i)) & 0x3f;
+                result.push(BASE64_ALPHABET[index as usize] as char);
+            } else {
+                result.push('=');
+            }
+        }
+    }
+    result
+}
+
+fn base64_decode(text: &[u8]) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(4) {
+        return None;
+    }
+    let mut result = Vec::with_capacity(text.len() / 4 * 3);
+    for chunk in text.chunks(4) {
+        let padding = chunk.iter().rev().take_while(|&&b| b == b'=').count();
+        if padding > 2 {
+            return None;
+        }
+        let mut group = 0u32;
+        for &byte in &chunk[..4 - padding] {
+            let value = BASE64_ALPHABET.iter().position(|&c| c == byte)?;
+            group = (group << 6) | value as u32;
+        }
+        group <<= 6 * padding as u32;
+        result.extend_from_slice(&group.to_be_bytes()[1..4 - padding]);
+    }
+    Some(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vorbis_comment_packet() -> Vec<u8> {
+        let mut packet = b"\x03vorbis".to_vec();
+        packet.extend_from_slice(&4u32.to_le_bytes());
+        packet.extend_from_slice(b"veil");
+        packet.extend_from_slice(&1u32.to_le_bytes());
+        packet.extend_from_slice(&11u32.to_le_bytes());
+        packet.extend_from_slice(b"TITLE=Hello");
+        packet.push(1);
+        packet
+    }
+
+    #[test]
+    fn test_parse_roundtrip() {
+        let packet = vorbis_comment_packet();
+        let header = CommentHeader::parse(Codec::Vorbis, &packet).unwrap();
+
+        assert_eq!(header.vendor(), b"veil");
+        assert_eq!(header.get("title"), Some(&b"Hello"[..]));
+        assert_eq!(header.to_packet(), packet);
+    }
+
+    #[test]
+    fn test_missing_framing_bit() {
+        let mut packet = vorbis_comment_packet();
+        packet.pop();
+        assert!(CommentHeader::parse(Codec::Vorbis, &packet).is_err());
+    }
+
+    #[test]
+    fn test_set_replaces_existing() {
+        let mut header = CommentHeader::parse(Codec::Vorbis, &vorbis_comment_packet()).unwrap();
+        header.set("Title", b"World");
+
+        assert_eq!(header.comments().len(), 1);
+        assert_eq!(header.get("TITLE"), Some(&b"World"[..]));
+    }
+
+    #[test]
+    fn test_binary_roundtrip() {
+        let mut header = CommentHeader::parse(Codec::Vorbis, &vorbis_comment_packet()).unwrap();
+        for data in [&b""[..], b"a", b"ab", b"abc", &[0, 255, 128, 7]] {
+            header.set_binary("VEIL", data);
+            assert_eq!(header.binary("VEIL").unwrap(), data);
+        }
+    }
+
+    #[test]
+    fn test_base64_known_values() {
+        assert_eq!(base64_encode(b"Man"), "TWFu");
+        assert_eq!(base64_encode(b"Ma"), "TWE=");
+        assert_eq!(base64_encode(b"M"), "TQ==");
+        assert_eq!(base64_decode(b"TWE="), Some(b"Ma".to_vec()));
+        assert_eq!(base64_decode(b"TW!="), None);
+    }
+}
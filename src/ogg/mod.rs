@@ -0,0 +1,8 @@
+mod comment;
+#[allow(clippy::module_inception)]
+mod ogg;
+mod page;
+
+pub use comment::{Codec, CommentHeader};
+pub use ogg::Ogg;
+pub use page::Page;
@@ -0,0 +1,345 @@
+use crate::ogg::{Codec, CommentHeader, Page};
+use crate::{Error, Result};
+use std::fmt;
+
+/// Represents an Ogg Vorbis or Ogg Opus file as a sequence of pages.
+///
+/// Only the header packets of the first logical bitstream are interpreted;
+/// audio pages are kept as they are apart from their sequence numbers.
+#[derive(Debug, Clone)]
+pub struct Ogg {
+    pages: Vec<Page>,
+}
+
+/// The header packets of the first logical bitstream and the pages holding them.
+struct Headers {
+    codec: Codec,
+    packets: Vec<Vec<u8>>,
+    page_count: usize,
+}
+
+impl Ogg {
+    /// Reads an Ogg file from the given path.
+    pub fn from_file<P: AsRef<std::path::Path>>(path: P) -> Result<Self> {
+        let data = std::fs::read(path)?;
+        Self::try_from(data.as_slice())
+    }
+
+    /// Creates an Ogg file from a vector of pages.
+    pub fn from_pages(pages: Vec<Page>) -> Self {
+        Self { pages }
+    }
+
+    /// Returns a slice of all pages.
+    pub fn pages(&self) -> &[Page] {
+        &self.pages
+    }
+
+    /// Returns the codec of the first logical bitstream.
+    pub fn codec(&self) -> Result<Codec> {
+        Ok(self.headers()?.codec)
+    }
+
+    /// Returns the parsed comment header of the first logical bitstream.
+    pub fn comments(&self) -> Result<CommentHeader> {
+        let headers = self.headers()?;
+        CommentHeader::parse(headers.codec, &headers.packets[1])
+    }
+
+    /// Replaces the comment header, rebuilding the header pages.
+    ///
+    /// Header pages are re-laced from scratch and every later page of the
+    /// stream is renumbered, so all page CRCs stay valid.
+    pub fn set_comments(&mut self, comments: &CommentHeader) -> Result<()> {
+        let headers = self.headers()?;
+        let serial = self.pages[0].serial();
+
+        let mut packets = headers.packets;
+        packets[1] = comments.to_packet();
+        let rebuilt = paginate(&packets[1..], serial, 1)?;
+
+        let delta = rebuilt.len() as i64 - (headers.page_count as i64 - 1);
+        let mut pages = vec![self.pages[0].clone()];
+        pages.extend(rebuilt);
+        for mut page in self.pages.drain(headers.page_count..) {
+            if page.serial() == serial {
+                page.set_sequence((page.sequence() as i64 + delta) as u32);
+            }
+            pages.push(page);
+        }
+        self.pages = pages;
+        Ok(())
+    }
+
+    /// Serializes all pages to a vector of bytes.
+    pub fn as_bytes(&self) -> Vec<u8> {
+        self.pages.iter().flat_map(Page::as_bytes).collect()
+    }
+
+    /// Writes the file to the given path.
+    pub fn to_file<P: AsRef<std::path::Path>>(&self, path: P) -> Result<()> {
+        std::fs::write(path, self.as_bytes())?;
+        Ok(())
+    }
+
+    /// Collects the header packets from the leading pages of the first stream.
+    fn headers(&self) -> Result<Headers> {
+        let first = self.pages.first().ok_or("Ogg file contains no pages")?;
+        let serial = first.serial();
+
+        let mut codec = None;
+        let mut packets = Vec::new();
+        let mut current = Vec::new();
+
+        for (index, page) in self.pages.iter().enumerate() {
+            if page.serial() != serial {
+                return Err("Multiplexed Ogg streams are not supported".into());
+            }
+
+            let mut offset = 0;
+            for (i, &lacing) in page.segments().iter().enumerate() {
+                current.extend_from_slice(&page.body()[offset..offset + lacing as usize]);
+                offset += lacing as usize;
+                if lacing == 255 {
+                    continue;
+                }
+
+                let packet = std::mem::take(&mut current);
+                let codec = match codec {
+                    Some(codec) => codec,
+                    None => *codec.insert(Codec::detect(&packet)?),
+                };
+                packets.push(packet);
+
+                if packets.len() == codec.header_packets() {
+                    if i + 1 != page.segments().len() {
+                        return Err("Ogg header packets must end a page".into());
+                    }
+                    return Ok(Headers {
+                        codec,
+                        packets,
+                        page_count: index + 1,
+                    });
+                }
+            }
+        }
+        Err("Ogg stream ends before its header packets".into())
+    }
+}
+
+/// Lays out packets on consecutive pages with granule position 0.
+fn paginate(packets: &[Vec<u8>], serial: u32, first_sequence: u32) -> Result<Vec<Page>> {
+    // Each entry is (lacing value, byte range) for one segment
+    let mut segments = Vec::new();
+    let mut ends_packet = Vec::new();
+    let data: Vec<u8> = packets.concat();
+    let mut offset = 0;
+    for packet in packets {
+        let mut remaining = packet.len();
+        loop {
+            let lacing = remaining.min(255);
+            segments.push((lacing as u8, offset..offset + lacing));
+            offset += lacing;
+            remaining -= lacing;
+            ends_packet.push(lacing < 255);
+            if lacing < 255 {
+                break;
+            }
+        }
+    }
+
+    let mut pages = Vec::new();
+    let mut continued = false;
+    for (i, group) in segments.chunks(255).enumerate() {
+        let ends = &ends_packet[i * 255..i * 255 + group.len()];
+        let body = group
+            .iter()
+            .flat_map(|(_, range)| data[range.clone()].iter().copied())
+            .collect();
+        let granule = if ends.contains(&true) {
+            0
+        } else {
+            Page::NO_GRANULE
+        };
+        let header_type = if continued { Page::FLAG_CONTINUED } else { 0 };
+
+        pages.push(Page::new(
+            header_type,
+            granule,
+            serial,
+            first_sequence + i as u32,
+            group.iter().map(|(lacing, _)| *lacing).collect(),
+            body,
+        )?);
+        continued = !ends[ends.len() - 1];
+    }
+    Ok(pages)
+}
+
+/// Implements conversion from a byte slice to an Ogg file, parsing and verifying all pages.
+impl TryFrom<&[u8]> for Ogg {
+    type Error = Error;
+
+    fn try_from(value: &[u8]) -> Result<Self> {
+        let mut pages = Vec::new();
+        let mut offset = 0;
+        while offset < value.len() {
+            let page = Page::try_from(&value[offset..])?;
+            offset += page.size();
+            pages.push(page);
+        }
+
+        if pages.is_empty() {
+            return Err("Ogg file contains no pages".into());
+        }
+        Ok(Self { pages })
+    }
+}
+
+impl fmt::Display for Ogg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Ogg {{")?;
+        writeln!(f, "  Pages: {} pages", self.pages.len())?;
+        for (i, page) in self.pages.iter().enumerate() {
+            writeln!(
+                f,
+                "    Page {}: Serial: {}, Sequence: {}, Body: {} bytes",
+                i,
+                page.serial(),
+                page.sequence(),
+                page.body().len()
+            )?;
+        }
+        writeln!(f, "}}")?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SERIAL: u32 = 0x1234;
+
+    fn comment_packet(codec: Codec, comment: &[u8]) -> Vec<u8> {
+        let mut packet = codec.comment_magic().to_vec();
+        packet.extend_from_slice(&4u32.to_le_bytes());
+        packet.extend_from_slice(b"veil");
+        packet.extend_from_slice(&1u32.to_le_bytes());
+        packet.extend_from_slice(&(comment.len() as u32).to_le_bytes());
+        packet.extend_from_slice(comment);
+        if codec == Codec::Vorbis {
+            packet.push(1);
+        }
+        packet
+    }
+
+    fn audio_pages(first_sequence: u32) -> Vec<Page> {
+        (0..3)
+            .map(|i| {
+                Page::new(
+                    0,
+                    960 * (i + 1),
+                    SERIAL,
+                    first_sequence + i as u32,
+                    vec![4],
+                    vec![i as u8; 4],
+                )
+                .unwrap()
+            })
+            .collect()
+    }
+
+    fn testing_vorbis() -> Ogg {
+        let mut pages = vec![
+            Page::new(
+                Page::FLAG_FIRST,
+                0,
+                SERIAL,
+                0,
+                vec![7],
+                b"\x01vorbis".to_vec(),
+            )
+            .unwrap(),
+        ];
+        pages.extend(
+            paginate(
+                &[
+                    comment_packet(Codec::Vorbis, b"TITLE=x"),
+                    b"\x05vorbis-setup".to_vec(),
+                ],
+                SERIAL,
+                1,
+            )
+            .unwrap(),
+        );
+        pages.extend(audio_pages(2));
+        Ogg::from_pages(pages)
+    }
+
+    fn testing_opus() -> Ogg {
+        let mut pages = vec![
+            Page::new(
+                Page::FLAG_FIRST,
+                0,
+                SERIAL,
+                0,
+                vec![8],
+                b"OpusHead".to_vec(),
+            )
+            .unwrap(),
+        ];
+        pages.extend(paginate(&[comment_packet(Codec::Opus, b"TITLE=x")], SERIAL, 1).unwrap());
+        pages.extend(audio_pages(2));
+        Ogg::from_pages(pages)
+    }
+
+    #[test]
+    fn test_bytes_roundtrip() {
+        let ogg = testing_vorbis();
+        let bytes = ogg.as_bytes();
+        let parsed = Ogg::try_from(bytes.as_slice()).unwrap();
+
+        assert_eq!(parsed.pages().len(), 5);
+        assert_eq!(parsed.as_bytes(), bytes);
+    }
+
+    #[test]
+    fn test_codec_detection() {
+        assert_eq!(testing_vorbis().codec().unwrap(), Codec::Vorbis);
+        assert_eq!(testing_opus().codec().unwrap(), Codec::Opus);
+    }
+
+    #[test]
+    fn test_large_comment_spans_pages() {
+        for mut ogg in [testing_vorbis(), testing_opus()] {
+            let mut comments = ogg.comments().unwrap();
+            comments.set_binary("VEIL", &vec![0xab; 100_000]);
+            ogg.set_comments(&comments).unwrap();
+
+            let parsed = Ogg::try_from(ogg.as_bytes().as_slice()).unwrap();
+            assert!(parsed.pages().len() > 5);
+
+            // Sequence numbers stay contiguous and the audio is untouched
+            for (i, page) in parsed.pages().iter().enumerate() {
+                assert_eq!(page.sequence(), i as u32);
+            }
+            assert_eq!(parsed.pages().last().unwrap().body(), &[2; 4]);
+            assert!(parsed.pages()[2].is_continued());
+
+            let comments = parsed.comments().unwrap();
+            assert_eq!(comments.get("TITLE"), Some(&b"x"[..]));
+            assert_eq!(comments.binary("VEIL").unwrap(), vec![0xab; 100_000]);
+        }
+    }
+
+    #[test]
+    fn test_multiplexed_stream_rejected() {
+        let mut pages = testing_opus().pages().to_vec();
+        pages.insert(
+            1,
+            Page::new(Page::FLAG_FIRST, 0, 99, 0, vec![1], vec![0]).unwrap(),
+        );
+        assert!(Ogg::from_pages(pages).comments().is_err());
+    }
+}
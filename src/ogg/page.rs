@@ -0,0 +1,202 @@
+use crate::{Error, Result};
+use crc::{Algorithm, Crc};
+
+/// The CRC used by Ogg pages: polynomial 0x04c11db7, no reflection, zero init and xorout.
+const CRC_32_OGG: Algorithm<u32> = Algorithm {
+    width: 32,
+    poly: 0x04c11db7,
+    init: 0,
+    refin: false,
+    refout: false,
+    xorout: 0,
+    check: 0x89a1897f,
+    residue: 0,
+};
+
+/// Represents a single Ogg page with its segment table and body.
+#[derive(Debug, Clone)]
+pub struct Page {
+    header_type: u8,
+    granule_position: u64,
+    serial: u32,
+    sequence: u32,
+    segments: Vec<u8>,
+    body: Vec<u8>,
+}
+
+impl Page {
+    pub const CAPTURE_PATTERN: [u8; 4] = *b"OggS";
+    pub const FLAG_CONTINUED: u8 = 0x01;
+    pub const FLAG_FIRST: u8 = 0x02;
+    pub const FLAG_LAST: u8 = 0x04;
+    /// Granule position of a page on which no packet ends.
+    pub const NO_GRANULE: u64 = u64::MAX;
+
+    /// Creates a page from its header fields, segment table and body.
+    pub fn new(
+        header_type: u8,
+        granule_position: u64,
+        serial: u32,
+        sequence: u32,
+        segments: Vec<u8>,
+        body: Vec<u8>,
+    ) -> Result<Self> {
+        if segments.len() > 255 {
+            return Err("Ogg page has more than 255 segments".into());
+        }
+        if segments.iter().map(|&s| s as usize).sum::<usize>() != body.len() {
+            return Err("Ogg segment table does not match the page body".into());
+        }
+        Ok(Self {
+            header_type,
+            granule_position,
+            serial,
+            sequence,
+            segments,
+            body,
+        })
+    }
+
+    /// Returns the header type flags.
+    pub fn header_type(&self) -> u8 {
+        self.header_type
+    }
+
+    /// Returns the granule position.
+    pub fn granule_position(&self) -> u64 {
+        self.granule_position
+    }
+
+    /// Returns the bitstream serial number.
+    pub fn serial(&self) -> u32 {
+        self.serial
+    }
+
+    /// Returns the page sequence number.
+    pub fn sequence(&self) -> u32 {
+        self.sequence
+    }
+
+    /// Sets the page sequence number.
+    pub fn set_sequence(&mut self, sequence: u32) {
+        self.sequence = sequence;
+    }
+
+    /// Returns the segment (lacing) table.
+    pub fn segments(&self) -> &[u8] {
+        &self.segments
+    }
+
+    /// Returns the page body.
+    pub fn body(&self) -> &[u8] {
+        &self.body
+    }
+
+    /// Returns true if the page starts with the continuation of a packet.
+    pub fn is_continued(&self) -> bool {
+        self.header_type & Self::FLAG_CONTINUED != 0
+    }
+
+    /// Returns the number of bytes the page occupies when serialized.
+    pub fn size(&self) -> usize {
+        27 + self.segments.len() + self.body.len()
+    }
+
+    /// Serializes the page, computing its CRC.
+    pub fn as_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.size());
+        bytes.extend_from_slice(&Self::CAPTURE_PATTERN);
+        bytes.push(0); // stream structure version
+        bytes.push(self.header_type);
+        bytes.extend_from_slice(&self.granule_position.to_le_bytes());
+        bytes.extend_from_slice(&self.serial.to_le_bytes());
+        bytes.extend_from_slice(&self.sequence.to_le_bytes());
+        bytes.extend_from_slice(&[0; 4]); // CRC placeholder
+        bytes.push(self.segments.len() as u8);
+        bytes.extend_from_slice(&self.segments);
+        bytes.extend_from_slice(&self.body);
+
+        let crc = Crc::<u32>::new(&CRC_32_OGG).checksum(&bytes);
+        bytes[22..26].copy_from_slice(&crc.to_le_bytes());
+        bytes
+    }
+}
+
+/// Implements conversion from a byte slice to a Page, validating the CRC.
+///
+/// The slice may extend past the page; use [`Page::size`] to find the next one.
+impl TryFrom<&[u8]> for Page {
+    type Error = Error;
+
+    fn try_from(value: &[u8]) -> Result<Self> {
+        if value.len() < 27 || value[..4] != Self::CAPTURE_PATTERN {
+            return Err("Invalid Ogg page header".into());
+        }
+        if value[4] != 0 {
+            return Err("Unsupported Ogg stream structure version".into());
+        }
+
+        let segment_count = value[26] as usize;
+        let segments = value
+            .get(27..27 + segment_count)
+            .ok_or("Ogg segment table is truncated")?
+            .to_vec();
+        let body_start = 27 + segment_count;
+        let body_length: usize = segments.iter().map(|&s| s as usize).sum();
+        let body = value
+            .get(body_start..body_start + body_length)
+            .ok_or("Ogg page body is truncated")?
+            .to_vec();
+
+        let page = Self::new(
+            value[5],
+            u64::from_le_bytes(value[6..14].try_into()?),
+            u32::from_le_bytes(value[14..18].try_into()?),
+            u32::from_le_bytes(value[18..22].try_into()?),
+            segments,
+            body,
+        )?;
+
+        if page.as_bytes()[22..26] != value[22..26] {
+            return Err("Invalid Ogg page CRC".into());
+        }
+        Ok(page)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crc_check_value() {
+        assert_eq!(
+            Crc::<u32>::new(&CRC_32_OGG).checksum(b"123456789"),
+            0x89a1897f
+        );
+    }
+
+    #[test]
+    fn test_page_roundtrip() {
+        let page = Page::new(Page::FLAG_FIRST, 0, 42, 0, vec![5], b"hello".to_vec()).unwrap();
+        let bytes = page.as_bytes();
+        let parsed = Page::try_from(bytes.as_slice()).unwrap();
+
+        assert_eq!(parsed.serial(), 42);
+        assert_eq!(parsed.body(), b"hello");
+        assert_eq!(parsed.as_bytes(), bytes);
+    }
+
+    #[test]
+    fn test_page_bad_crc() {
+        let page = Page::new(0, 0, 1, 1, vec![3], b"abc".to_vec()).unwrap();
+        let mut bytes = page.as_bytes();
+        bytes[30] ^= 1;
+        assert!(Page::try_from(bytes.as_slice()).is_err());
+    }
+
+    #[test]
+    fn test_segment_mismatch() {
+        assert!(Page::new(0, 0, 1, 1, vec![4], b"abc".to_vec()).is_err());
+    }
+}
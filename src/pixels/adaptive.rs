@@ -0,0 +1,202 @@
+//! Content-adaptive embedding that keeps to textured regions.
+//!
+//! Changes in flat areas such as a clear sky stand out to both the eye and
+//! statistical detectors, while noisy, textured areas hide them well. Every
+//! sample is scored by its edge strength (the sum of absolute differences to
+//! its four neighbours in the same channel), and only the most textured half
+//! of the samples carries the payload, in an order derived from a key.
+//!
+//! The scores must come out the same when the receiver computes them from
+//! the stego image, so they ignore the two least significant bits of each
+//! sample, and embedding only ever changes a sample within its group of four
+//! values (`4i..=4i+3`). Both LSB replacement and matching respect that, with
+//! matching forced inwards at the edges of a group.
+//!
+//! The selected samples are handed to [`lsb`] as a separate buffer, so matrix
+//! embedding and the LSB modes work the same as for uniform embedding.
+
+use crate::Result;
+use crate::pixels::PixelBuffer;
+use crate::pixels::lsb::{self, EmbedStats, LsbMode};
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use sha2::{Digest, Sha256};
+use std::cmp::Reverse;
+
+/// Returns the edge strength of every sample, ignoring the two lowest bits.
+pub fn texture(pixels: &PixelBuffer) -> Vec<u32> {
+    let (width, height) = (pixels.width() as usize, pixels.height() as usize);
+    let channels = pixels.channels() as usize;
+    let coarse = |x: usize, y: usize, c: usize| {
+        (pixels.samples()[(y * width + x) * channels + c] >> 2) as i32
+    };
+
+    let mut scores = Vec::with_capacity(pixels.samples().len());
+    for y in 0..height {
+        for x in 0..width {
+            for c in 0..channels {
+                let value = coarse(x, y, c);
+                let neighbours = [
+                    (x > 0).then(|| coarse(x - 1, y, c)),
+                    (x + 1 < width).then(|| coarse(x + 1, y, c)),
+                    (y > 0).then(|| coarse(x, y - 1, c)),
+                    (y + 1 < height).then(|| coarse(x, y + 1, c)),
+                ];
+                let score = neighbours
+                    .into_iter()
+                    .flatten()
+                    .map(|neighbour| value.abs_diff(neighbour))
+                    .sum();
+                scores.push(score);
+            }
+        }
+    }
+    scores
+}
+
+/// Returns the positions of the samples that carry the payload, in embedding order.
+pub fn positions(pixels: &PixelBuffer, key: &[u8]) -> Vec<usize> {
+    let scores = texture(pixels);
+    let max_value = pixels.max_value();
+    let order = keyed_permutation(scores.len(), key);
+
+    // Samples in an incomplete group of four at the top of the range could
+    // leave their group when changed, so they are never selected
+    let top_group_complete = max_value % 4 == 3;
+    let eligible = |i: usize| {
+        scores[i] > 0 && (top_group_complete || pixels.samples()[i] >> 2 != max_value >> 2)
+    };
+
+    // The stable sort breaks ties between equal scores in key order
+    let mut ranked: Vec<usize> = order.iter().copied().filter(|&i| eligible(i)).collect();
+    ranked.sort_by_key(|&i| Reverse(scores[i]));
+    ranked.truncate(scores.len() / 2);
+
+    let mut selected = vec![false; scores.len()];
+    for &i in &ranked {
+        selected[i] = true;
+    }
+    order.into_iter().filter(|&i| selected[i]).collect()
+}
+
+/// Returns the number of payload bytes adaptive embedding can hide.
+pub fn capacity(pixels: &PixelBuffer, key: &[u8]) -> Result<usize> {
+    Ok(lsb::capacity(&selection(pixels, &positions(pixels, key))?))
+}
+
+/// Hides data in the most textured samples, in an order derived from the key.
+pub fn embed(
+    pixels: &mut PixelBuffer,
+    data: &[u8],
+    mode: LsbMode,
+    key: &[u8],
+) -> Result<EmbedStats> {
+    let positions = positions(pixels, key);
+    let mut selection = selection(pixels, &positions)?;
+    let stats = lsb::embed(&mut selection, data, mode)
+        .map_err(|e| format!("{} (adaptive embedding only uses textured samples)", e))?;
+
+    let samples = pixels.samples_mut();
+    for (&position, &low_bits) in positions.iter().zip(selection.samples()) {
+        samples[position] = (samples[position] & !3) | low_bits;
+    }
+    Ok(stats)
+}
+
+/// Extracts data hidden with [`embed`] using the same key.
+pub fn extract(pixels: &PixelBuffer, key: &[u8]) -> Result<Vec<u8>> {
+    lsb::extract(&selection(pixels, &positions(pixels, key))?)
+}
+
+/// Returns true if the textured samples selected by the key carry a payload.
+pub fn has_hidden_data(pixels: &PixelBuffer, key: &[u8]) -> bool {
+    extract(pixels, key).is_ok()
+}
+
+/// Collects the two lowest bits of the given samples into a one-row buffer.
+fn selection(pixels: &PixelBuffer, positions: &[usize]) -> Result<PixelBuffer> {
+    if positions.is_empty() {
+        return Err("Image has no textured samples for adaptive embedding".into());
+    }
+    let samples = positions.iter().map(|&i| pixels.samples()[i] & 3).collect();
+    PixelBuffer::new(positions.len() as u32, 1, 1, 3, samples)
+}
+
+/// Shuffles `0..len` with a ChaCha20 stream seeded from the key.
+fn keyed_permutation(len: usize, key: &[u8]) -> Vec<usize> {
+    let seed: [u8; 32] = Sha256::new()
+        .chain_update(b"veil adaptive selection")
+        .chain_update(key)
+        .finalize()
+        .into();
+    let mut rng = ChaCha20Rng::from_seed(seed);
+
+    let mut order: Vec<usize> = (0..len).collect();
+    for i in (1..len).rev() {
+        let j = (rng.next_u64() % (i as u64 + 1)) as usize;
+        order.swap(i, j);
+    }
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 64x64 graymap whose left half is flat and right half is noisy.
+    fn half_textured() -> PixelBuffer {
+        let samples = (0..64 * 64)
+            .map(|i: usize| {
+                let x = i % 64;
+                if x < 32 { 120 } else { (i * 7919 % 251) as u16 }
+            })
+            .collect();
+        PixelBuffer::new(64, 64, 1, 255, samples).unwrap()
+    }
+
+    #[test]
+    fn test_only_textured_samples_change() {
+        let original = half_textured();
+        let mut pixels = original.clone();
+        embed(&mut pixels, &[0x5a; 120], LsbMode::Match, b"key").unwrap();
+
+        for (i, (a, b)) in original.samples().iter().zip(pixels.samples()).enumerate() {
+            if i % 64 < 31 {
+                assert_eq!(a, b);
+            }
+            assert_eq!(a >> 2, b >> 2);
+        }
+        assert_eq!(texture(&pixels), texture(&original));
+        assert_eq!(extract(&pixels, b"key").unwrap(), [0x5a; 120]);
+    }
+
+    #[test]
+    fn test_wrong_key_finds_nothing() {
+        let mut pixels = half_textured();
+        embed(&mut pixels, b"keyed", LsbMode::Replace, b"right").unwrap();
+
+        assert!(has_hidden_data(&pixels, b"right"));
+        assert!(!has_hidden_data(&pixels, b"wrong"));
+    }
+
+    #[test]
+    fn test_flat_image_has_no_capacity() {
+        let mut flat = PixelBuffer::new(16, 16, 3, 255, vec![90; 768]).unwrap();
+        assert!(capacity(&flat, b"").is_err());
+        assert!(embed(&mut flat, b"x", LsbMode::Replace, b"").is_err());
+    }
+
+    #[test]
+    fn test_top_group_excluded_for_uneven_range() {
+        // With a maximum of 254 the values 252..=254 form an incomplete group
+        let samples = (0..256)
+            .map(|i| if i % 2 == 0 { 253 } else { 10 })
+            .collect();
+        let pixels = PixelBuffer::new(16, 16, 1, 254, samples).unwrap();
+        assert!(
+            positions(&pixels, b"")
+                .iter()
+                .all(|&i| pixels.samples()[i] == 10)
+        );
+    }
+}
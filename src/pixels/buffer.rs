@@ -0,0 +1,165 @@
+use crate::Result;
+
+/// Interleaved, decoded image samples in row-major order.
+///
+/// Every format that embeds data in the pixel domain converts its raster
+/// into a `PixelBuffer`, so embedding strategies only ever deal with plain
+/// samples and never with file-format details. Samples are stored as `u16`
+/// so that 16-bit formats fit without loss; `max_value` records the real
+/// range (e.g. 1 for bilevel images, 255 for 8-bit, 65535 for 16-bit).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PixelBuffer {
+    width: u32,
+    height: u32,
+    channels: u8,
+    max_value: u16,
+    samples: Vec<u16>,
+}
+
+impl PixelBuffer {
+    /// Creates a buffer, validating that the sample count matches the dimensions.
+    pub fn new(
+        width: u32,
+        height: u32,
+        channels: u8,
+        max_value: u16,
+        samples: Vec<u16>,
+    ) -> Result<Self> {
+        if channels == 0 || max_value == 0 {
+            return Err("Pixel buffer needs at least one channel and a non-zero range".into());
+        }
+
+        let expected = width as usize * height as usize * channels as usize;
+        if samples.len() != expected {
+            return Err(format!(
+                "Expected {} samples for {}x{}x{}, got {}",
+                expected,
+                width,
+                height,
+                channels,
+                samples.len()
+            )
+            .into());
+        }
+
+        if samples.iter().any(|&s| s > max_value) {
+            return Err("Sample value exceeds the maximum value".into());
+        }
+
+        Ok(Self {
+            width,
+            height,
+            channels,
+            max_value,
+            samples,
+        })
+    }
+
+    /// Returns the image width in pixels.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Returns the image height in pixels.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Returns the number of interleaved channels per pixel.
+    pub fn channels(&self) -> u8 {
+        self.channels
+    }
+
+    /// Returns the largest value a sample may take.
+    pub fn max_value(&self) -> u16 {
+        self.max_value
+    }
+
+    /// Returns a slice of all samples.
+    pub fn samples(&self) -> &[u16] {
+        &self.samples
+    }
+
+    /// Returns a mutable slice of all samples.
+    pub fn samples_mut(&mut self) -> &mut [u16] {
+        &mut self.samples
+    }
+
+    /// Returns the samples of the pixel at the given position.
+    pub fn pixel(&self, x: u32, y: u32) -> &[u16] {
+        let channels = self.channels as usize;
+        let start = (y as usize * self.width as usize + x as usize) * channels;
+        &self.samples[start..start + channels]
+    }
+
+    /// Returns a single-channel copy of one channel.
+    pub fn channel(&self, channel: u8) -> Result<PixelBuffer> {
+        if channel >= self.channels {
+            return Err(format!("Channel {} does not exist", channel).into());
+        }
+        let samples = self
+            .samples
+            .iter()
+            .skip(channel as usize)
+            .step_by(self.channels as usize)
+            .copied()
+            .collect();
+        PixelBuffer::new(self.width, self.height, 1, self.max_value, samples)
+    }
+
+    /// Overwrites one channel with the samples of a single-channel buffer.
+    pub fn set_channel(&mut self, channel: u8, plane: &PixelBuffer) -> Result<()> {
+        if channel >= self.channels
+            || plane.channels != 1
+            || plane.samples.len() * self.channels as usize != self.samples.len()
+            || plane.max_value > self.max_value
+        {
+            return Err("Channel does not match the pixel buffer".into());
+        }
+        for (sample, &value) in self
+            .samples
+            .iter_mut()
+            .skip(channel as usize)
+            .step_by(self.channels as usize)
+            .zip(&plane.samples)
+        {
+            *sample = value;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_validates_sample_count() {
+        assert!(PixelBuffer::new(2, 2, 3, 255, vec![0; 12]).is_ok());
+        assert!(PixelBuffer::new(2, 2, 3, 255, vec![0; 11]).is_err());
+    }
+
+    #[test]
+    fn test_new_validates_range() {
+        assert!(PixelBuffer::new(1, 1, 1, 1, vec![2]).is_err());
+        assert!(PixelBuffer::new(1, 1, 0, 255, vec![]).is_err());
+    }
+
+    #[test]
+    fn test_channel_roundtrip() {
+        let mut buffer = PixelBuffer::new(2, 1, 2, 255, vec![1, 2, 3, 4]).unwrap();
+        let mut alpha = buffer.channel(1).unwrap();
+        assert_eq!(alpha.samples(), &[2, 4]);
+
+        alpha.samples_mut()[0] = 9;
+        buffer.set_channel(1, &alpha).unwrap();
+        assert_eq!(buffer.samples(), &[1, 9, 3, 4]);
+        assert!(buffer.channel(2).is_err());
+    }
+
+    #[test]
+    fn test_pixel() {
+        let buffer = PixelBuffer::new(2, 1, 2, 255, vec![1, 2, 3, 4]).unwrap();
+        assert_eq!(buffer.pixel(1, 0), &[3, 4]);
+    }
+}
@@ -0,0 +1,283 @@
+//! Least-significant-bit embedding in decoded samples.
+//!
+//! The payload is sealed into an [envelope](crate::envelope) and written
+//! with matrix embedding: samples are grouped into blocks of `2^k - 1`, and
+//! each block carries `k` bits as the syndrome of the (1, 2^k - 1, k) Hamming
+//! code over its LSBs. Changing at most one sample per block is enough to set
+//! any syndrome, so larger `k` means fewer changes per embedded bit at the
+//! cost of capacity. With `k = 1` this is plain one-bit-per-sample LSB
+//! embedding. The code is not stored; [`extract`] tries each `k` until the
+//! envelope header matches.
+//!
+//! Plain replacement only ever swaps values within the pairs (2i, 2i+1),
+//! which evens out their histogram counts and is exactly what chi-square and
+//! RS analysis look for. [`LsbMode::Match`] instead adds or subtracts one at
+//! random whenever a bit has to change, which flips the LSB just the same but
+//! leaves no pair structure behind.
+
+use crate::Result;
+use crate::envelope::{self, HEADER_LEN, PROBE_LEN};
+use crate::pixels::PixelBuffer;
+use rand::Rng;
+
+/// How a sample is changed when its least significant bit does not match.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum LsbMode {
+    /// Overwrite the least significant bit.
+    #[default]
+    Replace,
+    /// Randomly add or subtract one (LSB matching, also called ±1 embedding).
+    Match,
+}
+
+/// Largest Hamming code parameter tried by [`embed`] and [`extract`].
+pub const MAX_K: u8 = 16;
+
+/// Statistics about a finished embedding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EmbedStats {
+    /// Parameter of the (1, 2^k - 1, k) Hamming code used; 1 is plain LSB embedding.
+    pub k: u8,
+    /// Number of samples the message was spread over.
+    pub cover_samples: usize,
+    /// Number of samples that had to change.
+    pub changes: usize,
+}
+
+impl EmbedStats {
+    /// Returns the number of samples in one code block.
+    pub fn block_len(&self) -> usize {
+        block_len(self.k)
+    }
+
+    /// Returns the fraction of cover samples that changed.
+    pub fn change_rate(&self) -> f64 {
+        if self.cover_samples == 0 {
+            return 0.0;
+        }
+        self.changes as f64 / self.cover_samples as f64
+    }
+}
+
+/// Returns the number of payload bytes that fit into the buffer.
+pub fn capacity(pixels: &PixelBuffer) -> usize {
+    (pixels.samples().len() / 8).saturating_sub(HEADER_LEN)
+}
+
+/// Hides data in the least significant bits of the buffer's samples.
+///
+/// The largest Hamming code whose capacity still fits the sealed payload is
+/// picked automatically, so small payloads need far fewer changes.
+pub fn embed(pixels: &mut PixelBuffer, data: &[u8], mode: LsbMode) -> Result<EmbedStats> {
+    if data.len() > capacity(pixels) {
+        return Err(format!(
+            "Payload of {} bytes exceeds LSB capacity of {} bytes",
+            data.len(),
+            capacity(pixels)
+        )
+        .into());
+    }
+
+    let bits: Vec<u8> = envelope::bits(&envelope::seal(data)).collect();
+    let samples = pixels.samples().len();
+    let k = (1..=MAX_K)
+        .rev()
+        .find(|&k| bit_capacity(samples, k) >= bits.len())
+        .unwrap_or(1);
+    let n = block_len(k);
+
+    let max_value = pixels.max_value();
+    let mut rng = rand::rng();
+    let mut stats = EmbedStats {
+        k,
+        cover_samples: 0,
+        changes: 0,
+    };
+    for (block, message) in pixels
+        .samples_mut()
+        .chunks_exact_mut(n)
+        .zip(bits.chunks(k as usize))
+    {
+        // A short last group is padded with zero bits
+        let target = message
+            .iter()
+            .fold(0, |acc, &bit| (acc << 1) | bit as usize)
+            << (k as usize - message.len());
+        let position = syndrome(block) ^ target;
+        if position != 0 {
+            block[position - 1] = flip(block[position - 1], max_value, mode, &mut rng);
+            stats.changes += 1;
+        }
+        stats.cover_samples += n;
+    }
+    Ok(stats)
+}
+
+/// Extracts data previously hidden with [`embed`], trying every code size.
+pub fn extract(pixels: &PixelBuffer) -> Result<Vec<u8>> {
+    let samples = pixels.samples();
+    for k in 1..=MAX_K {
+        let capacity = bit_capacity(samples.len(), k);
+        if capacity < HEADER_LEN * 8 {
+            break;
+        }
+
+        let header = envelope::pack_bits(read_bits(samples, k).take(PROBE_LEN * 8));
+        let Ok(length) = envelope::message_length(&header) else {
+            continue;
+        };
+        if (HEADER_LEN + length) * 8 > capacity {
+            continue;
+        }
+        let message = envelope::pack_bits(read_bits(samples, k).take((HEADER_LEN + length) * 8));
+        return envelope::open(&message);
+    }
+    Err("No hidden data found".into())
+}
+
+/// Returns true if the buffer starts with an LSB payload header.
+pub fn has_hidden_data(pixels: &PixelBuffer) -> bool {
+    extract(pixels).is_ok()
+}
+
+/// Returns the number of samples in a block of the (1, 2^k - 1, k) Hamming code.
+fn block_len(k: u8) -> usize {
+    (1 << k) - 1
+}
+
+/// Returns the number of message bits a code can embed in the given number of samples.
+fn bit_capacity(samples: usize, k: u8) -> usize {
+    samples / block_len(k) * k as usize
+}
+
+/// Returns the XOR of the (1-based) positions of all samples with an odd value.
+fn syndrome(block: &[u16]) -> usize {
+    block
+        .iter()
+        .enumerate()
+        .filter(|(_, sample)| *sample & 1 == 1)
+        .fold(0, |acc, (i, _)| acc ^ (i + 1))
+}
+
+/// Reads k message bits from every block, most significant first.
+fn read_bits(samples: &[u16], k: u8) -> impl Iterator<Item = u8> + '_ {
+    samples.chunks_exact(block_len(k)).flat_map(move |block| {
+        let value = syndrome(block);
+        (0..k).rev().map(move |i| (value >> i) as u8 & 1)
+    })
+}
+
+/// Changes a sample by one so that its least significant bit flips, staying
+/// within `0..=max_value`.
+fn flip(sample: u16, max_value: u16, mode: LsbMode, rng: &mut impl Rng) -> u16 {
+    let up = match mode {
+        _ if sample == 0 => true,
+        _ if sample >= max_value => false,
+        LsbMode::Replace => sample & 1 == 0,
+        LsbMode::Match => rng.random_bool(0.5),
+    };
+    if up { sample + 1 } else { sample - 1 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn buffer(samples: usize) -> PixelBuffer {
+        let values = (0..samples).map(|i| (i * 37 % 256) as u16).collect();
+        PixelBuffer::new(samples as u32, 1, 1, 255, values).unwrap()
+    }
+
+    #[test]
+    fn test_embed_extract_roundtrip() {
+        let mut pixels = buffer(256);
+        embed(&mut pixels, b"hello", LsbMode::Replace).unwrap();
+
+        assert!(has_hidden_data(&pixels));
+        assert_eq!(extract(&pixels).unwrap(), b"hello");
+    }
+
+    #[test]
+    fn test_embed_changes_only_lsb() {
+        let original = buffer(256);
+        let mut pixels = original.clone();
+        embed(&mut pixels, b"hello", LsbMode::Replace).unwrap();
+
+        for (a, b) in original.samples().iter().zip(pixels.samples()) {
+            assert!(a.abs_diff(*b) <= 1);
+        }
+    }
+
+    #[test]
+    fn test_matching_roundtrip_and_clamping() {
+        // Every sample at 0 or the maximum must move inwards
+        let values = (0..256).map(|i| if i % 2 == 0 { 0 } else { 100 }).collect();
+        let original = PixelBuffer::new(256, 1, 1, 100, values).unwrap();
+        let mut pixels = original.clone();
+        embed(&mut pixels, &[0xff; 24], LsbMode::Match).unwrap();
+
+        assert_eq!(extract(&pixels).unwrap(), [0xff; 24]);
+        for (a, b) in original.samples().iter().zip(pixels.samples()) {
+            assert!(a.abs_diff(*b) <= 1 && *b <= 100);
+        }
+    }
+
+    #[test]
+    fn test_matching_moves_both_ways() {
+        let mut pixels = PixelBuffer::new(2048, 1, 1, 255, vec![128; 2048]).unwrap();
+        embed(&mut pixels, &[0xff; 200], LsbMode::Match).unwrap();
+
+        let samples = pixels.samples();
+        assert!(samples.contains(&127) && samples.contains(&129));
+    }
+
+    #[test]
+    fn test_small_payload_uses_matrix_code() {
+        let original = buffer(4096);
+        let mut pixels = original.clone();
+        let stats = embed(&mut pixels, b"ten bytes!", LsbMode::Replace).unwrap();
+
+        // 144 sealed bits fit into 32 blocks of 127 samples, but not 16 of 255
+        assert_eq!(stats.k, 7);
+        assert_eq!(stats.cover_samples, 21 * 127);
+        assert!(stats.changes <= 21);
+        let changed = original
+            .samples()
+            .iter()
+            .zip(pixels.samples())
+            .filter(|(a, b)| a != b)
+            .count();
+        assert_eq!(changed, stats.changes);
+        assert_eq!(extract(&pixels).unwrap(), b"ten bytes!");
+    }
+
+    #[test]
+    fn test_full_payload_uses_plain_lsb() {
+        let mut pixels = buffer(256);
+        let stats = embed(&mut pixels, &[0x3c; 24], LsbMode::Match).unwrap();
+
+        assert_eq!(stats.k, 1);
+        assert_eq!(stats.block_len(), 1);
+        assert!(stats.change_rate() < 1.0);
+        assert_eq!(extract(&pixels).unwrap(), [0x3c; 24]);
+    }
+
+    #[test]
+    fn test_syndrome() {
+        assert_eq!(syndrome(&[1, 0, 1]), 1 ^ 3);
+        assert_eq!(syndrome(&[2, 4, 6]), 0);
+    }
+
+    #[test]
+    fn test_capacity_exceeded() {
+        let mut pixels = buffer(128);
+        assert_eq!(capacity(&pixels), 8);
+        assert!(embed(&mut pixels, &[0; 9], LsbMode::Replace).is_err());
+    }
+
+    #[test]
+    fn test_no_hidden_data() {
+        let pixels = PixelBuffer::new(128, 1, 1, 255, vec![0; 128]).unwrap();
+        assert!(!has_hidden_data(&pixels));
+    }
+}
@@ -0,0 +1,10 @@
+//! Decoded pixel data shared by all carriers that embed in the sample domain.
+
+pub mod adaptive;
+pub mod lsb;
+pub mod planes;
+pub mod quality;
+
+mod buffer;
+
+pub use buffer::PixelBuffer;
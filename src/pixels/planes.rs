@@ -0,0 +1,169 @@
+//! Bit planes and difference maps for visual inspection.
+//!
+//! Natural images show their structure in the higher bit planes and mostly
+//! noise in the lowest one. Sequential LSB embedding leaves a visibly
+//! different band in that noise, and XORing two planes removes structure the
+//! planes share. The results are bilevel buffers (sample range 1) where a set
+//! bit or a changed pixel is white.
+
+use crate::Result;
+use crate::pixels::PixelBuffer;
+use std::fmt;
+use std::str::FromStr;
+
+/// One bit of one channel, written like `R0` or `Y7`.
+///
+/// Channels are named `R`, `G`, `B` and `A` for color images and `Y` for the
+/// gray channel; `A` is always the last channel of an image with alpha.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Plane {
+    pub channel: char,
+    pub bit: u8,
+}
+
+impl Plane {
+    /// Returns the index of the plane's channel in images with the given channel count.
+    pub fn channel_index(&self, channels: u8) -> Result<u8> {
+        let index = match (self.channel, channels) {
+            ('Y', 1 | 2) => Some(0),
+            ('R', 3 | 4) => Some(0),
+            ('G', 3 | 4) => Some(1),
+            ('B', 3 | 4) => Some(2),
+            ('A', 2 | 4) => Some(channels - 1),
+            _ => None,
+        };
+        index.ok_or_else(|| {
+            format!(
+                "An image with {} channels has no {} channel",
+                channels, self.channel
+            )
+            .into()
+        })
+    }
+}
+
+impl FromStr for Plane {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut chars = s.chars();
+        let channel = chars
+            .next()
+            .map(|c| c.to_ascii_uppercase())
+            .filter(|c| "RGBAY".contains(*c))
+            .ok_or_else(|| format!("Unknown channel in bit plane '{}'", s))?;
+        let bit: u8 = chars
+            .as_str()
+            .parse()
+            .ok()
+            .filter(|&bit| bit < 16)
+            .ok_or_else(|| format!("Bit plane '{}' needs a bit from 0 to 15", s))?;
+        Ok(Self { channel, bit })
+    }
+}
+
+impl fmt::Display for Plane {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.channel, self.bit)
+    }
+}
+
+/// Renders one bit plane, or the XOR of several, as a bilevel image.
+pub fn render(pixels: &PixelBuffer, planes: &[Plane]) -> Result<PixelBuffer> {
+    if planes.is_empty() {
+        return Err("No bit plane given".into());
+    }
+    let bits = 16 - pixels.max_value().leading_zeros();
+    let selected = planes
+        .iter()
+        .map(|plane| {
+            if plane.bit as u32 >= bits {
+                return Err(format!("Samples have no bit {} in plane {}", plane.bit, plane).into());
+            }
+            Ok((plane.channel_index(pixels.channels())? as usize, plane.bit))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let samples = pixels
+        .samples()
+        .chunks_exact(pixels.channels() as usize)
+        .map(|pixel| {
+            selected
+                .iter()
+                .fold(0, |acc, &(channel, bit)| acc ^ (pixel[channel] >> bit & 1))
+        })
+        .collect();
+    PixelBuffer::new(pixels.width(), pixels.height(), 1, 1, samples)
+}
+
+/// Marks every pixel where any sample differs between two images of the same layout.
+pub fn difference(cover: &PixelBuffer, stego: &PixelBuffer) -> Result<PixelBuffer> {
+    if cover.width() != stego.width()
+        || cover.height() != stego.height()
+        || cover.channels() != stego.channels()
+    {
+        return Err("Images differ in size or channel count".into());
+    }
+
+    let channels = cover.channels() as usize;
+    let samples = cover
+        .samples()
+        .chunks_exact(channels)
+        .zip(stego.samples().chunks_exact(channels))
+        .map(|(a, b)| u16::from(a != b))
+        .collect();
+    PixelBuffer::new(cover.width(), cover.height(), 1, 1, samples)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rgb() -> PixelBuffer {
+        // Two pixels: (R, G, B) = (1, 2, 3) and (4, 5, 7)
+        PixelBuffer::new(2, 1, 3, 255, vec![1, 2, 3, 4, 5, 7]).unwrap()
+    }
+
+    #[test]
+    fn test_parse_planes() {
+        assert_eq!(
+            "r0".parse::<Plane>().unwrap(),
+            Plane {
+                channel: 'R',
+                bit: 0
+            }
+        );
+        assert_eq!("A15".parse::<Plane>().unwrap().to_string(), "A15");
+        assert!("X0".parse::<Plane>().is_err());
+        assert!("G16".parse::<Plane>().is_err());
+        assert!("B".parse::<Plane>().is_err());
+    }
+
+    #[test]
+    fn test_render_and_xor() {
+        let plane = |s: &str| s.parse::<Plane>().unwrap();
+        let pixels = rgb();
+
+        assert_eq!(render(&pixels, &[plane("R0")]).unwrap().samples(), [1, 0]);
+        assert_eq!(render(&pixels, &[plane("B1")]).unwrap().samples(), [1, 1]);
+        assert_eq!(
+            render(&pixels, &[plane("R0"), plane("G0"), plane("B0")])
+                .unwrap()
+                .samples(),
+            [0, 0]
+        );
+        assert!(render(&pixels, &[plane("Y0")]).is_err());
+        assert!(render(&pixels, &[plane("R8")]).is_err());
+    }
+
+    #[test]
+    fn test_difference() {
+        let cover = rgb();
+        let mut stego = cover.clone();
+        stego.samples_mut()[4] ^= 1;
+
+        assert_eq!(difference(&cover, &stego).unwrap().samples(), [0, 1]);
+        let gray = PixelBuffer::new(2, 1, 1, 255, vec![0, 0]).unwrap();
+        assert!(difference(&cover, &gray).is_err());
+    }
+}
@@ -0,0 +1,172 @@
+//! How much embedding changed an image.
+//!
+//! The mean squared error and PSNR measure the size of the changes, while
+//! SSIM (structural similarity) measures how much they disturb local means,
+//! contrast and structure, which tracks visible damage more closely. Both
+//! are computed over all channels, using the real sample range as peak.
+
+use crate::Result;
+use crate::pixels::PixelBuffer;
+
+/// Side length of the SSIM windows in pixels.
+const WINDOW: usize = 8;
+
+/// Distance between the SSIM windows in pixels.
+const WINDOW_STEP: usize = 4;
+
+/// Differences between a cover image and its stego version.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quality {
+    /// Mean squared error per sample.
+    pub mse: f64,
+    /// Peak signal-to-noise ratio in decibels, infinite for identical images.
+    pub psnr: f64,
+    /// Mean structural similarity, 1 for identical images.
+    pub ssim: f64,
+    pub changed_samples: usize,
+    pub total_samples: usize,
+}
+
+impl Quality {
+    /// Returns the fraction of samples that changed.
+    pub fn changed_fraction(&self) -> f64 {
+        if self.total_samples == 0 {
+            0.0
+        } else {
+            self.changed_samples as f64 / self.total_samples as f64
+        }
+    }
+}
+
+/// Measures the differences between two images of the same layout.
+pub fn measure(cover: &PixelBuffer, stego: &PixelBuffer) -> Result<Quality> {
+    if cover.width() != stego.width()
+        || cover.height() != stego.height()
+        || cover.channels() != stego.channels()
+    {
+        return Err("Images differ in size or channel count".into());
+    }
+
+    let pairs = cover.samples().iter().zip(stego.samples());
+    let (squared, changed) = pairs.fold((0.0, 0), |(squared, changed), (&a, &b)| {
+        let difference = a as f64 - b as f64;
+        (
+            squared + difference * difference,
+            changed + usize::from(a != b),
+        )
+    });
+    let total_samples = cover.samples().len();
+    let mse = squared / total_samples.max(1) as f64;
+    let peak = cover.max_value().max(stego.max_value()) as f64;
+
+    Ok(Quality {
+        mse,
+        psnr: 10.0 * (peak * peak / mse).log10(),
+        ssim: ssim(cover, stego, peak),
+        changed_samples: changed,
+        total_samples,
+    })
+}
+
+/// Averages SSIM over overlapping windows of every channel.
+///
+/// Images smaller than a window are treated as a single window.
+fn ssim(cover: &PixelBuffer, stego: &PixelBuffer, peak: f64) -> f64 {
+    let (width, height) = (cover.width() as usize, cover.height() as usize);
+    let channels = cover.channels() as usize;
+    let c1 = (0.01 * peak).powi(2);
+    let c2 = (0.03 * peak).powi(2);
+    let starts = |size: usize| -> Vec<usize> {
+        if size <= WINDOW {
+            vec![0]
+        } else {
+            (0..=size - WINDOW).step_by(WINDOW_STEP).collect()
+        }
+    };
+
+    let mut sum = 0.0;
+    let mut windows = 0;
+    for channel in 0..channels {
+        for &y0 in &starts(height) {
+            for &x0 in &starts(width) {
+                let mut n = 0.0;
+                let (mut sa, mut sb, mut saa, mut sbb, mut sab) = (0.0, 0.0, 0.0, 0.0, 0.0);
+                for y in y0..(y0 + WINDOW).min(height) {
+                    for x in x0..(x0 + WINDOW).min(width) {
+                        let i = (y * width + x) * channels + channel;
+                        let (a, b) = (cover.samples()[i] as f64, stego.samples()[i] as f64);
+                        n += 1.0;
+                        sa += a;
+                        sb += b;
+                        saa += a * a;
+                        sbb += b * b;
+                        sab += a * b;
+                    }
+                }
+                if n == 0.0 {
+                    continue;
+                }
+                let (mean_a, mean_b) = (sa / n, sb / n);
+                let var_a = saa / n - mean_a * mean_a;
+                let var_b = sbb / n - mean_b * mean_b;
+                let covariance = sab / n - mean_a * mean_b;
+                sum += (2.0 * mean_a * mean_b + c1) * (2.0 * covariance + c2)
+                    / ((mean_a * mean_a + mean_b * mean_b + c1) * (var_a + var_b + c2));
+                windows += 1;
+            }
+        }
+    }
+    if windows == 0 {
+        1.0
+    } else {
+        sum / windows as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gradient() -> PixelBuffer {
+        let samples = (0..32 * 32 * 3).map(|i| (i % 200) as u16).collect();
+        PixelBuffer::new(32, 32, 3, 255, samples).unwrap()
+    }
+
+    #[test]
+    fn test_identical_images() {
+        let quality = measure(&gradient(), &gradient()).unwrap();
+        assert_eq!(quality.mse, 0.0);
+        assert_eq!(quality.psnr, f64::INFINITY);
+        assert!((quality.ssim - 1.0).abs() < 1e-12);
+        assert_eq!(quality.changed_samples, 0);
+    }
+
+    #[test]
+    fn test_lsb_changes() {
+        let cover = gradient();
+        let mut stego = cover.clone();
+        for sample in stego.samples_mut().iter_mut().step_by(4) {
+            *sample ^= 1;
+        }
+
+        let quality = measure(&cover, &stego).unwrap();
+        assert_eq!(quality.changed_samples, 768);
+        assert_eq!(quality.changed_fraction(), 0.25);
+        assert_eq!(quality.mse, 0.25);
+        assert!((quality.psnr - 54.15).abs() < 0.01);
+        assert!(quality.ssim > 0.99 && quality.ssim < 1.0);
+    }
+
+    #[test]
+    fn test_structural_damage() {
+        let cover = gradient();
+        let mut inverted = cover.clone();
+        for sample in inverted.samples_mut() {
+            *sample = 255 - *sample;
+        }
+        assert!(measure(&cover, &inverted).unwrap().ssim < 0.0);
+
+        let small = PixelBuffer::new(2, 2, 3, 255, vec![0; 12]).unwrap();
+        assert!(measure(&cover, &small).is_err());
+    }
+}
@@ -0,0 +1,437 @@
+//! Hiding data where transparency keeps it out of sight.
+//!
+//! - [`AlphaMode::Transparent`] only touches fully transparent pixels. Their
+//!   color samples are never displayed, so they are overwritten completely:
+//!   24 bits per pixel for 8-bit RGBA, 48 for 16-bit. In indexed images the
+//!   transparency comes from `tRNS`, so transparent pixels are remapped
+//!   between fully transparent palette entries instead, adding entries to the
+//!   palette when there are too few.
+//! - [`AlphaMode::Lsb`] writes to the least significant bits of the alpha
+//!   channel only, leaving the color channels untouched.
+
+use crate::Result;
+use crate::envelope::{self, HEADER_LEN};
+use crate::pixels::PixelBuffer;
+use crate::pixels::lsb::{self, EmbedStats, LsbMode};
+use crate::png::{Chunk, ChunkType, ImageHeader, Png, raster};
+use std::str::FromStr;
+
+/// Which samples of a transparent image carry the payload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum AlphaMode {
+    /// The color samples of fully transparent pixels.
+    Transparent,
+    /// The least significant bits of the alpha channel.
+    Lsb,
+}
+
+/// Returns the number of payload bytes the given mode can hide in a PNG.
+pub fn capacity(png: &Png, mode: AlphaMode) -> Result<usize> {
+    Ok((capacity_bits(png, mode)? / 8).saturating_sub(HEADER_LEN))
+}
+
+/// Hides data in a PNG using the given mode, replacing any earlier payload.
+///
+/// The LSB mode only applies to [`AlphaMode::Lsb`], which is also the only
+/// mode that returns embedding statistics.
+pub fn hide(
+    png: &mut Png,
+    data: &[u8],
+    mode: AlphaMode,
+    lsb_mode: LsbMode,
+) -> Result<Option<EmbedStats>> {
+    png.check_critical_chunks()?;
+    let message = envelope::seal(data);
+    if message.len() * 8 > capacity_bits(png, mode)? {
+        return Err(format!(
+            "Payload of {} bytes exceeds the alpha capacity of {} bytes",
+            data.len(),
+            capacity(png, mode)?
+        )
+        .into());
+    }
+
+    let header = ImageHeader::from_png(png)?;
+    let mut pixels = raster::decode(png)?;
+    let mut stats = None;
+
+    match (mode, alpha_channel(&header)) {
+        (AlphaMode::Transparent, Some(alpha)) => {
+            let positions = transparent_samples(&pixels, alpha);
+            let bytes_per_sample = header.bit_depth as usize / 8;
+            let samples = pixels.samples_mut();
+            for (&position, bytes) in positions.iter().zip(message.chunks(bytes_per_sample)) {
+                samples[position] = bytes
+                    .iter()
+                    .chain(std::iter::repeat(&0))
+                    .take(bytes_per_sample)
+                    .fold(0u16, |acc, &byte| (acc << 8) | byte as u16);
+            }
+        }
+        (AlphaMode::Transparent, None) => {
+            // Use as few bits per pixel as possible, adding palette entries if needed
+            let mut entries = transparent_entries(png, &header)?;
+            let count = transparent_pixels(&pixels, &entries);
+            let needed = (message.len() * 8).div_ceil(count).max(1);
+            if entries.len() < 1 << needed {
+                entries = add_transparent_entries(png, &header, (1 << needed) - entries.len())?;
+            }
+
+            let bits_per_pixel = entries.len().ilog2() as usize;
+            let mut bits = envelope::bits(&message);
+            for sample in pixels.samples_mut() {
+                if !entries.contains(&(*sample as usize)) {
+                    continue;
+                }
+                let group: Vec<u8> = bits.by_ref().take(bits_per_pixel).collect();
+                if group.is_empty() {
+                    break;
+                }
+                let value = group
+                    .iter()
+                    .chain(std::iter::repeat(&0))
+                    .take(bits_per_pixel)
+                    .fold(0usize, |acc, &bit| (acc << 1) | bit as usize);
+                *sample = entries[value] as u16;
+            }
+        }
+        (AlphaMode::Lsb, Some(alpha)) => {
+            let mut plane = pixels.channel(alpha)?;
+            stats = Some(lsb::embed(&mut plane, data, lsb_mode)?);
+            pixels.set_channel(alpha, &plane)?;
+        }
+        (AlphaMode::Lsb, None) => return Err(NO_ALPHA_CHANNEL.into()),
+    }
+
+    raster::encode(png, &pixels)?;
+    Ok(stats)
+}
+
+/// Extracts a payload hidden with either mode.
+pub fn extract(png: &Png) -> Result<Vec<u8>> {
+    let header = ImageHeader::from_png(png)?;
+    let pixels = raster::decode(png)?;
+
+    match alpha_channel(&header) {
+        Some(alpha) => {
+            let bytes_per_sample = header.bit_depth as usize / 8;
+            let samples = pixels.samples();
+            let message: Vec<u8> = transparent_samples(&pixels, alpha)
+                .into_iter()
+                .flat_map(|position| {
+                    samples[position].to_be_bytes()[2 - bytes_per_sample..].to_vec()
+                })
+                .collect();
+            envelope::open(&message).or_else(|_| lsb::extract(&pixels.channel(alpha)?))
+        }
+        None => {
+            let entries = transparent_entries(png, &header)?;
+            if entries.len() < 2 {
+                return Err("No hidden data found".into());
+            }
+            let bits_per_pixel = entries.len().ilog2() as usize;
+            let bits = pixels
+                .samples()
+                .iter()
+                .filter_map(|&sample| entries.iter().position(|&e| e == sample as usize))
+                .flat_map(|value| {
+                    let value = if value < 1 << bits_per_pixel {
+                        value
+                    } else {
+                        0
+                    };
+                    (0..bits_per_pixel)
+                        .rev()
+                        .map(move |i| (value >> i) as u8 & 1)
+                });
+            envelope::open(&envelope::pack_bits(bits))
+        }
+    }
+}
+
+/// Returns true if the transparent pixels or the alpha channel carry a payload.
+pub fn has_hidden_data(png: &Png) -> bool {
+    extract(png).is_ok()
+}
+
+/// Returns the number of bits the given mode can hide, including the envelope header.
+fn capacity_bits(png: &Png, mode: AlphaMode) -> Result<usize> {
+    let header = ImageHeader::from_png(png)?;
+    let pixels = raster::decode(png)?;
+
+    Ok(match (mode, alpha_channel(&header)) {
+        (AlphaMode::Transparent, Some(alpha)) => {
+            transparent_samples(&pixels, alpha).len() * header.bit_depth as usize
+        }
+        (AlphaMode::Transparent, None) => {
+            let entries = transparent_entries(png, &header)?;
+            let palette_len = png.chunk_by_type("PLTE").map_or(0, |c| c.data().len() / 3);
+            let available =
+                (entries.len() + (1usize << header.bit_depth)).saturating_sub(palette_len);
+            transparent_pixels(&pixels, &entries) * available.checked_ilog2().unwrap_or(0) as usize
+        }
+        (AlphaMode::Lsb, Some(alpha)) => pixels.channel(alpha)?.samples().len(),
+        (AlphaMode::Lsb, None) => return Err(NO_ALPHA_CHANNEL.into()),
+    })
+}
+
+const NO_ALPHA_CHANNEL: &str = "Alpha LSB embedding requires a PNG with an alpha channel";
+
+/// Returns the index of the alpha channel, if the color type has one.
+fn alpha_channel(header: &ImageHeader) -> Option<u8> {
+    match header.color_type {
+        ImageHeader::COLOR_GRAYSCALE_ALPHA => Some(1),
+        ImageHeader::COLOR_RGBA => Some(3),
+        _ => None,
+    }
+}
+
+/// Returns the positions of all color samples that belong to fully transparent pixels.
+fn transparent_samples(pixels: &PixelBuffer, alpha: u8) -> Vec<usize> {
+    let channels = pixels.channels() as usize;
+    pixels
+        .samples()
+        .chunks_exact(channels)
+        .enumerate()
+        .filter(|(_, pixel)| pixel[alpha as usize] == 0)
+        .flat_map(|(i, _)| (0..alpha as usize).map(move |c| i * channels + c))
+        .collect()
+}
+
+/// Returns the indices of fully transparent palette entries.
+fn transparent_entries(png: &Png, header: &ImageHeader) -> Result<Vec<usize>> {
+    if header.color_type != ImageHeader::COLOR_INDEXED {
+        return Err(
+            "Transparent-pixel embedding requires an alpha channel or a palette with tRNS".into(),
+        );
+    }
+    let palette_len = png.chunk_by_type("PLTE").map_or(0, |c| c.data().len() / 3);
+    Ok(png
+        .chunk_by_type("tRNS")
+        .map_or(&[][..], |chunk| chunk.data())
+        .iter()
+        .take(palette_len)
+        .enumerate()
+        .filter(|&(_, &alpha)| alpha == 0)
+        .map(|(i, _)| i)
+        .collect())
+}
+
+fn transparent_pixels(pixels: &PixelBuffer, entries: &[usize]) -> usize {
+    pixels
+        .samples()
+        .iter()
+        .filter(|&&sample| entries.contains(&(sample as usize)))
+        .count()
+}
+
+/// Appends fully transparent entries with distinct colors to the palette and
+/// returns the indices of all transparent entries.
+fn add_transparent_entries(
+    png: &mut Png,
+    header: &ImageHeader,
+    count: usize,
+) -> Result<Vec<usize>> {
+    let mut palette = png
+        .chunk_by_type("PLTE")
+        .ok_or("PNG has no PLTE chunk")?
+        .data()
+        .to_vec();
+    let palette_len = palette.len() / 3;
+    if palette_len + count > 1 << header.bit_depth {
+        return Err("Palette has no room for more transparent entries".into());
+    }
+
+    let mut alphas = png
+        .chunk_by_type("tRNS")
+        .map_or(Vec::new(), |chunk| chunk.data().to_vec());
+    alphas.resize(palette_len, 255);
+
+    let existing = transparent_entries(png, header)?;
+    let used: Vec<&[u8]> = existing
+        .iter()
+        .map(|&i| &palette[i * 3..i * 3 + 3])
+        .collect();
+    let colors: Vec<[u8; 3]> = (0u32..)
+        .map(|v| [(v >> 16) as u8, (v >> 8) as u8, v as u8])
+        .filter(|color| !used.contains(&&color[..]))
+        .take(count)
+        .collect();
+
+    for color in colors {
+        palette.extend_from_slice(&color);
+        alphas.push(0);
+    }
+    png.replace_chunk(Chunk::new(ChunkType::from_str("PLTE")?, palette))?;
+    png.replace_chunk(Chunk::new(ChunkType::from_str("tRNS")?, alphas))?;
+    transparent_entries(png, header)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn testing_png(color_type: u8, bit_depth: u8, extra: Vec<Chunk>) -> Png {
+        let header = ImageHeader {
+            width: 16,
+            height: 16,
+            bit_depth,
+            color_type,
+            compression_method: 0,
+            filter_method: 0,
+            interlace_method: 0,
+        };
+        let chunk = |t: &str, d: &[u8]| Chunk::new(ChunkType::from_str(t).unwrap(), d.to_vec());
+        let stream = miniz_oxide::deflate::compress_to_vec_zlib(&[], 6);
+        let mut chunks = vec![chunk("IHDR", &header.as_bytes())];
+        chunks.extend(extra);
+        chunks.push(chunk("IDAT", &stream));
+        chunks.push(chunk("IEND", b""));
+
+        // Fill the left half with opaque gradients and leave the right half transparent
+        let mut png = Png::from_chunks(chunks);
+        let channels = header.channels() as usize;
+        let max_value = ((1u32 << bit_depth) - 1) as u16;
+        let samples = (0..256)
+            .flat_map(|i| {
+                let visible = i % 16 < 8;
+                (0..channels).map(move |c| match (color_type, c) {
+                    (ImageHeader::COLOR_INDEXED, _) => (visible as u16) * (1 + i as u16 % 3),
+                    (_, c) if c == channels - 1 => visible as u16 * max_value,
+                    _ => (i * 3 + c) as u16 % 200,
+                })
+            })
+            .collect();
+        let pixels = PixelBuffer::new(16, 16, header.channels(), max_value, samples).unwrap();
+        raster::encode(&mut png, &pixels).unwrap();
+        png
+    }
+
+    fn indexed_png() -> Png {
+        let chunk = |t: &str, d: &[u8]| Chunk::new(ChunkType::from_str(t).unwrap(), d.to_vec());
+        testing_png(
+            ImageHeader::COLOR_INDEXED,
+            8,
+            vec![
+                chunk("PLTE", &[0, 0, 0, 255, 0, 0, 0, 255, 0, 0, 0, 255]),
+                chunk("tRNS", &[0]),
+            ],
+        )
+    }
+
+    /// Returns the pixels as they would be displayed, with transparent ones blanked.
+    fn visible(png: &Png) -> Vec<u16> {
+        let header = ImageHeader::from_png(png).unwrap();
+        let pixels = raster::decode(png).unwrap();
+        let channels = pixels.channels() as usize;
+        match alpha_channel(&header) {
+            Some(alpha) => pixels
+                .samples()
+                .chunks_exact(channels)
+                .flat_map(|p| {
+                    if p[alpha as usize] == 0 {
+                        vec![0; channels]
+                    } else {
+                        p.to_vec()
+                    }
+                })
+                .collect(),
+            None => {
+                let transparent = transparent_entries(png, &header).unwrap();
+                pixels
+                    .samples()
+                    .iter()
+                    .map(|&s| {
+                        if transparent.contains(&(s as usize)) {
+                            0
+                        } else {
+                            s
+                        }
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    #[test]
+    fn test_transparent_rgba_roundtrip() {
+        for bit_depth in [8, 16] {
+            let original = testing_png(ImageHeader::COLOR_RGBA, bit_depth, vec![]);
+            let mut png = original.clone();
+            let data = vec![0xa5; capacity(&png, AlphaMode::Transparent).unwrap()];
+            hide(&mut png, &data, AlphaMode::Transparent, LsbMode::Replace).unwrap();
+
+            assert_eq!(visible(&png), visible(&original));
+            assert_eq!(extract(&png).unwrap(), data);
+            assert!(
+                hide(
+                    &mut png,
+                    &[data, vec![0]].concat(),
+                    AlphaMode::Transparent,
+                    LsbMode::Replace
+                )
+                .is_err()
+            );
+        }
+    }
+
+    #[test]
+    fn test_alpha_lsb_roundtrip() {
+        let original = testing_png(ImageHeader::COLOR_GRAYSCALE_ALPHA, 8, vec![]);
+        let mut png = original.clone();
+        hide(&mut png, b"alpha bits", AlphaMode::Lsb, LsbMode::Match).unwrap();
+
+        let before = raster::decode(&original).unwrap();
+        let after = raster::decode(&png).unwrap();
+        assert_eq!(before.channel(0).unwrap(), after.channel(0).unwrap());
+        assert_eq!(extract(&png).unwrap(), b"alpha bits");
+    }
+
+    #[test]
+    fn test_output_passes_validation() {
+        for (mut png, mode) in [
+            (
+                testing_png(ImageHeader::COLOR_RGBA, 8, vec![]),
+                AlphaMode::Transparent,
+            ),
+            (
+                testing_png(ImageHeader::COLOR_RGBA, 8, vec![]),
+                AlphaMode::Lsb,
+            ),
+            (indexed_png(), AlphaMode::Transparent),
+        ] {
+            hide(&mut png, b"secret", mode, LsbMode::Replace).unwrap();
+            assert_eq!(png.validate(), []);
+        }
+    }
+
+    #[test]
+    fn test_indexed_transparency_adds_entries() {
+        let original = indexed_png();
+        let mut png = original.clone();
+
+        // 128 transparent pixels need 2 bits each for 26 sealed bytes
+        hide(&mut png, &[7; 18], AlphaMode::Transparent, LsbMode::Replace).unwrap();
+
+        assert_eq!(
+            transparent_entries(&png, &ImageHeader::from_png(&png).unwrap())
+                .unwrap()
+                .len(),
+            4
+        );
+        assert_eq!(visible(&png), visible(&original));
+        assert_eq!(extract(&png).unwrap(), [7; 18]);
+    }
+
+    #[test]
+    fn test_unsupported_modes() {
+        let mut rgb = testing_png(ImageHeader::COLOR_RGB, 8, vec![]);
+        assert!(hide(&mut rgb, b"x", AlphaMode::Transparent, LsbMode::Replace).is_err());
+        assert!(hide(&mut indexed_png(), b"x", AlphaMode::Lsb, LsbMode::Replace).is_err());
+        assert!(!has_hidden_data(&testing_png(
+            ImageHeader::COLOR_RGBA,
+            8,
+            vec![]
+        )));
+    }
+}
@@ -29,6 +29,19 @@ impl Chunk {
         chunk
     }
 
+    /// Creates a chunk that keeps the given CRC, even if it does not match.
+    ///
+    /// A damaged chunk is then written back as it was read, instead of having
+    /// its damage hidden behind a fresh CRC.
+    pub(crate) fn with_crc(chunk_type: ChunkType, data: Vec<u8>, crc: u32) -> Self {
+        Self {
+            length: data.len() as u32,
+            chunk_type,
+            data,
+            crc,
+        }
+    }
+
     /// Returns the length of the chunk data.
     pub fn length(&self) -> u32 {
         self.length
@@ -49,6 +62,11 @@ impl Chunk {
         self.crc
     }
 
+    /// Returns true if the CRC matches the chunk type and data.
+    pub fn has_valid_crc(&self) -> bool {
+        self.crc == self.calculate_crc()
+    }
+
     /// Returns the chunk data as a UTF-8 string, or an error if invalid.
     pub fn data_as_string(&self) -> Result<String> {
         String::from_utf8(self.data.clone()).map_err(|e| e.into())
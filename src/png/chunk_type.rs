@@ -16,11 +16,6 @@ impl ChunkType {
         self.bytes
     }
 
-    /// Returns the chunk type as a string.
-    pub fn to_string(&self) -> String {
-        self.bytes.iter().map(|b| char::from(*b)).collect()
-    }
-
     /// Returns true if the chunk is critical (first byte uppercase).
     pub fn is_critical(&self) -> bool {
         self.bytes[0] & 0b00100000 == 0b00000000
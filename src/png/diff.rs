@@ -0,0 +1,364 @@
+//! Differences between the chunk lists of two PNGs.
+//!
+//! Chunks are matched by type and occurrence: the n-th `tEXt` chunk of one
+//! file is compared with the n-th `tEXt` chunk of the other. Whatever has no
+//! counterpart was added or removed. Matched chunks that are outside the
+//! longest run kept in the same relative order count as reordered.
+
+use crate::png::Png;
+
+/// Most differing rows shown by [`hex_diff`].
+const HEX_ROWS: usize = 16;
+
+/// Bytes per row of [`hex_diff`].
+const HEX_WIDTH: usize = 16;
+
+/// How a chunk differs between two files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    Unchanged,
+    Modified,
+    /// Same data, but moved relative to the other chunks.
+    Reordered,
+    Added,
+    Removed,
+}
+
+/// One chunk compared between two files.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+    pub kind: ChangeKind,
+    pub chunk_type: String,
+    /// Index in the old file's chunks, if the chunk is there.
+    pub old_index: Option<usize>,
+    /// Index in the new file's chunks, if the chunk is there.
+    pub new_index: Option<usize>,
+    /// Byte offset in the old file, if the chunk is there.
+    pub old_offset: Option<usize>,
+    /// Byte offset in the new file, if the chunk is there.
+    pub new_offset: Option<usize>,
+    /// Data length in the old file, if the chunk is there.
+    pub old_length: Option<u32>,
+    /// Data length in the new file, if the chunk is there.
+    pub new_length: Option<u32>,
+    /// Whether a matched chunk left its relative place, even if its data changed too.
+    pub moved: bool,
+}
+
+impl Change {
+    /// Describes the change in one line, like `+ vEiL at 1234 (25 bytes)`.
+    pub fn to_text(&self) -> String {
+        let length = |length: Option<u32>| length.unwrap_or_default();
+        let offset = match (self.old_offset, self.new_offset) {
+            (Some(old), Some(new)) if old != new => format!("at {} -> {}", old, new),
+            (_, Some(offset)) | (Some(offset), None) => format!("at {}", offset),
+            (None, None) => String::new(),
+        };
+        match self.kind {
+            ChangeKind::Unchanged => format!(
+                "  {} {} ({} bytes)",
+                self.chunk_type,
+                offset,
+                length(self.new_length)
+            ),
+            ChangeKind::Modified => format!(
+                "~ {} {} ({} -> {} bytes{})",
+                self.chunk_type,
+                offset,
+                length(self.old_length),
+                length(self.new_length),
+                if self.moved { ", moved" } else { "" }
+            ),
+            ChangeKind::Reordered => format!(
+                "> {} {} ({} bytes, moved)",
+                self.chunk_type,
+                offset,
+                length(self.new_length)
+            ),
+            ChangeKind::Added => format!(
+                "+ {} {} ({} bytes)",
+                self.chunk_type,
+                offset,
+                length(self.new_length)
+            ),
+            ChangeKind::Removed => format!(
+                "- {} {} ({} bytes)",
+                self.chunk_type,
+                offset,
+                length(self.old_length)
+            ),
+        }
+    }
+}
+
+/// Compares the chunks of two PNGs, in the order of the new file followed
+/// by the removed chunks.
+pub fn diff(old: &Png, new: &Png) -> Vec<Change> {
+    let old_offsets = old.chunk_offsets();
+    let new_offsets = new.chunk_offsets();
+    let mut matched = vec![false; old.chunks().len()];
+    let mut counterparts = Vec::with_capacity(new.chunks().len());
+
+    for chunk in new.chunks() {
+        let counterpart = old
+            .chunks()
+            .iter()
+            .enumerate()
+            .position(|(i, other)| !matched[i] && other.chunk_type() == chunk.chunk_type());
+        if let Some(i) = counterpart {
+            matched[i] = true;
+        }
+        counterparts.push(counterpart);
+    }
+    let moved = moved_chunks(&counterparts);
+
+    let mut changes = Vec::new();
+    for (j, chunk) in new.chunks().iter().enumerate() {
+        let old_index = counterparts[j];
+        let kind = match old_index {
+            Some(i) if old.chunks()[i].data() != chunk.data() => ChangeKind::Modified,
+            Some(_) if moved[j] => ChangeKind::Reordered,
+            Some(_) => ChangeKind::Unchanged,
+            None => ChangeKind::Added,
+        };
+        changes.push(Change {
+            kind,
+            chunk_type: chunk.chunk_type().to_string(),
+            old_index,
+            new_index: Some(j),
+            old_offset: old_index.map(|i| old_offsets[i]),
+            new_offset: Some(new_offsets[j]),
+            old_length: old_index.map(|i| old.chunks()[i].length()),
+            new_length: Some(chunk.length()),
+            moved: moved[j],
+        });
+    }
+
+    for (i, chunk) in old
+        .chunks()
+        .iter()
+        .enumerate()
+        .filter(|&(i, _)| !matched[i])
+    {
+        changes.push(Change {
+            kind: ChangeKind::Removed,
+            chunk_type: chunk.chunk_type().to_string(),
+            old_index: Some(i),
+            new_index: None,
+            old_offset: Some(old_offsets[i]),
+            new_offset: None,
+            old_length: Some(chunk.length()),
+            new_length: None,
+            moved: false,
+        });
+    }
+    changes
+}
+
+/// Flags the matched chunks outside the longest increasing run of old indices.
+fn moved_chunks(counterparts: &[Option<usize>]) -> Vec<bool> {
+    let matched: Vec<(usize, usize)> = counterparts
+        .iter()
+        .enumerate()
+        .filter_map(|(j, i)| i.map(|i| (j, i)))
+        .collect();
+
+    // Longest increasing subsequence, quadratic but chunk lists are short
+    let mut length = vec![1; matched.len()];
+    let mut previous = vec![None; matched.len()];
+    for b in 0..matched.len() {
+        for a in 0..b {
+            if matched[a].1 < matched[b].1 && length[a] + 1 > length[b] {
+                length[b] = length[a] + 1;
+                previous[b] = Some(a);
+            }
+        }
+    }
+
+    let mut moved = vec![false; counterparts.len()];
+    for &(j, _) in &matched {
+        moved[j] = true;
+    }
+    let mut last = (0..matched.len()).max_by_key(|&b| (length[b], std::cmp::Reverse(b)));
+    while let Some(b) = last {
+        moved[matched[b].0] = false;
+        last = previous[b];
+    }
+    moved
+}
+
+/// Shows the rows of two byte strings that differ, as `-` and `+` lines of
+/// offset, hex and ASCII.
+pub fn hex_diff(old: &[u8], new: &[u8]) -> Vec<String> {
+    let rows = old.len().max(new.len()).div_ceil(HEX_WIDTH);
+    fn row(data: &[u8], start: usize) -> &[u8] {
+        &data[start.min(data.len())..(start + HEX_WIDTH).min(data.len())]
+    }
+
+    let mut lines = Vec::new();
+    let mut differing = 0;
+    for start in (0..rows).map(|r| r * HEX_WIDTH) {
+        let (a, b) = (row(old, start), row(new, start));
+        if a == b {
+            continue;
+        }
+        differing += 1;
+        if differing > HEX_ROWS {
+            continue;
+        }
+        if !a.is_empty() {
+            lines.push(format!("- {:08x}  {}", start, hex_row(a)));
+        }
+        if !b.is_empty() {
+            lines.push(format!("+ {:08x}  {}", start, hex_row(b)));
+        }
+    }
+    if differing > HEX_ROWS {
+        lines.push(format!(
+            "  ... {} more differing rows",
+            differing - HEX_ROWS
+        ));
+    }
+    lines
+}
+
+/// Formats up to one row of bytes as padded hex followed by printable ASCII.
+fn hex_row(bytes: &[u8]) -> String {
+    let hex: Vec<String> = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    let ascii: String = bytes
+        .iter()
+        .map(|&b| {
+            if b.is_ascii_graphic() || b == b' ' {
+                b as char
+            } else {
+                '.'
+            }
+        })
+        .collect();
+    format!(
+        "{:<width$}  |{}|",
+        hex.join(" "),
+        ascii,
+        width = HEX_WIDTH * 3 - 1
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::png::{Chunk, ChunkType};
+    use std::str::FromStr;
+
+    fn png(chunks: &[(&str, &[u8])]) -> Png {
+        Png::from_chunks(
+            chunks
+                .iter()
+                .map(|(t, d)| Chunk::new(ChunkType::from_str(t).unwrap(), d.to_vec()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_diff_chunk_lists() {
+        let old = png(&[
+            ("IHDR", b"header"),
+            ("tEXt", b"one"),
+            ("tEXt", b"two"),
+            ("IDAT", b"pixels"),
+            ("IEND", b""),
+        ]);
+        let new = png(&[
+            ("IHDR", b"header"),
+            ("tEXt", b"one"),
+            ("IDAT", b"pixels!"),
+            ("vEiL", b"payload"),
+            ("IEND", b""),
+        ]);
+
+        let lines: Vec<String> = diff(&old, &new).iter().map(Change::to_text).collect();
+        assert_eq!(
+            lines,
+            [
+                "  IHDR at 8 (6 bytes)",
+                "  tEXt at 26 (3 bytes)",
+                "~ IDAT at 56 -> 41 (6 -> 7 bytes)",
+                "+ vEiL at 60 (7 bytes)",
+                "  IEND at 74 -> 79 (0 bytes)",
+                "- tEXt at 41 (3 bytes)",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reordered_chunks() {
+        let old = png(&[
+            ("IHDR", b"header"),
+            ("tEXt", b"one"),
+            ("gAMA", b"gama"),
+            ("IDAT", b"pixels"),
+            ("IEND", b""),
+        ]);
+        let new = png(&[
+            ("IHDR", b"header"),
+            ("gAMA", b"gama"),
+            ("IDAT", b"pixels"),
+            ("tEXt", b"one"),
+            ("IEND", b""),
+        ]);
+
+        let changes = diff(&old, &new);
+        let kinds: Vec<ChangeKind> = changes.iter().map(|c| c.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                ChangeKind::Unchanged,
+                ChangeKind::Unchanged,
+                ChangeKind::Unchanged,
+                ChangeKind::Reordered,
+                ChangeKind::Unchanged,
+            ]
+        );
+        assert_eq!(changes[3].to_text(), "> tEXt at 26 -> 60 (3 bytes, moved)");
+    }
+
+    #[test]
+    fn test_identical_files() {
+        let a = png(&[("IHDR", b"header"), ("IEND", b"")]);
+        assert!(
+            diff(&a, &a.clone())
+                .iter()
+                .all(|c| c.kind == ChangeKind::Unchanged)
+        );
+    }
+
+    #[test]
+    fn test_hex_diff() {
+        let old = b"0123456789abcdef0123456789abcdef";
+        let mut new = old.to_vec();
+        new[17] = 0;
+        new.push(b'!');
+
+        assert_eq!(
+            hex_diff(old, &new),
+            [
+                format!(
+                    "- 00000010  {}  |0123456789abcdef|",
+                    hex("0123456789abcdef")
+                ),
+                format!(
+                    "+ 00000010  30 00{}  |0.23456789abcdef|",
+                    &hex("0123456789abcdef")[5..]
+                ),
+                format!("+ 00000020  21{}  |!|", " ".repeat(45)),
+            ]
+        );
+        assert!(hex_diff(old, old).is_empty());
+    }
+
+    fn hex(s: &str) -> String {
+        s.bytes()
+            .map(|b| format!("{:02x}", b))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
@@ -0,0 +1,360 @@
+//! Hiding data inside the compressed image data stream.
+//!
+//! Tools that sanitize PNGs usually drop unknown ancillary chunks but keep
+//! `IDAT` untouched. Two placements survive that while leaving the decoded
+//! pixels unchanged:
+//!
+//! - [`IdatMode::Trailing`] appends the sealed payload after the end of the
+//!   zlib stream (after its Adler-32 checksum) in the last `IDAT` chunk.
+//!   Decoders stop at the end of the stream and never look at these bytes.
+//! - [`IdatMode::StoredBlock`] re-encodes the image data without a final
+//!   block, then adds the payload as stored deflate blocks followed by an
+//!   empty final stored block. The zlib stream stays fully valid (including
+//!   its checksum); decoders stop reading once they have every scanline and
+//!   ignore the surplus decompressed bytes.
+
+use crate::Result;
+use crate::envelope;
+use crate::png::{Chunk, ChunkType, ImageHeader, Png};
+use miniz_oxide::deflate::core::{CompressorOxide, create_comp_flags_from_zip_params};
+use miniz_oxide::inflate::stream::InflateState;
+use miniz_oxide::{DataFormat, MZFlush, MZStatus};
+use std::str::FromStr;
+
+/// Most decompressed bytes accepted beyond what the header requires, so that
+/// a small crafted stream cannot exhaust memory.
+const MAX_SURPLUS: usize = 16 * 1024 * 1024;
+
+/// Where a payload is placed inside the `IDAT` stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum IdatMode {
+    /// After the end of the zlib stream.
+    Trailing,
+    /// In stored deflate blocks after the image data.
+    StoredBlock,
+}
+
+/// The concatenated `IDAT` data split into its parts.
+#[derive(Debug, Clone)]
+pub struct ImageData {
+    /// The complete zlib stream as stored in the file.
+    pub stream: Vec<u8>,
+    /// Length of the zlib stream, up to and including the Adler-32 checksum.
+    pub stream_len: usize,
+    /// The decompressed data.
+    pub inflated: Vec<u8>,
+}
+
+impl ImageData {
+    /// Reads and decompresses the image data of a PNG.
+    ///
+    /// Fails if the data inflates to more than [`MAX_SURPLUS`] bytes beyond
+    /// what the header requires.
+    pub fn from_png(png: &Png) -> Result<Self> {
+        let limit = ImageHeader::from_png(png)?
+            .raw_data_len()
+            .saturating_add(MAX_SURPLUS);
+        let stream: Vec<u8> = png
+            .chunks()
+            .iter()
+            .filter(|chunk| chunk.chunk_type().to_string() == "IDAT")
+            .flat_map(|chunk| chunk.data().iter().copied())
+            .collect();
+        if stream.is_empty() {
+            return Err("PNG has no image data".into());
+        }
+
+        let (inflated, stream_len) = inflate(&stream, limit)?;
+        Ok(Self {
+            stream,
+            stream_len,
+            inflated,
+        })
+    }
+
+    /// Returns the bytes stored after the end of the zlib stream.
+    pub fn trailing(&self) -> &[u8] {
+        &self.stream[self.stream_len..]
+    }
+}
+
+/// Hides data in the image data stream of a PNG, replacing any earlier payload.
+pub fn hide(png: &mut Png, data: &[u8], mode: IdatMode) -> Result<()> {
+    png.check_critical_chunks()?;
+    let header = ImageHeader::from_png(png)?;
+    let image = ImageData::from_png(png)?;
+    let raw_len = header.raw_data_len();
+    if image.inflated.len() < raw_len {
+        return Err("PNG image data is shorter than its header requires".into());
+    }
+
+    let sealed = envelope::seal(data);
+    if mode == IdatMode::StoredBlock && sealed.len() > MAX_SURPLUS {
+        return Err(format!(
+            "Data too large: stored blocks hold at most {} bytes",
+            MAX_SURPLUS
+        )
+        .into());
+    }
+    let stream = match mode {
+        // Keep the original compressed bytes unless they already carry a stored payload
+        IdatMode::Trailing if image.inflated.len() == raw_len => {
+            let mut stream = image.stream[..image.stream_len].to_vec();
+            stream.extend_from_slice(&sealed);
+            stream
+        }
+        IdatMode::Trailing => {
+            let mut stream = deflate_with_stored(&image.inflated[..raw_len], &[])?;
+            stream.extend_from_slice(&sealed);
+            stream
+        }
+        IdatMode::StoredBlock => deflate_with_stored(&image.inflated[..raw_len], &sealed)?,
+    };
+
+    replace_image_data(png, stream)
+}
+
+/// Extracts a payload hidden with either mode.
+pub fn extract(png: &Png) -> Result<Vec<u8>> {
+    let header = ImageHeader::from_png(png)?;
+    let image = ImageData::from_png(png)?;
+
+    envelope::open(image.trailing()).or_else(|_| {
+        let surplus = image.inflated.get(header.raw_data_len()..).unwrap_or(&[]);
+        envelope::open(surplus)
+    })
+}
+
+/// Returns true if the image data stream carries a payload.
+pub fn has_hidden_data(png: &Png) -> bool {
+    extract(png).is_ok()
+}
+
+/// Decompresses a zlib stream of at most `limit` bytes, returning the output
+/// and the number of bytes the stream occupies.
+fn inflate(stream: &[u8], limit: usize) -> Result<(Vec<u8>, usize)> {
+    let mut state = InflateState::new_boxed(DataFormat::Zlib);
+    let mut output = Vec::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+    let mut consumed = 0;
+
+    loop {
+        let result = miniz_oxide::inflate::stream::inflate(
+            &mut state,
+            &stream[consumed..],
+            &mut buffer,
+            MZFlush::None,
+        );
+        consumed += result.bytes_consumed;
+        output.extend_from_slice(&buffer[..result.bytes_written]);
+        if output.len() > limit {
+            return Err("PNG image data inflates to more than its header allows".into());
+        }
+
+        match result.status {
+            Ok(MZStatus::StreamEnd) => return Ok((output, consumed)),
+            Ok(_) if result.bytes_consumed > 0 || result.bytes_written > 0 => {}
+            _ => return Err("PNG image data is not a complete zlib stream".into()),
+        }
+    }
+}
+
+/// Compresses data into a zlib stream whose deflate part ends with the given
+/// bytes in stored blocks and an empty final stored block.
+fn deflate_with_stored(data: &[u8], stored: &[u8]) -> Result<Vec<u8>> {
+    // Raw deflate (negative window bits) with a sync flush leaves the stream
+    // byte-aligned and without a final block, so stored blocks can follow
+    let flags = create_comp_flags_from_zip_params(6, -15, 0);
+    let mut compressor = CompressorOxide::new(flags);
+    let mut stream = vec![0x78, 0x9c];
+    let mut buffer = vec![0u8; 64 * 1024];
+    let mut input = data;
+
+    loop {
+        let result = miniz_oxide::deflate::stream::deflate(
+            &mut compressor,
+            input,
+            &mut buffer,
+            MZFlush::Sync,
+        );
+        stream.extend_from_slice(&buffer[..result.bytes_written]);
+        input = &input[result.bytes_consumed..];
+        result
+            .status
+            .map_err(|_| "Failed to compress PNG image data")?;
+        if input.is_empty() && result.bytes_written < buffer.len() {
+            break;
+        }
+    }
+
+    for block in stored.chunks(u16::MAX as usize) {
+        let length = block.len() as u16;
+        stream.push(0x00); // BFINAL = 0, BTYPE = stored
+        stream.extend_from_slice(&length.to_le_bytes());
+        stream.extend_from_slice(&(!length).to_le_bytes());
+        stream.extend_from_slice(block);
+    }
+    stream.extend_from_slice(&[0x01, 0x00, 0x00, 0xff, 0xff]); // final empty stored block
+
+    let mut checked = data.to_vec();
+    checked.extend_from_slice(stored);
+    stream.extend_from_slice(&adler32(&checked).to_be_bytes());
+    Ok(stream)
+}
+
+/// Replaces the `IDAT` chunks with the given stream.
+///
+/// The sizes of all but the last original chunk are kept where possible, so
+/// that only the last `IDAT` changes when data is appended to the stream.
+/// Unknown unsafe-to-copy chunks are dropped, as they may depend on the old data.
+pub(crate) fn replace_image_data(png: &mut Png, stream: Vec<u8>) -> Result<()> {
+    let is_idat = |chunk: &Chunk| chunk.chunk_type().to_string() == "IDAT";
+    let first = png
+        .chunks()
+        .iter()
+        .position(is_idat)
+        .ok_or("PNG has no image data")?;
+    let sizes: Vec<usize> = png
+        .chunks()
+        .iter()
+        .filter(|chunk| is_idat(chunk))
+        .map(|chunk| chunk.data().len())
+        .collect();
+
+    while png.remove_first_chunk("IDAT").is_ok() {}
+
+    let mut pieces = Vec::new();
+    let mut rest = stream.as_slice();
+    for &size in &sizes[..sizes.len() - 1] {
+        if size >= rest.len() {
+            break;
+        }
+        let (piece, remainder) = rest.split_at(size);
+        pieces.push(piece);
+        rest = remainder;
+    }
+    pieces.push(rest);
+
+    for (i, piece) in pieces.into_iter().enumerate() {
+        let chunk = Chunk::new(ChunkType::from_str("IDAT")?, piece.to_vec());
+        png.insert_chunk(first + i, chunk);
+    }
+    png.remove_unsafe_to_copy();
+    Ok(())
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An 8x4 RGB image split over two `IDAT` chunks.
+    fn testing_png() -> Png {
+        let header = ImageHeader {
+            width: 8,
+            height: 4,
+            bit_depth: 8,
+            color_type: ImageHeader::COLOR_RGB,
+            compression_method: 0,
+            filter_method: 0,
+            interlace_method: 0,
+        };
+        let raw: Vec<u8> = (0..header.raw_data_len())
+            .map(|i| (i * 7 % 251) as u8)
+            .collect();
+        let stream = miniz_oxide::deflate::compress_to_vec_zlib(&raw, 6);
+        let (a, b) = stream.split_at(stream.len() / 2);
+
+        let chunk = |t: &str, d: &[u8]| Chunk::new(ChunkType::from_str(t).unwrap(), d.to_vec());
+        Png::from_chunks(vec![
+            chunk("IHDR", &header.as_bytes()),
+            chunk("IDAT", a),
+            chunk("IDAT", b),
+            chunk("IEND", b""),
+        ])
+    }
+
+    fn decoded(png: &Png) -> Vec<u8> {
+        let raw_len = ImageHeader::from_png(png).unwrap().raw_data_len();
+        ImageData::from_png(png).unwrap().inflated[..raw_len].to_vec()
+    }
+
+    #[test]
+    fn test_adler32_known_value() {
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+    }
+
+    #[test]
+    fn test_trailing_roundtrip() {
+        let original = testing_png();
+        let mut png = original.clone();
+        hide(&mut png, b"after the stream", IdatMode::Trailing).unwrap();
+
+        // Only the last IDAT changes, and the pixels stay the same
+        assert_eq!(png.chunks()[1].data(), original.chunks()[1].data());
+        assert_eq!(decoded(&png), decoded(&original));
+        assert_eq!(extract(&png).unwrap(), b"after the stream");
+    }
+
+    #[test]
+    fn test_stored_block_roundtrip() {
+        let original = testing_png();
+        let mut png = original.clone();
+        hide(&mut png, &vec![0x5a; 70_000], IdatMode::StoredBlock).unwrap();
+
+        let image = ImageData::from_png(&png).unwrap();
+        assert!(image.trailing().is_empty());
+        assert_eq!(decoded(&png), decoded(&original));
+        assert_eq!(extract(&png).unwrap(), vec![0x5a; 70_000]);
+    }
+
+    #[test]
+    fn test_switching_modes_replaces_payload() {
+        let mut png = testing_png();
+        hide(&mut png, b"first", IdatMode::StoredBlock).unwrap();
+        hide(&mut png, b"second", IdatMode::Trailing).unwrap();
+
+        let image = ImageData::from_png(&png).unwrap();
+        assert_eq!(
+            image.inflated.len(),
+            ImageHeader::from_png(&png).unwrap().raw_data_len()
+        );
+        assert_eq!(extract(&png).unwrap(), b"second");
+
+        hide(&mut png, b"third", IdatMode::Trailing).unwrap();
+        assert_eq!(extract(&png).unwrap(), b"third");
+    }
+
+    #[test]
+    fn test_inflate_limit() {
+        let stream = miniz_oxide::deflate::compress_to_vec_zlib(&[0; 200_000], 6);
+        assert_eq!(inflate(&stream, 200_000).unwrap().0.len(), 200_000);
+        assert!(inflate(&stream, 199_999).is_err());
+    }
+
+    #[test]
+    fn test_no_hidden_data() {
+        assert!(!has_hidden_data(&testing_png()));
+    }
+
+    #[test]
+    fn test_output_passes_validation() {
+        for mode in [IdatMode::Trailing, IdatMode::StoredBlock] {
+            let mut png = testing_png();
+            hide(&mut png, b"secret", mode).unwrap();
+            assert_eq!(png.validate(), []);
+        }
+    }
+}
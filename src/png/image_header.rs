@@ -0,0 +1,197 @@
+use crate::png::Png;
+use crate::{Error, Result};
+
+/// The decoded contents of an `IHDR` chunk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImageHeader {
+    pub width: u32,
+    pub height: u32,
+    pub bit_depth: u8,
+    pub color_type: u8,
+    pub compression_method: u8,
+    pub filter_method: u8,
+    pub interlace_method: u8,
+}
+
+impl ImageHeader {
+    pub const COLOR_GRAYSCALE: u8 = 0;
+    pub const COLOR_RGB: u8 = 2;
+    pub const COLOR_INDEXED: u8 = 3;
+    pub const COLOR_GRAYSCALE_ALPHA: u8 = 4;
+    pub const COLOR_RGBA: u8 = 6;
+
+    /// Starting column, starting row, column step and row step of the seven Adam7 passes.
+    pub const ADAM7_PASSES: [(u32, u32, u32, u32); 7] = [
+        (0, 0, 8, 8),
+        (4, 0, 8, 8),
+        (0, 4, 4, 8),
+        (2, 0, 4, 4),
+        (0, 2, 2, 4),
+        (1, 0, 2, 2),
+        (0, 1, 1, 2),
+    ];
+
+    /// Reads the header from the first `IHDR` chunk of a PNG.
+    pub fn from_png(png: &Png) -> Result<Self> {
+        let chunk = png.chunk_by_type("IHDR").ok_or("PNG has no IHDR chunk")?;
+        Self::try_from(chunk.data())
+    }
+
+    /// Returns the number of samples per pixel for the color type.
+    pub fn channels(&self) -> u8 {
+        match self.color_type {
+            Self::COLOR_RGB => 3,
+            Self::COLOR_GRAYSCALE_ALPHA => 2,
+            Self::COLOR_RGBA => 4,
+            _ => 1,
+        }
+    }
+
+    /// Returns the number of bits used by one pixel.
+    pub fn bits_per_pixel(&self) -> usize {
+        self.channels() as usize * self.bit_depth as usize
+    }
+
+    /// Returns the filter byte distance: bytes per complete pixel, at least one.
+    pub fn filter_stride(&self) -> usize {
+        self.bits_per_pixel().div_ceil(8)
+    }
+
+    /// Returns the number of bytes in one unfiltered scanline of the given width.
+    pub fn row_bytes(&self, width: u32) -> usize {
+        (width as usize * self.bits_per_pixel()).div_ceil(8)
+    }
+
+    /// Returns the width and height of each sub-image stored in the data stream.
+    ///
+    /// Non-interlaced images consist of a single sub-image; Adam7 images of
+    /// up to seven, where empty passes are reported with a zero dimension.
+    pub fn passes(&self) -> Vec<(u32, u32)> {
+        if self.interlace_method == 0 {
+            return vec![(self.width, self.height)];
+        }
+        Self::ADAM7_PASSES
+            .iter()
+            .map(|&(x0, y0, dx, dy)| {
+                let width = self.width.saturating_sub(x0).div_ceil(dx);
+                let height = self.height.saturating_sub(y0).div_ceil(dy);
+                (width, height)
+            })
+            .collect()
+    }
+
+    /// Returns the length of the decompressed image data, including filter bytes.
+    pub fn raw_data_len(&self) -> usize {
+        self.passes()
+            .into_iter()
+            .filter(|&(width, height)| width > 0 && height > 0)
+            .map(|(width, height)| height as usize * (1 + self.row_bytes(width)))
+            .sum()
+    }
+
+    /// Serializes the header to `IHDR` chunk data.
+    pub fn as_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(13);
+        bytes.extend_from_slice(&self.width.to_be_bytes());
+        bytes.extend_from_slice(&self.height.to_be_bytes());
+        bytes.extend_from_slice(&[
+            self.bit_depth,
+            self.color_type,
+            self.compression_method,
+            self.filter_method,
+            self.interlace_method,
+        ]);
+        bytes
+    }
+}
+
+/// Implements conversion from `IHDR` chunk data, validating the field combinations.
+impl TryFrom<&[u8]> for ImageHeader {
+    type Error = Error;
+
+    fn try_from(value: &[u8]) -> Result<Self> {
+        if value.len() != 13 {
+            return Err("IHDR chunk must be 13 bytes long".into());
+        }
+
+        let header = Self {
+            width: u32::from_be_bytes(value[0..4].try_into()?),
+            height: u32::from_be_bytes(value[4..8].try_into()?),
+            bit_depth: value[8],
+            color_type: value[9],
+            compression_method: value[10],
+            filter_method: value[11],
+            interlace_method: value[12],
+        };
+
+        let valid_depths: &[u8] = match header.color_type {
+            Self::COLOR_GRAYSCALE => &[1, 2, 4, 8, 16],
+            Self::COLOR_INDEXED => &[1, 2, 4, 8],
+            Self::COLOR_RGB | Self::COLOR_GRAYSCALE_ALPHA | Self::COLOR_RGBA => &[8, 16],
+            _ => return Err("Invalid PNG color type".into()),
+        };
+        if !valid_depths.contains(&header.bit_depth) {
+            return Err("Invalid bit depth for PNG color type".into());
+        }
+        if header.width == 0 || header.height == 0 {
+            return Err("PNG dimensions must be non-zero".into());
+        }
+        if header.compression_method != 0 || header.filter_method != 0 {
+            return Err("Unsupported PNG compression or filter method".into());
+        }
+        if header.interlace_method > 1 {
+            return Err("Invalid PNG interlace method".into());
+        }
+
+        Ok(header)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(
+        width: u32,
+        height: u32,
+        bit_depth: u8,
+        color_type: u8,
+        interlace: u8,
+    ) -> ImageHeader {
+        let mut bytes = width.to_be_bytes().to_vec();
+        bytes.extend_from_slice(&height.to_be_bytes());
+        bytes.extend_from_slice(&[bit_depth, color_type, 0, 0, interlace]);
+        ImageHeader::try_from(bytes.as_slice()).unwrap()
+    }
+
+    #[test]
+    fn test_raw_data_len() {
+        assert_eq!(header(50, 50, 8, 6, 0).raw_data_len(), 50 * (1 + 200));
+        assert_eq!(header(10, 2, 1, 0, 0).raw_data_len(), 2 * (1 + 2));
+        assert_eq!(header(3, 1, 16, 2, 0).raw_data_len(), 1 + 18);
+    }
+
+    #[test]
+    fn test_adam7_passes() {
+        // A 1x1 image only has data in the first pass
+        assert_eq!(header(1, 1, 8, 0, 1).raw_data_len(), 2);
+
+        let passes = header(8, 8, 8, 0, 1).passes();
+        assert_eq!(
+            passes,
+            vec![(1, 1), (1, 1), (2, 1), (2, 2), (4, 2), (4, 4), (8, 4)]
+        );
+        assert_eq!(header(8, 8, 8, 0, 1).raw_data_len(), 64 + 15);
+    }
+
+    #[test]
+    fn test_invalid_combinations() {
+        let mut bytes = header(1, 1, 8, 2, 0).as_bytes();
+        bytes[8] = 4;
+        assert!(ImageHeader::try_from(bytes.as_slice()).is_err());
+        bytes[8] = 8;
+        bytes[9] = 5;
+        assert!(ImageHeader::try_from(bytes.as_slice()).is_err());
+        assert!(ImageHeader::try_from(&bytes[..12]).is_err());
+    }
+}
@@ -1,7 +1,17 @@
+pub mod alpha;
 mod chunk;
 mod chunk_type;
+pub mod diff;
+pub mod idat;
+mod image_header;
+pub mod palette;
+pub mod payload;
+#[allow(clippy::module_inception)]
 mod png;
+pub mod raster;
+pub mod validate;
 
 pub use chunk::Chunk;
 pub use chunk_type::ChunkType;
+pub use image_header::ImageHeader;
 pub use png::Png;
@@ -0,0 +1,342 @@
+//! Hiding data in the order of palette entries.
+//!
+//! An indexed-color PNG looks exactly the same under any permutation of its
+//! `PLTE` entries, as long as `tRNS`, `bKGD`, `hIST` and the index data are
+//! remapped to match. A palette of `n` distinct entries can be arranged in
+//! `n!` ways, so its order carries `floor(log2(n!))` bits without changing a
+//! single pixel.
+//!
+//! The sealed payload is read as a big-endian integer and written as the
+//! Lehmer code of the permutation, relative to the entries sorted by color.
+
+use crate::png::{Chunk, ChunkType, ImageHeader, Png, raster};
+use crate::{Result, envelope};
+use std::str::FromStr;
+
+/// An RGBA palette entry, with the alpha value taken from `tRNS`.
+type Entry = [u8; 4];
+
+/// Returns the number of bits the palette order of a PNG can carry.
+pub fn capacity(png: &Png) -> Result<usize> {
+    Ok(capacity_bits(entries(png)?.len()))
+}
+
+/// Hides data in the palette order of an indexed PNG, replacing any earlier payload.
+pub fn hide(png: &mut Png, data: &[u8]) -> Result<()> {
+    png.check_critical_chunks()?;
+    let header = ImageHeader::from_png(png)?;
+    let entries = entries(png)?;
+    let n = entries.len();
+    if n > 1 << header.bit_depth {
+        return Err("Palette has more entries than the bit depth can address".into());
+    }
+
+    let capacity = capacity_bits(n) / 8;
+    let mut message = envelope::seal(data);
+    if message.len() > capacity {
+        return Err(format!(
+            "Data too large: a palette of {} colors holds {} bytes, {} needed",
+            n,
+            capacity.saturating_sub(envelope::HEADER_LEN),
+            data.len()
+        )
+        .into());
+    }
+    message.resize(capacity, 0);
+
+    // Mixed-radix digits of the payload, the first one in base n
+    let mut number = BigUint::from_be_bytes(&message);
+    let mut digits = vec![0; n];
+    for i in (0..n).rev() {
+        digits[i] = number.div_rem((n - i) as u32) as usize;
+    }
+
+    let mut remaining = sorted(&entries)?;
+    let order: Vec<Entry> = digits.iter().map(|&d| remaining.remove(d)).collect();
+    let mapping: Vec<usize> = entries
+        .iter()
+        .map(|entry| order.iter().position(|e| e == entry).unwrap_or_default())
+        .collect();
+
+    reorder(png, &order, &mapping)
+}
+
+/// Extracts a payload hidden in the palette order.
+pub fn extract(png: &Png) -> Result<Vec<u8>> {
+    let entries = entries(png)?;
+    let n = entries.len();
+
+    let mut remaining = sorted(&entries)?;
+    let mut number = BigUint::default();
+    for (i, entry) in entries.iter().enumerate() {
+        let digit = remaining
+            .iter()
+            .position(|e| e == entry)
+            .unwrap_or_default();
+        remaining.remove(digit);
+        number.mul_add((n - i) as u32, digit as u32);
+    }
+
+    envelope::open(&number.into_be_bytes(capacity_bits(n) / 8))
+}
+
+/// Returns true if the palette order carries a payload.
+pub fn has_hidden_data(png: &Png) -> bool {
+    extract(png).is_ok()
+}
+
+/// Reads the palette entries with their alpha values.
+pub(crate) fn entries(png: &Png) -> Result<Vec<Entry>> {
+    let header = ImageHeader::from_png(png)?;
+    if header.color_type != ImageHeader::COLOR_INDEXED {
+        return Err("Palette embedding requires an indexed-color PNG".into());
+    }
+
+    let palette = png.chunk_by_type("PLTE").ok_or("PNG has no PLTE chunk")?;
+    let colors = palette.data();
+    if colors.is_empty() || colors.len() % 3 != 0 || colors.len() > 256 * 3 {
+        return Err("Invalid PLTE chunk length".into());
+    }
+    let alphas = png
+        .chunk_by_type("tRNS")
+        .map_or(&[][..], |chunk| chunk.data());
+
+    Ok(colors
+        .chunks_exact(3)
+        .enumerate()
+        .map(|(i, rgb)| {
+            [
+                rgb[0],
+                rgb[1],
+                rgb[2],
+                alphas.get(i).copied().unwrap_or(255),
+            ]
+        })
+        .collect())
+}
+
+/// Returns the entries in canonical order, failing if any two are identical.
+fn sorted(entries: &[Entry]) -> Result<Vec<Entry>> {
+    let mut sorted = entries.to_vec();
+    sorted.sort_unstable();
+    if sorted.windows(2).any(|pair| pair[0] == pair[1]) {
+        return Err("Palette contains duplicate entries, so its order is ambiguous".into());
+    }
+    Ok(sorted)
+}
+
+/// Returns `floor(log2(n!))`.
+fn capacity_bits(n: usize) -> usize {
+    let mut factorial = BigUint(vec![1]);
+    for k in 2..=n {
+        factorial.mul_add(k as u32, 0);
+    }
+    factorial.bits() - 1
+}
+
+/// Rewrites the palette in the given order, where `mapping[i]` is the new
+/// position of the entry previously at index `i`.
+fn reorder(png: &mut Png, order: &[Entry], mapping: &[usize]) -> Result<()> {
+    let chunk = |chunk_type: &str, data: Vec<u8>| -> Result<Chunk> {
+        Ok(Chunk::new(ChunkType::from_str(chunk_type)?, data))
+    };
+
+    let mut pixels = raster::decode(png)?;
+    for sample in pixels.samples_mut() {
+        if let Some(&index) = mapping.get(*sample as usize) {
+            *sample = index as u16;
+        }
+    }
+    raster::encode(png, &pixels)?;
+
+    let colors = order.iter().flat_map(|entry| entry[..3].to_vec()).collect();
+    png.replace_chunk(chunk("PLTE", colors)?)?;
+
+    if png.chunk_by_type("tRNS").is_some() {
+        let mut alphas: Vec<u8> = order.iter().map(|entry| entry[3]).collect();
+        while alphas.last() == Some(&255) {
+            alphas.pop();
+        }
+        png.replace_chunk(chunk("tRNS", alphas)?)?;
+    }
+
+    if let Some(&[index]) = png.chunk_by_type("bKGD").map(|chunk| chunk.data()) {
+        let index = mapping.get(index as usize).map_or(index, |&i| i as u8);
+        png.replace_chunk(chunk("bKGD", vec![index])?)?;
+    }
+
+    if let Some(histogram) = png.chunk_by_type("hIST").map(|chunk| chunk.data().to_vec()) {
+        let mut reordered = histogram.clone();
+        for (old, frequency) in histogram.chunks_exact(2).enumerate() {
+            if let Some(&new) = mapping.get(old) {
+                reordered[new * 2..new * 2 + 2].copy_from_slice(frequency);
+            }
+        }
+        png.replace_chunk(chunk("hIST", reordered)?)?;
+    }
+
+    Ok(())
+}
+
+/// A minimal arbitrary-precision unsigned integer, in little-endian 32-bit limbs.
+#[derive(Debug, Clone, Default)]
+struct BigUint(Vec<u32>);
+
+impl BigUint {
+    fn from_be_bytes(bytes: &[u8]) -> Self {
+        let mut number = Self::default();
+        for &byte in bytes {
+            number.mul_add(256, byte as u32);
+        }
+        number
+    }
+
+    /// Returns the lowest `len` bytes in big-endian order.
+    fn into_be_bytes(mut self, len: usize) -> Vec<u8> {
+        let mut bytes = vec![0u8; len];
+        for byte in bytes.iter_mut().rev() {
+            *byte = self.div_rem(256) as u8;
+        }
+        bytes
+    }
+
+    /// Computes `self * factor + addend` in place.
+    fn mul_add(&mut self, factor: u32, addend: u32) {
+        let mut carry = addend as u64;
+        for limb in &mut self.0 {
+            let value = *limb as u64 * factor as u64 + carry;
+            *limb = value as u32;
+            carry = value >> 32;
+        }
+        if carry > 0 {
+            self.0.push(carry as u32);
+        }
+    }
+
+    /// Divides in place and returns the remainder.
+    fn div_rem(&mut self, divisor: u32) -> u32 {
+        let mut remainder = 0u64;
+        for limb in self.0.iter_mut().rev() {
+            let value = (remainder << 32) | *limb as u64;
+            *limb = (value / divisor as u64) as u32;
+            remainder = value % divisor as u64;
+        }
+        while self.0.last() == Some(&0) {
+            self.0.pop();
+        }
+        remainder as u32
+    }
+
+    /// Returns the number of significant bits.
+    fn bits(&self) -> usize {
+        self.0.last().map_or(0, |&top| {
+            (self.0.len() - 1) * 32 + (u32::BITS - top.leading_zeros()) as usize
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 16x16 indexed image with a 64-color palette, partly transparent.
+    fn testing_png() -> Png {
+        let header = ImageHeader {
+            width: 16,
+            height: 16,
+            bit_depth: 8,
+            color_type: ImageHeader::COLOR_INDEXED,
+            compression_method: 0,
+            filter_method: 0,
+            interlace_method: 0,
+        };
+        let palette: Vec<u8> = (0..64u8).flat_map(|i| [i * 4, 255 - i, i / 2]).collect();
+        let alphas: Vec<u8> = (0..10u8).map(|i| i * 25).collect();
+        let histogram: Vec<u8> = (0..64u16).flat_map(|i| i.to_be_bytes()).collect();
+        let raw: Vec<u8> = (0..16)
+            .flat_map(|y| std::iter::once(0).chain((0..16).map(move |x| (x * 4 + y) % 64)))
+            .collect();
+        let stream = miniz_oxide::deflate::compress_to_vec_zlib(&raw, 6);
+
+        let chunk = |t: &str, d: &[u8]| Chunk::new(ChunkType::from_str(t).unwrap(), d.to_vec());
+        Png::from_chunks(vec![
+            chunk("IHDR", &header.as_bytes()),
+            chunk("PLTE", &palette),
+            chunk("tRNS", &alphas),
+            chunk("bKGD", &[7]),
+            chunk("hIST", &histogram),
+            chunk("IDAT", &stream),
+            chunk("IEND", b""),
+        ])
+    }
+
+    /// Resolves every pixel to its RGBA color.
+    fn colors(png: &Png) -> Vec<Entry> {
+        let entries = entries(png).unwrap();
+        raster::decode(png)
+            .unwrap()
+            .samples()
+            .iter()
+            .map(|&index| entries[index as usize])
+            .collect()
+    }
+
+    #[test]
+    fn test_capacity_bits() {
+        assert_eq!(capacity_bits(1), 0);
+        assert_eq!(capacity_bits(4), 4);
+        assert_eq!(capacity_bits(256), 1683);
+    }
+
+    #[test]
+    fn test_roundtrip_keeps_appearance() {
+        let original = testing_png();
+        let mut png = original.clone();
+        hide(&mut png, b"palette secret").unwrap();
+
+        assert_ne!(entries(&png).unwrap(), entries(&original).unwrap());
+        assert_eq!(colors(&png), colors(&original));
+        assert_eq!(extract(&png).unwrap(), b"palette secret");
+
+        // The background and histogram follow their entries
+        let background = png.chunk_by_type("bKGD").unwrap().data()[0] as usize;
+        assert_eq!(
+            entries(&png).unwrap()[background],
+            entries(&original).unwrap()[7]
+        );
+        let histogram = png.chunk_by_type("hIST").unwrap().data();
+        assert_eq!(histogram[background * 2..background * 2 + 2], [0, 7]);
+    }
+
+    #[test]
+    fn test_data_too_large() {
+        let mut png = testing_png();
+        let capacity = capacity(&png).unwrap() / 8 - envelope::HEADER_LEN;
+        assert!(hide(&mut png, &vec![1; capacity + 1]).is_err());
+        assert!(hide(&mut png, &vec![1; capacity]).is_ok());
+    }
+
+    #[test]
+    fn test_output_passes_validation() {
+        let mut png = testing_png();
+        hide(&mut png, b"secret").unwrap();
+        assert_eq!(png.validate(), []);
+    }
+
+    #[test]
+    fn test_duplicate_entries() {
+        let mut png = testing_png();
+        let mut palette = png.chunk_by_type("PLTE").unwrap().data().to_vec();
+        palette.copy_within(60..63, 63);
+        png.replace_chunk(Chunk::new(ChunkType::from_str("PLTE").unwrap(), palette))
+            .unwrap();
+        assert!(hide(&mut png, b"x").is_err());
+    }
+
+    #[test]
+    fn test_biguint_roundtrip() {
+        let bytes = [0x12, 0x34, 0x56, 0x78, 0x9a, 0xbc, 0xde, 0xf0, 0x11];
+        assert_eq!(BigUint::from_be_bytes(&bytes).into_be_bytes(9), bytes);
+        assert_eq!(BigUint::from_be_bytes(&bytes).bits(), 69);
+    }
+}
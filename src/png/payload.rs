@@ -0,0 +1,169 @@
+//! Payloads stored in an ancillary chunk of their own.
+//!
+//! The letters of the chunk type decide how other software treats it. The
+//! chunk must be ancillary (lowercase first letter), or decoders refuse the
+//! image, and private (lowercase second letter), since public types are
+//! reserved for chunks registered with the specification. A safe-to-copy type (lowercase fourth letter) survives editors that
+//! change the image data, while an unsafe-to-copy one is dropped by them,
+//! just as veil drops such chunks when it embeds in the image data.
+
+use crate::Result;
+use crate::png::{Chunk, ChunkType, Png};
+use std::str::FromStr;
+
+/// Chunk type used unless another one is chosen.
+pub const DEFAULT_CHUNK_TYPE: &str = "veIl";
+
+/// Chunk type written by older versions, still read so that their files extract.
+///
+/// It is an unregistered public type, and its lowercase third letter sets the
+/// reserved bit, both of which the specification forbids.
+pub const LEGACY_CHUNK_TYPE: &str = "vEiL";
+
+/// Parses a chunk type chosen for a payload, rejecting types that break the
+/// specification or collide with a standard chunk.
+pub fn chunk_type(name: &str) -> Result<ChunkType> {
+    let chunk_type =
+        ChunkType::from_str(name).map_err(|_| format!("Invalid chunk type '{}'", name))?;
+    if chunk_type.is_critical() {
+        return Err(format!(
+            "Payload chunk '{}' must be ancillary (lowercase first letter)",
+            name
+        )
+        .into());
+    }
+    if chunk_type.is_public() {
+        return Err(format!(
+            "Payload chunk '{}' must be private (lowercase second letter)",
+            name
+        )
+        .into());
+    }
+    if !chunk_type.is_reserved_bit_valid() {
+        return Err(format!(
+            "Payload chunk '{}' must have an uppercase third letter",
+            name
+        )
+        .into());
+    }
+    if Png::is_standard_chunk(&chunk_type) {
+        return Err(format!("'{}' is a standard chunk type", name).into());
+    }
+    Ok(chunk_type)
+}
+
+/// Stores the data in a new chunk of the given type, just before `IEND`,
+/// replacing any earlier payload of that type or the legacy one.
+///
+/// Fails on images with an unknown critical chunk, as a decoder would.
+pub fn hide(png: &mut Png, data: &[u8], chunk_type: ChunkType) -> Result<()> {
+    png.check_critical_chunks()?;
+    let name = chunk_type.to_string();
+    while png.remove_first_chunk(&name).is_ok() {}
+    while png.remove_first_chunk(LEGACY_CHUNK_TYPE).is_ok() {}
+
+    // Anything after IEND is read back as trailing data, not as a chunk
+    let index = png
+        .chunks()
+        .iter()
+        .position(|chunk| chunk.chunk_type().to_string() == "IEND")
+        .unwrap_or(png.chunks().len());
+    png.insert_chunk(index, Chunk::new(chunk_type, data.to_vec()));
+    Ok(())
+}
+
+/// Returns the data of the first chunk of the given type.
+pub fn extract<'a>(png: &'a Png, chunk_type: &ChunkType) -> Option<&'a [u8]> {
+    png.chunks()
+        .iter()
+        .find(|chunk| chunk.chunk_type() == chunk_type)
+        .map(|chunk| chunk.data())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pixels::PixelBuffer;
+    use crate::png::raster;
+
+    fn image() -> Png {
+        let samples = (0..4 * 4 * 3).map(|i| (i * 5 % 256) as u16).collect();
+        raster::to_png(&PixelBuffer::new(4, 4, 3, 255, samples).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_chunk_type_rules() {
+        assert!(chunk_type("prVt").is_ok());
+        assert!(chunk_type("prVT").is_ok());
+        assert!(chunk_type("PrVt").is_err());
+        assert!(chunk_type("prvt").is_err());
+        assert!(chunk_type("pRVt").is_err());
+        assert!(chunk_type("tEXt").is_err());
+        assert!(chunk_type("pr").is_err());
+    }
+
+    #[test]
+    fn test_unsafe_chunks_dropped_on_pixel_changes() {
+        let mut png = image();
+        hide(&mut png, b"survives", chunk_type("prVt").unwrap()).unwrap();
+        hide(&mut png, b"dropped", chunk_type("prVT").unwrap()).unwrap();
+        assert_eq!(png.validate(), []);
+
+        let mut pixels = raster::decode(&png).unwrap();
+        pixels.samples_mut()[0] ^= 1;
+        raster::encode(&mut png, &pixels).unwrap();
+
+        assert_eq!(
+            extract(&png, &chunk_type("prVt").unwrap()),
+            Some(&b"survives"[..])
+        );
+        assert_eq!(extract(&png, &chunk_type("prVT").unwrap()), None);
+    }
+
+    #[test]
+    fn test_unknown_critical_chunk() {
+        let mut png = image();
+        let index = png.chunks().len() - 1;
+        png.insert_chunk(
+            index,
+            Chunk::new(ChunkType::from_str("PRVT").unwrap(), b"data".to_vec()),
+        );
+
+        let message = "Unknown critical chunk 'PRVT', the image cannot be processed safely";
+        assert_eq!(raster::decode(&png).unwrap_err().to_string(), message);
+        assert_eq!(
+            hide(&mut png, b"data", chunk_type("prVt").unwrap())
+                .unwrap_err()
+                .to_string(),
+            message
+        );
+    }
+
+    #[test]
+    fn test_default_chunk_type_is_valid() {
+        let mut png = image();
+        hide(&mut png, b"secret", chunk_type(DEFAULT_CHUNK_TYPE).unwrap()).unwrap();
+        assert_eq!(png.validate(), []);
+    }
+
+    #[test]
+    fn test_hide_replaces_earlier_payload() {
+        let mut png = image();
+        let index = png.chunks().len() - 1;
+        png.insert_chunk(
+            index,
+            Chunk::new(
+                ChunkType::from_str(LEGACY_CHUNK_TYPE).unwrap(),
+                b"legacy".to_vec(),
+            ),
+        );
+        let default = chunk_type(DEFAULT_CHUNK_TYPE).unwrap();
+        hide(&mut png, b"first", default.clone()).unwrap();
+        hide(&mut png, b"second", default.clone()).unwrap();
+
+        assert_eq!(extract(&png, &default), Some(&b"second"[..]));
+        assert!(png.chunk_by_type(LEGACY_CHUNK_TYPE).is_none());
+        let payloads = png.chunks().iter().filter(|c| c.chunk_type() == &default);
+        assert_eq!(payloads.count(), 1);
+    }
+}
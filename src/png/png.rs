@@ -1,4 +1,5 @@
-use crate::png::Chunk;
+use crate::png::validate::{self, Violation};
+use crate::png::{Chunk, ChunkType};
 use crate::{Error, Result};
 use std::fmt;
 use std::io::{BufReader, Read};
@@ -8,19 +9,29 @@ use std::io::{BufReader, Read};
 pub struct Png {
     header: [u8; 8],
     chunks: Vec<Chunk>,
+    /// Bytes after the `IEND` chunk, kept so that the file is written back unchanged.
+    trailing: Vec<u8>,
 }
 
 impl Png {
     pub const STANDARD_HEADER: [u8; 8] = [137, 80, 78, 71, 13, 10, 26, 10];
-    pub const STANDARD_CHUNKS: [&str; 21] = [
+    pub const STANDARD_CHUNKS: [&str; 28] = [
         "IHDR", "PLTE", "IDAT", "IEND", "tRNS", "cHRM", "gAMA", "iCCP", "sBIT", "sRGB", "tEXt",
-        "zTXt", "iTXt", "bKGD", "hIST", "pHYs", "sPLT", "tIME", "oFFs", "pCAL", "sCAL",
+        "zTXt", "iTXt", "bKGD", "hIST", "pHYs", "sPLT", "tIME", "oFFs", "pCAL", "sCAL", "eXIf",
+        "acTL", "fcTL", "fdAT", "cICP", "mDCv", "cLLi",
     ];
 
+    pub const CRITICAL_CHUNKS: [&str; 4] = ["IHDR", "PLTE", "IDAT", "IEND"];
+
+    /// Returns true if the chunk type is defined by the PNG specification.
+    pub fn is_standard_chunk(chunk_type: &ChunkType) -> bool {
+        Self::STANDARD_CHUNKS.contains(&chunk_type.to_string().as_str())
+    }
+
     /// Reads a PNG from a file at the given path.
     pub fn from_file<P: AsRef<std::path::Path>>(path: P) -> Result<Self> {
         let data = std::fs::read(path)?;
-        Ok(Self::try_from(data.as_slice())?)
+        Self::try_from(data.as_slice())
     }
 
     /// Creates a PNG from a vector of chunks, using the standard PNG header.
@@ -28,6 +39,7 @@ impl Png {
         Self {
             header: Self::STANDARD_HEADER,
             chunks,
+            trailing: Vec::new(),
         }
     }
 
@@ -36,6 +48,11 @@ impl Png {
         self.chunks.push(chunk);
     }
 
+    /// Inserts a chunk at the given position, shifting all chunks after it.
+    pub fn insert_chunk(&mut self, index: usize, chunk: Chunk) {
+        self.chunks.insert(index, chunk);
+    }
+
     /// Removes and returns the first chunk of the given type, or returns an error if not found.
     pub fn remove_first_chunk(&mut self, chunk_type: &str) -> Result<Chunk> {
         match self
@@ -48,6 +65,22 @@ impl Png {
         }
     }
 
+    /// Replaces the first chunk of the same type in place, or returns an error if not found.
+    pub fn replace_chunk(&mut self, chunk: Chunk) -> Result<()> {
+        let chunk_type = chunk.chunk_type().to_string();
+        match self
+            .chunks
+            .iter_mut()
+            .find(|existing| existing.chunk_type().to_string() == chunk_type)
+        {
+            Some(existing) => {
+                *existing = chunk;
+                Ok(())
+            }
+            None => Err(format!("Chunk type '{}' not found", chunk_type).into()),
+        }
+    }
+
     /// Returns a reference to the PNG header bytes.
     pub fn header(&self) -> &[u8; 8] {
         &self.header
@@ -58,12 +91,53 @@ impl Png {
         &self.chunks
     }
 
+    /// Returns the byte offset of every chunk in the serialized file.
+    pub fn chunk_offsets(&self) -> Vec<usize> {
+        self.chunks
+            .iter()
+            .scan(self.header.len(), |offset, chunk| {
+                let start = *offset;
+                *offset += chunk.as_bytes().len();
+                Some(start)
+            })
+            .collect()
+    }
+
+    /// Returns the bytes that follow the `IEND` chunk, e.g. from `cat image.png archive.zip`.
+    pub fn trailing_data(&self) -> &[u8] {
+        &self.trailing
+    }
+
+    /// Returns the ancillary chunks that were read despite a CRC mismatch.
+    ///
+    /// Their data may be damaged, but a payload protected by error correction
+    /// can still be repaired. They keep their original CRC when written back.
+    pub fn damaged_chunks(&self) -> Vec<&Chunk> {
+        self.chunks
+            .iter()
+            .filter(|chunk| !chunk.has_valid_crc())
+            .collect()
+    }
+
+    /// Replaces the bytes written after the `IEND` chunk.
+    pub fn set_trailing_data(&mut self, data: Vec<u8>) {
+        self.trailing = data;
+    }
+
+    /// Returns the first chunk of the given type, if any.
+    pub fn chunk_by_type(&self, chunk_type: &str) -> Option<&Chunk> {
+        self.chunks
+            .iter()
+            .find(|chunk| chunk.chunk_type().to_string() == chunk_type)
+    }
+
     /// Serializes the PNG to a vector of bytes.
     pub fn as_bytes(&self) -> Vec<u8> {
         let mut result = self.header.to_vec();
         for chunk in &self.chunks {
             result.extend(chunk.as_bytes());
         }
+        result.extend_from_slice(&self.trailing);
         result
     }
 
@@ -77,19 +151,65 @@ impl Png {
         Ok(())
     }
 
+    /// Checks the chunk structure against the PNG specification.
+    pub fn validate(&self) -> Vec<Violation> {
+        validate::validate(self)
+    }
+
     /// Get all custom (non-standard) chunks in the PNG
+    ///
+    /// The property bits cannot decide this on their own: the animation chunks
+    /// (`acTL`, `fcTL`, `fdAT`) that the specification now defines have the
+    /// private bit, and older files may carry unregistered public types.
     pub fn custom_chunks(&self) -> Vec<&Chunk> {
         self.chunks
             .iter()
-            .filter(|chunk| {
-                let chunk_type = chunk.chunk_type().to_string();
-                !Self::STANDARD_CHUNKS.contains(&chunk_type.as_str())
-            })
+            .filter(|chunk| !Self::is_standard_chunk(chunk.chunk_type()))
             .collect()
     }
+
+    /// Fails if the PNG has a critical chunk that the specification does not define.
+    ///
+    /// Such a chunk may change how the image data is meant to be read, so the
+    /// image must not be decoded or modified.
+    pub fn check_critical_chunks(&self) -> Result<()> {
+        match self.chunks.iter().find(|chunk| {
+            let chunk_type = chunk.chunk_type();
+            chunk_type.is_critical()
+                && !Self::CRITICAL_CHUNKS.contains(&chunk_type.to_string().as_str())
+        }) {
+            Some(chunk) => Err(format!(
+                "Unknown critical chunk '{}', the image cannot be processed safely",
+                chunk.chunk_type()
+            )
+            .into()),
+            None => Ok(()),
+        }
+    }
+
+    /// Removes the non-standard chunks that are unsafe to copy and returns them.
+    ///
+    /// These chunks may depend on the image data, so they have to go once it
+    /// has been modified.
+    pub fn remove_unsafe_to_copy(&mut self) -> Vec<Chunk> {
+        let (removed, kept) =
+            std::mem::take(&mut self.chunks)
+                .into_iter()
+                .partition(|chunk: &Chunk| {
+                    let chunk_type = chunk.chunk_type();
+                    !chunk_type.is_safe_to_copy() && !Self::is_standard_chunk(chunk_type)
+                });
+        self.chunks = kept;
+        removed
+    }
 }
 
 /// Implements conversion from a byte slice to a PNG, validating the header and parsing all chunks.
+///
+/// Complete chunks with a valid CRC after `IEND` are still read, since older
+/// versions of veil appended their payload chunk there. Whatever follows is
+/// kept as trailing data rather than treated as a broken chunk. A chunk cut
+/// short before `IEND` is still an error.
 impl TryFrom<&[u8]> for Png {
     type Error = Error;
 
@@ -104,8 +224,10 @@ impl TryFrom<&[u8]> for Png {
         }
 
         let mut chunks = Vec::new();
+        let mut trailing = Vec::new();
+        let mut offset = header.len();
 
-        // Read chunks until we reach the end of the data
+        // Read chunks until we reach the end of the data or the IEND chunk
         loop {
             let mut length_bytes = [0; 4];
             match reader.read_exact(&mut length_bytes) {
@@ -114,15 +236,18 @@ impl TryFrom<&[u8]> for Png {
             }
 
             let length = u32::from_be_bytes(length_bytes);
+            let truncated = |_| format!("Truncated chunk at offset {}", offset);
 
             let mut chunk_type_bytes = [0; 4];
-            reader.read_exact(&mut chunk_type_bytes)?;
+            reader
+                .read_exact(&mut chunk_type_bytes)
+                .map_err(truncated)?;
 
             let mut data = vec![0; length as usize];
-            reader.read_exact(&mut data)?;
+            reader.read_exact(&mut data).map_err(truncated)?;
 
             let mut crc_bytes = [0; 4];
-            reader.read_exact(&mut crc_bytes)?;
+            reader.read_exact(&mut crc_bytes).map_err(truncated)?;
 
             let chunk_bytes: Vec<u8> = length_bytes
                 .iter()
@@ -132,13 +257,41 @@ impl TryFrom<&[u8]> for Png {
                 .copied()
                 .collect();
 
-            let chunk = Chunk::try_from(chunk_bytes.as_slice())?;
+            let chunk = match Chunk::try_from(chunk_bytes.as_slice()) {
+                Ok(chunk) => chunk,
+                // Keep damaged ancillary chunks, a protected payload may still be repaired
+                Err(_)
+                    if let Ok(chunk_type) = ChunkType::try_from(chunk_type_bytes)
+                        && !chunk_type.is_critical() =>
+                {
+                    Chunk::with_crc(chunk_type, data, u32::from_be_bytes(crc_bytes))
+                }
+                Err(e) => return Err(e),
+            };
+            offset += chunk_bytes.len();
             chunks.push(chunk);
+
+            if chunk_type_bytes == *b"IEND" {
+                reader.read_to_end(&mut trailing)?;
+                let mut rest = trailing.as_slice();
+                while let Some(chunk) = rest
+                    .get(..4)
+                    .map(|length| u32::from_be_bytes(length.try_into().unwrap()) as usize)
+                    .and_then(|length| rest.get(..length.checked_add(12)?))
+                    .and_then(|bytes| Chunk::try_from(bytes).ok())
+                {
+                    rest = &rest[chunk.as_bytes().len()..];
+                    chunks.push(chunk);
+                }
+                trailing = rest.to_vec();
+                break;
+            }
         }
 
         Ok(Self {
             header: Self::STANDARD_HEADER,
             chunks,
+            trailing,
         })
     }
 }
@@ -157,6 +310,9 @@ impl fmt::Display for Png {
                 chunk.data_as_string().unwrap_or_default()
             )?;
         }
+        if !self.trailing.is_empty() {
+            writeln!(f, "  Trailing data: {} bytes", self.trailing.len())?;
+        }
         writeln!(f, "}}")?;
         Ok(())
     }
@@ -257,6 +413,26 @@ mod tests {
         assert!(png.is_err());
     }
 
+    #[test]
+    fn test_damaged_ancillary_chunk() {
+        let png = testing_png();
+        let mut bytes = png.as_bytes();
+        // Flip a data byte of the ancillary "miDl" chunk
+        let offset = png.chunk_offsets()[1] + 8;
+        bytes[offset] ^= 0xff;
+
+        let damaged = Png::try_from(bytes.as_slice()).unwrap();
+        assert_eq!(damaged.damaged_chunks()[0].chunk_type().to_string(), "miDl");
+        assert_eq!(damaged.chunks()[1].data()[0], b'I' ^ 0xff);
+        // The damage stays visible once the file is written back
+        assert_eq!(damaged.as_bytes(), bytes);
+
+        // Critical chunks must still be intact
+        let mut bytes = png.as_bytes();
+        bytes[png.chunk_offsets()[0] + 8] ^= 0xff;
+        assert!(Png::try_from(bytes.as_slice()).is_err());
+    }
+
     #[test]
     fn test_list_chunks() {
         let png = testing_png();
@@ -264,6 +440,39 @@ mod tests {
         assert_eq!(chunks.len(), 3);
     }
 
+    #[test]
+    fn test_custom_chunks() {
+        let png = Png::from_chunks(vec![
+            chunk_from_strings("IHDR", "header").unwrap(),
+            chunk_from_strings("eXIf", "exif").unwrap(),
+            chunk_from_strings("acTL", "animation").unwrap(),
+            chunk_from_strings("prVt", "private").unwrap(),
+            chunk_from_strings("veIl", "payload").unwrap(),
+            chunk_from_strings("IEND", "").unwrap(),
+        ]);
+
+        let custom: Vec<String> = png
+            .custom_chunks()
+            .iter()
+            .map(|chunk| chunk.chunk_type().to_string())
+            .collect();
+        assert_eq!(custom, ["prVt", "veIl"]);
+    }
+
+    #[test]
+    fn test_replace_chunk() {
+        let mut png = testing_png();
+        png.replace_chunk(chunk_from_strings("miDl", "replaced").unwrap())
+            .unwrap();
+
+        assert_eq!(png.chunks()[1].data(), b"replaced");
+        assert_eq!(png.chunks().len(), 3);
+        assert!(
+            png.replace_chunk(chunk_from_strings("nOne", "").unwrap())
+                .is_err()
+        );
+    }
+
     #[test]
     fn test_png_from_image_file() {
         let png = Png::try_from(&PNG_FILE[..]);
@@ -278,6 +487,48 @@ mod tests {
         assert_eq!(actual, expected);
     }
 
+    #[test]
+    fn test_trailing_data_after_iend() {
+        let mut bytes = PNG_FILE.to_vec();
+        bytes.extend_from_slice(b"PK\x03\x04 not a chunk");
+
+        let png = Png::try_from(bytes.as_slice()).unwrap();
+        assert_eq!(png.trailing_data(), b"PK\x03\x04 not a chunk");
+        assert_eq!(
+            png.chunks().last().unwrap().chunk_type().to_string(),
+            "IEND"
+        );
+        assert_eq!(png.as_bytes(), bytes);
+
+        let plain = Png::try_from(&PNG_FILE[..]).unwrap();
+        assert!(plain.trailing_data().is_empty());
+    }
+
+    #[test]
+    fn test_legacy_chunk_after_iend() {
+        // Older versions appended the payload chunk after IEND
+        let mut bytes = PNG_FILE.to_vec();
+        bytes.extend(
+            chunk_from_strings("vEiL", "legacy payload")
+                .unwrap()
+                .as_bytes(),
+        );
+        bytes.extend_from_slice(b"PK\x03\x04");
+
+        let png = Png::try_from(bytes.as_slice()).unwrap();
+        let last = png.chunks().last().unwrap();
+        assert_eq!(last.chunk_type().to_string(), "vEiL");
+        assert_eq!(last.data(), b"legacy payload");
+        assert_eq!(png.trailing_data(), b"PK\x03\x04");
+        assert_eq!(png.as_bytes(), bytes);
+    }
+
+    #[test]
+    fn test_truncated_chunk_before_iend() {
+        let error = Png::try_from(&PNG_FILE[..100]).unwrap_err().to_string();
+        assert_eq!(error, "Truncated chunk at offset 83");
+    }
+
     #[test]
     fn test_png_trait_impls() {
         let chunk_bytes: Vec<u8> = testing_chunks()
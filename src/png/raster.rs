@@ -0,0 +1,384 @@
+//! Decoding and encoding of the PNG raster.
+//!
+//! Pixel-domain embedding needs the samples themselves rather than the
+//! compressed stream. [`decode`] inflates the image data, reverses the
+//! scanline filters and Adam7 interlacing, and unpacks sub-byte samples into
+//! a [`PixelBuffer`]. Indexed images decode to their palette indices, while
+//! [`decode_colors`] resolves them to the colors they show.
+//! [`encode`] performs the inverse and replaces the `IDAT` chunks, keeping
+//! the interlace method of the original image. [`to_png`] writes samples
+//! into a new image.
+
+use crate::Result;
+use crate::pixels::PixelBuffer;
+use crate::png::idat::{self, ImageData};
+use crate::png::palette;
+use crate::png::{Chunk, ChunkType, ImageHeader, Png};
+use std::str::FromStr;
+
+const FILTER_NONE: u8 = 0;
+const FILTER_SUB: u8 = 1;
+const FILTER_UP: u8 = 2;
+const FILTER_AVERAGE: u8 = 3;
+const FILTER_PAETH: u8 = 4;
+
+/// Decodes the samples of a PNG.
+pub fn decode(png: &Png) -> Result<PixelBuffer> {
+    png.check_critical_chunks()?;
+    let header = ImageHeader::from_png(png)?;
+    let image = ImageData::from_png(png)?;
+    if image.inflated.len() < header.raw_data_len() {
+        return Err("PNG image data is shorter than its header requires".into());
+    }
+
+    let channels = header.channels() as usize;
+    let stride = header.filter_stride();
+    let mut samples = vec![0u16; header.width as usize * header.height as usize * channels];
+    let mut offset = 0;
+
+    for (pass, (width, height)) in header.passes().into_iter().enumerate() {
+        if width == 0 || height == 0 {
+            continue;
+        }
+        let row_len = header.row_bytes(width);
+        let mut prior = vec![0u8; row_len];
+
+        for y in 0..height {
+            let filter_type = image.inflated[offset];
+            let mut row = image.inflated[offset + 1..offset + 1 + row_len].to_vec();
+            unfilter(filter_type, &mut row, &prior, stride)?;
+            offset += 1 + row_len;
+
+            for x in 0..width {
+                let (px, py) = position(&header, pass, x, y);
+                let start = (py as usize * header.width as usize + px as usize) * channels;
+                for c in 0..channels {
+                    samples[start + c] = unpack(&row, x as usize * channels + c, header.bit_depth);
+                }
+            }
+            prior = row;
+        }
+    }
+
+    let max_value = ((1u32 << header.bit_depth) - 1) as u16;
+    PixelBuffer::new(
+        header.width,
+        header.height,
+        header.channels(),
+        max_value,
+        samples,
+    )
+}
+
+/// Decodes the samples of a PNG as they are displayed.
+///
+/// Indexed images are resolved through `PLTE` and `tRNS` to RGBA, so that
+/// images which only differ in their palette order compare as equal.
+pub fn decode_colors(png: &Png) -> Result<PixelBuffer> {
+    let pixels = decode(png)?;
+    if ImageHeader::from_png(png)?.color_type != ImageHeader::COLOR_INDEXED {
+        return Ok(pixels);
+    }
+
+    let entries = palette::entries(png)?;
+    let mut samples = Vec::with_capacity(pixels.samples().len() * 4);
+    for &index in pixels.samples() {
+        let entry = entries
+            .get(index as usize)
+            .ok_or("Palette index out of range")?;
+        samples.extend(entry.iter().map(|&s| s as u16));
+    }
+    PixelBuffer::new(pixels.width(), pixels.height(), 4, 255, samples)
+}
+
+/// Encodes samples into the image data of a PNG, replacing its `IDAT` chunks.
+///
+/// The samples must match the dimensions, channel count and bit depth of the
+/// PNG's header.
+pub fn encode(png: &mut Png, pixels: &PixelBuffer) -> Result<()> {
+    let header = ImageHeader::from_png(png)?;
+    let max_value = ((1u32 << header.bit_depth) - 1) as u16;
+    if pixels.width() != header.width
+        || pixels.height() != header.height
+        || pixels.channels() != header.channels()
+        || pixels.max_value() != max_value
+    {
+        return Err("Pixels do not match the PNG header".into());
+    }
+
+    let channels = header.channels() as usize;
+    let stride = header.filter_stride();
+    let mut raw = Vec::with_capacity(header.raw_data_len());
+
+    for (pass, (width, height)) in header.passes().into_iter().enumerate() {
+        if width == 0 || height == 0 {
+            continue;
+        }
+        let row_len = header.row_bytes(width);
+        let mut prior = vec![0u8; row_len];
+
+        for y in 0..height {
+            let mut row = vec![0u8; row_len];
+            for x in 0..width {
+                let (px, py) = position(&header, pass, x, y);
+                let pixel = pixels.pixel(px, py);
+                for (c, &sample) in pixel.iter().enumerate() {
+                    pack(
+                        &mut row,
+                        x as usize * channels + c,
+                        header.bit_depth,
+                        sample,
+                    );
+                }
+            }
+
+            let filter_type = choose_filter(&header, &row, &prior, stride);
+            raw.push(filter_type);
+            raw.extend(filter(filter_type, &row, &prior, stride));
+            prior = row;
+        }
+    }
+
+    let stream = miniz_oxide::deflate::compress_to_vec_zlib(&raw, 9);
+    idat::replace_image_data(png, stream)
+}
+
+/// Creates a new, non-interlaced PNG holding the samples.
+///
+/// The color type follows the channel count (gray, gray with alpha, RGB or
+/// RGBA) and the bit depth follows the sample range, which must be one of
+/// 1, 3, 15, 255 or 65535.
+pub fn to_png(pixels: &PixelBuffer) -> Result<Png> {
+    let bit_depth = match pixels.max_value() {
+        1 => 1,
+        3 => 2,
+        15 => 4,
+        255 => 8,
+        65535 => 16,
+        _ => return Err("PNG samples need a range of 1, 2, 4, 8 or 16 bits".into()),
+    };
+    let color_type = match pixels.channels() {
+        1 => ImageHeader::COLOR_GRAYSCALE,
+        2 => ImageHeader::COLOR_GRAYSCALE_ALPHA,
+        3 => ImageHeader::COLOR_RGB,
+        4 => ImageHeader::COLOR_RGBA,
+        _ => return Err("PNG images have between 1 and 4 channels".into()),
+    };
+    if bit_depth < 8 && color_type != ImageHeader::COLOR_GRAYSCALE {
+        return Err("Bit depths below 8 are only allowed for grayscale PNGs".into());
+    }
+
+    let header = ImageHeader {
+        width: pixels.width(),
+        height: pixels.height(),
+        bit_depth,
+        color_type,
+        compression_method: 0,
+        filter_method: 0,
+        interlace_method: 0,
+    };
+    let chunk = |chunk_type: &str, data: Vec<u8>| -> Result<Chunk> {
+        Ok(Chunk::new(ChunkType::from_str(chunk_type)?, data))
+    };
+    let mut png = Png::from_chunks(vec![
+        chunk("IHDR", header.as_bytes())?,
+        chunk("IDAT", Vec::new())?,
+        chunk("IEND", Vec::new())?,
+    ]);
+    encode(&mut png, pixels)?;
+    Ok(png)
+}
+
+/// Maps a position within an interlace pass to its position in the image.
+fn position(header: &ImageHeader, pass: usize, x: u32, y: u32) -> (u32, u32) {
+    if header.interlace_method == 0 {
+        return (x, y);
+    }
+    let (x0, y0, dx, dy) = ImageHeader::ADAM7_PASSES[pass];
+    (x0 + x * dx, y0 + y * dy)
+}
+
+/// Reads the sample at the given index of an unfiltered scanline.
+fn unpack(row: &[u8], index: usize, bit_depth: u8) -> u16 {
+    match bit_depth {
+        16 => u16::from_be_bytes([row[index * 2], row[index * 2 + 1]]),
+        8 => row[index] as u16,
+        _ => {
+            let bit = index * bit_depth as usize;
+            let shift = 8 - bit_depth as usize - bit % 8;
+            ((row[bit / 8] >> shift) & ((1 << bit_depth) - 1)) as u16
+        }
+    }
+}
+
+/// Writes the sample at the given index of an unfiltered scanline.
+fn pack(row: &mut [u8], index: usize, bit_depth: u8, sample: u16) {
+    match bit_depth {
+        16 => row[index * 2..index * 2 + 2].copy_from_slice(&sample.to_be_bytes()),
+        8 => row[index] = sample as u8,
+        _ => {
+            let bit = index * bit_depth as usize;
+            let shift = 8 - bit_depth as usize - bit % 8;
+            let mask = ((1u8 << bit_depth) - 1) << shift;
+            row[bit / 8] = (row[bit / 8] & !mask) | ((sample as u8) << shift & mask);
+        }
+    }
+}
+
+/// Predicts a byte from its left, upper and upper-left neighbours.
+fn predict(filter_type: u8, left: u8, up: u8, up_left: u8) -> u8 {
+    match filter_type {
+        FILTER_SUB => left,
+        FILTER_UP => up,
+        FILTER_AVERAGE => ((left as u16 + up as u16) / 2) as u8,
+        FILTER_PAETH => {
+            let estimate = left as i16 + up as i16 - up_left as i16;
+            let distance = |value: u8| (estimate - value as i16).abs();
+            if distance(left) <= distance(up) && distance(left) <= distance(up_left) {
+                left
+            } else if distance(up) <= distance(up_left) {
+                up
+            } else {
+                up_left
+            }
+        }
+        _ => 0,
+    }
+}
+
+fn unfilter(filter_type: u8, row: &mut [u8], prior: &[u8], stride: usize) -> Result<()> {
+    if filter_type > FILTER_PAETH {
+        return Err("Invalid PNG filter type".into());
+    }
+    for i in 0..row.len() {
+        let left = if i >= stride { row[i - stride] } else { 0 };
+        let up_left = if i >= stride { prior[i - stride] } else { 0 };
+        row[i] = row[i].wrapping_add(predict(filter_type, left, prior[i], up_left));
+    }
+    Ok(())
+}
+
+fn filter(filter_type: u8, row: &[u8], prior: &[u8], stride: usize) -> Vec<u8> {
+    (0..row.len())
+        .map(|i| {
+            let left = if i >= stride { row[i - stride] } else { 0 };
+            let up_left = if i >= stride { prior[i - stride] } else { 0 };
+            row[i].wrapping_sub(predict(filter_type, left, prior[i], up_left))
+        })
+        .collect()
+}
+
+/// Picks a filter per scanline the way libpng recommends: none for indexed
+/// and sub-byte images, otherwise the one with the smallest sum of absolute
+/// differences.
+fn choose_filter(header: &ImageHeader, row: &[u8], prior: &[u8], stride: usize) -> u8 {
+    if header.color_type == ImageHeader::COLOR_INDEXED || header.bit_depth < 8 {
+        return FILTER_NONE;
+    }
+    (FILTER_NONE..=FILTER_PAETH)
+        .min_by_key(|&filter_type| {
+            filter(filter_type, row, prior, stride)
+                .iter()
+                .map(|&byte| (byte as i8).unsigned_abs() as u32)
+                .sum::<u32>()
+        })
+        .unwrap_or(FILTER_NONE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn testing_png(width: u32, height: u32, bit_depth: u8, color_type: u8, interlace: u8) -> Png {
+        let header = ImageHeader {
+            width,
+            height,
+            bit_depth,
+            color_type,
+            compression_method: 0,
+            filter_method: 0,
+            interlace_method: interlace,
+        };
+        let raw = vec![0u8; header.raw_data_len()];
+        let stream = miniz_oxide::deflate::compress_to_vec_zlib(&raw, 6);
+
+        let chunk = |t: &str, d: &[u8]| Chunk::new(ChunkType::from_str(t).unwrap(), d.to_vec());
+        Png::from_chunks(vec![
+            chunk("IHDR", &header.as_bytes()),
+            chunk("IDAT", &stream),
+            chunk("IEND", b""),
+        ])
+    }
+
+    fn pattern(png: &Png) -> PixelBuffer {
+        let pixels = decode(png).unwrap();
+        let max_value = pixels.max_value() as usize;
+        let samples = (0..pixels.samples().len())
+            .map(|i| ((i * 37 + i / 5) % (max_value + 1)) as u16)
+            .collect();
+        PixelBuffer::new(
+            pixels.width(),
+            pixels.height(),
+            pixels.channels(),
+            pixels.max_value(),
+            samples,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_roundtrip_all_layouts() {
+        let layouts = [
+            (ImageHeader::COLOR_RGBA, 8),
+            (ImageHeader::COLOR_RGB, 16),
+            (ImageHeader::COLOR_GRAYSCALE, 1),
+            (ImageHeader::COLOR_GRAYSCALE_ALPHA, 8),
+            (ImageHeader::COLOR_INDEXED, 4),
+        ];
+        for (color_type, bit_depth) in layouts {
+            for interlace in [0, 1] {
+                let mut png = testing_png(13, 7, bit_depth, color_type, interlace);
+                let pixels = pattern(&png);
+                encode(&mut png, &pixels).unwrap();
+                assert_eq!(decode(&png).unwrap(), pixels);
+            }
+        }
+    }
+
+    #[test]
+    fn test_new_png_from_pixels() {
+        let bilevel = PixelBuffer::new(10, 3, 1, 1, (0..30).map(|i| i % 3 / 2).collect()).unwrap();
+        let rgb = PixelBuffer::new(2, 2, 3, 255, (0..12).map(|i| i * 20).collect()).unwrap();
+        for pixels in [bilevel, rgb] {
+            let png = to_png(&pixels).unwrap();
+            let parsed = Png::try_from(png.as_bytes().as_slice()).unwrap();
+            assert_eq!(decode(&parsed).unwrap(), pixels);
+        }
+
+        let odd_range = PixelBuffer::new(1, 1, 1, 100, vec![0]).unwrap();
+        assert!(to_png(&odd_range).is_err());
+    }
+
+    #[test]
+    fn test_pack_sub_byte_samples() {
+        let mut row = vec![0u8; 1];
+        pack(&mut row, 1, 2, 3);
+        pack(&mut row, 3, 2, 1);
+        assert_eq!(row, [0b0011_0001]);
+        assert_eq!(unpack(&row, 1, 2), 3);
+    }
+
+    #[test]
+    fn test_paeth_prediction() {
+        assert_eq!(predict(FILTER_PAETH, 10, 20, 10), 20);
+        assert_eq!(predict(FILTER_PAETH, 20, 10, 10), 20);
+        assert_eq!(predict(FILTER_PAETH, 10, 10, 30), 10);
+    }
+
+    #[test]
+    fn test_mismatched_pixels() {
+        let mut png = testing_png(4, 4, 8, ImageHeader::COLOR_RGB, 0);
+        let pixels = PixelBuffer::new(4, 4, 4, 255, vec![0; 64]).unwrap();
+        assert!(encode(&mut png, &pixels).is_err());
+    }
+}
@@ -0,0 +1,452 @@
+//! Checks a PNG against the rules of the specification.
+//!
+//! Parsing only checks the signature and the CRCs. These checks cover the
+//! rest of the chunk structure: `IHDR` first and `IEND` last, consecutive
+//! `IDAT` chunks, where the standard chunks may appear and how often,
+//! the `PLTE` rules of each color type and the chunk type property bits.
+//! Breaking a rule that decoders rely on is an error; anything decoders
+//! tolerate but the specification advises against is a warning.
+
+use crate::png::{ImageHeader, Png};
+use std::fmt;
+
+/// Chunks that must appear before `PLTE` and `IDAT`.
+const BEFORE_PLTE: [&str; 5] = ["cHRM", "gAMA", "iCCP", "sBIT", "sRGB"];
+
+/// Chunks that must appear after `PLTE` but before `IDAT`.
+const AFTER_PLTE: [&str; 3] = ["tRNS", "bKGD", "hIST"];
+
+/// Chunks that must appear before `IDAT`.
+const BEFORE_IDAT: [&str; 6] = ["pHYs", "sPLT", "oFFs", "pCAL", "sCAL", "eXIf"];
+
+/// Chunks that may appear at most once.
+const SINGLE_CHUNKS: [&str; 15] = [
+    "IHDR", "PLTE", "IEND", "cHRM", "gAMA", "iCCP", "sBIT", "sRGB", "tRNS", "bKGD", "hIST", "pHYs",
+    "tIME", "oFFs", "pCAL",
+];
+
+/// How serious a violation is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// Allowed, but advised against by the specification.
+    Warning,
+    /// Breaks a rule of the specification; decoders may reject the file.
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Warning => f.pad("warning"),
+            Severity::Error => f.pad("error"),
+        }
+    }
+}
+
+/// One broken rule.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    pub severity: Severity,
+    /// Index of the offending chunk, if the rule concerns a single chunk.
+    pub chunk: Option<usize>,
+    pub message: String,
+}
+
+impl Violation {
+    fn error(chunk: Option<usize>, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Error,
+            chunk,
+            message: message.into(),
+        }
+    }
+
+    fn warning(chunk: Option<usize>, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            chunk,
+            message: message.into(),
+        }
+    }
+}
+
+/// Checks every rule and returns the violations in chunk order, file-wide ones last.
+pub fn validate(png: &Png) -> Vec<Violation> {
+    let chunks = png.chunks();
+    let names: Vec<String> = chunks.iter().map(|c| c.chunk_type().to_string()).collect();
+    let first = |name: &str| names.iter().position(|n| n == name);
+    let plte = first("PLTE");
+    let idat = first("IDAT");
+    let mut violations = Vec::new();
+
+    let header = match chunks.first() {
+        Some(chunk) if names[0] == "IHDR" => match ImageHeader::try_from(chunk.data()) {
+            Ok(header) => Some(header),
+            Err(e) => {
+                violations.push(Violation::error(Some(0), format!("Invalid IHDR: {}", e)));
+                None
+            }
+        },
+        _ => {
+            violations.push(Violation::error(None, "IHDR must be the first chunk"));
+            None
+        }
+    };
+
+    for (i, (chunk, name)) in chunks.iter().zip(&names).enumerate() {
+        let chunk_type = chunk.chunk_type();
+        let name = name.as_str();
+
+        if !chunk.has_valid_crc() {
+            violations.push(Violation::error(
+                Some(i),
+                format!("{} has a CRC mismatch, its data may be damaged", name),
+            ));
+        }
+        if !chunk_type.is_reserved_bit_valid() {
+            violations.push(Violation::error(
+                Some(i),
+                format!("{} has the reserved bit set (lowercase third letter)", name),
+            ));
+        }
+        if chunk_type.is_critical() && !Png::CRITICAL_CHUNKS.contains(&name) {
+            violations.push(Violation::error(
+                Some(i),
+                format!("{} is an unknown critical chunk", name),
+            ));
+        } else if chunk_type.is_public() && !Png::is_standard_chunk(chunk_type) {
+            violations.push(Violation::warning(
+                Some(i),
+                format!(
+                    "{} is an unregistered public chunk (uppercase second letter)",
+                    name
+                ),
+            ));
+        }
+        if SINGLE_CHUNKS.contains(&name) && first(name) != Some(i) {
+            violations.push(Violation::error(
+                Some(i),
+                format!("{} must not appear more than once", name),
+            ));
+        }
+
+        let after_plte = plte.is_some_and(|p| i > p);
+        let after_idat = idat.is_some_and(|d| i > d);
+        if BEFORE_PLTE.contains(&name) && (after_plte || after_idat) {
+            violations.push(Violation::error(
+                Some(i),
+                format!("{} must come before PLTE and IDAT", name),
+            ));
+        }
+        if AFTER_PLTE.contains(&name) && plte.is_some_and(|p| i < p) {
+            violations.push(Violation::error(
+                Some(i),
+                format!("{} must come after PLTE", name),
+            ));
+        }
+        if (AFTER_PLTE.contains(&name) || BEFORE_IDAT.contains(&name) || name == "PLTE")
+            && after_idat
+        {
+            violations.push(Violation::error(
+                Some(i),
+                format!("{} must come before IDAT", name),
+            ));
+        }
+
+        match name {
+            "IHDR" if i > 0 => {
+                violations.push(Violation::error(Some(i), "IHDR must be the first chunk"));
+            }
+            "IDAT" if idat.is_some_and(|d| i > d && names[i - 1] != "IDAT") => {
+                violations.push(Violation::error(Some(i), "IDAT chunks must be consecutive"));
+            }
+            "IEND" => {
+                if i + 1 != chunks.len() {
+                    violations.push(Violation::error(Some(i), "IEND must be the last chunk"));
+                }
+                if !chunk.data().is_empty() {
+                    violations.push(Violation::error(Some(i), "IEND must be empty"));
+                }
+            }
+            "hIST" if plte.is_none() => {
+                violations.push(Violation::error(Some(i), "hIST requires a PLTE chunk"));
+            }
+            _ => {}
+        }
+    }
+
+    if let Some(header) = header {
+        violations.extend(check_palette(png, &header, plte));
+        if let Some(i) = first("tRNS")
+            && matches!(
+                header.color_type,
+                ImageHeader::COLOR_GRAYSCALE_ALPHA | ImageHeader::COLOR_RGBA
+            )
+        {
+            violations.push(Violation::error(
+                Some(i),
+                "tRNS is not allowed in images with an alpha channel",
+            ));
+        }
+    }
+    if idat.is_none() {
+        violations.push(Violation::error(None, "PNG has no IDAT chunk"));
+    }
+    if first("IEND").is_none() {
+        violations.push(Violation::error(None, "PNG has no IEND chunk"));
+    }
+    if first("iCCP").is_some() && first("sRGB").is_some() {
+        violations.push(Violation::warning(
+            None,
+            "iCCP and sRGB should not both be present",
+        ));
+    }
+    if !png.trailing_data().is_empty() {
+        violations.push(Violation::warning(
+            None,
+            format!("{} bytes after IEND", png.trailing_data().len()),
+        ));
+    }
+    violations
+}
+
+/// Checks whether the color type allows or requires a palette, and its size.
+fn check_palette(png: &Png, header: &ImageHeader, plte: Option<usize>) -> Vec<Violation> {
+    let Some(i) = plte else {
+        return match header.color_type {
+            ImageHeader::COLOR_INDEXED => {
+                vec![Violation::error(None, "Indexed-color images require PLTE")]
+            }
+            _ => Vec::new(),
+        };
+    };
+
+    let mut violations = Vec::new();
+    if matches!(
+        header.color_type,
+        ImageHeader::COLOR_GRAYSCALE | ImageHeader::COLOR_GRAYSCALE_ALPHA
+    ) {
+        violations.push(Violation::error(
+            Some(i),
+            "PLTE is not allowed in grayscale images",
+        ));
+    }
+
+    let length = png.chunks()[i].data().len();
+    let entries = length / 3;
+    if !length.is_multiple_of(3) || entries == 0 || entries > 256 {
+        violations.push(Violation::error(
+            Some(i),
+            format!(
+                "PLTE must hold 1 to 256 entries of 3 bytes, not {} bytes",
+                length
+            ),
+        ));
+    } else if header.color_type == ImageHeader::COLOR_INDEXED && entries > 1 << header.bit_depth {
+        violations.push(Violation::error(
+            Some(i),
+            format!(
+                "PLTE has {} entries, more than a bit depth of {} can index",
+                entries, header.bit_depth
+            ),
+        ));
+    }
+    violations
+}
+
+/// Formats violations as lines like `error    IEND at 57: IEND must be empty`.
+pub fn to_text(png: &Png, violations: &[Violation]) -> String {
+    let offsets = png.chunk_offsets();
+    violations
+        .iter()
+        .map(|violation| {
+            let location = violation.chunk.map_or("file".to_string(), |i| {
+                format!("{} at {}", png.chunks()[i].chunk_type(), offsets[i])
+            });
+            format!(
+                "{:<8} {}: {}",
+                violation.severity, location, violation.message
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::png::{Chunk, ChunkType};
+    use std::str::FromStr;
+
+    fn chunk(chunk_type: &str, data: &[u8]) -> Chunk {
+        Chunk::new(ChunkType::from_str(chunk_type).unwrap(), data.to_vec())
+    }
+
+    fn ihdr(bit_depth: u8, color_type: u8) -> Chunk {
+        let header = ImageHeader {
+            width: 1,
+            height: 1,
+            bit_depth,
+            color_type,
+            compression_method: 0,
+            filter_method: 0,
+            interlace_method: 0,
+        };
+        chunk("IHDR", &header.as_bytes())
+    }
+
+    fn messages(png: &Png) -> Vec<(Severity, Option<usize>, String)> {
+        validate(png)
+            .into_iter()
+            .map(|v| (v.severity, v.chunk, v.message))
+            .collect()
+    }
+
+    #[test]
+    fn test_valid_png() {
+        let png = Png::from_chunks(vec![
+            ihdr(8, ImageHeader::COLOR_RGB),
+            chunk("gAMA", &[0, 0, 177, 143]),
+            chunk("IDAT", b"pixels"),
+            chunk("IDAT", b"pixels"),
+            chunk("tEXt", b"Comment\0hi"),
+            chunk("IEND", b""),
+        ]);
+        assert!(validate(&png).is_empty());
+    }
+
+    #[test]
+    fn test_structure_violations() {
+        let mut png = Png::from_chunks(vec![
+            chunk("gAMA", &[0, 0, 177, 143]),
+            ihdr(8, ImageHeader::COLOR_RGB),
+            chunk("IDAT", b"pixels"),
+            chunk("tEXt", b"Comment\0hi"),
+            chunk("IDAT", b"pixels"),
+            chunk("IEND", b"x"),
+            chunk("rust", b"data"),
+        ]);
+        png.set_trailing_data(b"zip".to_vec());
+
+        let error = |chunk, message: &str| (Severity::Error, chunk, message.to_string());
+        assert_eq!(
+            messages(&png),
+            [
+                error(None, "IHDR must be the first chunk"),
+                error(Some(1), "IHDR must be the first chunk"),
+                error(Some(4), "IDAT chunks must be consecutive"),
+                error(Some(5), "IEND must be the last chunk"),
+                error(Some(5), "IEND must be empty"),
+                error(
+                    Some(6),
+                    "rust has the reserved bit set (lowercase third letter)"
+                ),
+                (Severity::Warning, None, "3 bytes after IEND".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_palette_rules() {
+        let indexed = Png::from_chunks(vec![
+            ihdr(1, ImageHeader::COLOR_INDEXED),
+            chunk("tRNS", &[0]),
+            chunk("PLTE", &[0; 9]),
+            chunk("IDAT", b"pixels"),
+            chunk("IEND", b""),
+        ]);
+        let text = to_text(&indexed, &validate(&indexed));
+        assert_eq!(
+            text,
+            "error    tRNS at 33: tRNS must come after PLTE\n\
+             error    PLTE at 46: PLTE has 3 entries, more than a bit depth of 1 can index"
+        );
+
+        let gray = Png::from_chunks(vec![
+            ihdr(8, ImageHeader::COLOR_GRAYSCALE_ALPHA),
+            chunk("PLTE", &[0; 4]),
+            chunk("tRNS", &[0]),
+            chunk("IDAT", b"pixels"),
+            chunk("IEND", b""),
+        ]);
+        let messages: Vec<String> = validate(&gray).into_iter().map(|v| v.message).collect();
+        assert_eq!(
+            messages,
+            [
+                "PLTE is not allowed in grayscale images",
+                "PLTE must hold 1 to 256 entries of 3 bytes, not 4 bytes",
+                "tRNS is not allowed in images with an alpha channel",
+            ]
+        );
+
+        let missing = Png::from_chunks(vec![
+            ihdr(8, ImageHeader::COLOR_INDEXED),
+            chunk("IDAT", b"pixels"),
+            chunk("IEND", b""),
+        ]);
+        assert_eq!(
+            validate(&missing)[0].message,
+            "Indexed-color images require PLTE"
+        );
+    }
+
+    #[test]
+    fn test_unknown_critical_chunk() {
+        let png = Png::from_chunks(vec![
+            ihdr(8, ImageHeader::COLOR_RGB),
+            chunk("IDAT", b"pixels"),
+            chunk("VEIL", b"data"),
+            chunk("IEND", b""),
+        ]);
+        assert_eq!(
+            messages(&png),
+            [(
+                Severity::Error,
+                Some(2),
+                "VEIL is an unknown critical chunk".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_unregistered_public_chunk() {
+        let png = Png::from_chunks(vec![
+            ihdr(8, ImageHeader::COLOR_RGB),
+            chunk("IDAT", b"pixels"),
+            chunk("pUBl", b"data"),
+            chunk("prVt", b"data"),
+            chunk("acTL", &[0; 8]),
+            chunk("IEND", b""),
+        ]);
+        assert_eq!(
+            messages(&png),
+            [(
+                Severity::Warning,
+                Some(2),
+                "pUBl is an unregistered public chunk (uppercase second letter)".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_damaged_chunk() {
+        let png = Png::from_chunks(vec![
+            ihdr(8, ImageHeader::COLOR_RGB),
+            chunk("IDAT", b"pixels"),
+            chunk("tEXt", b"Comment\0hi"),
+            chunk("IEND", b""),
+        ]);
+        let mut bytes = png.as_bytes();
+        bytes[png.chunk_offsets()[2] + 8] ^= 0xff;
+
+        let damaged = Png::try_from(bytes.as_slice()).unwrap();
+        assert_eq!(
+            messages(&damaged),
+            [(
+                Severity::Error,
+                Some(2),
+                "tEXt has a CRC mismatch, its data may be damaged".to_string()
+            )]
+        );
+    }
+}
@@ -0,0 +1,195 @@
+//! Files that are valid in two formats at once.
+//!
+//! A ZIP/PNG polyglot stores a complete ZIP archive in an ancillary PNG chunk
+//! placed right before `IEND`. Image viewers skip the unknown chunk, while ZIP
+//! readers find the end record near the end of the file. The central
+//! directory offsets are written relative to the start of the PNG file, and
+//! the archive comment length is set to cover the bytes after the end record
+//! (the chunk CRC and `IEND`), so `unzip` accepts the file without warnings.
+
+pub mod zip;
+
+use crate::Result;
+use crate::png::{Chunk, ChunkType, Png};
+use std::str::FromStr;
+
+/// Chunk type holding the ZIP archive (ancillary, private, safe to copy).
+pub const ZIP_CHUNK: &str = "pkZp";
+
+/// Secondary formats that can be merged into a carrier.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Polyglot {
+    /// The file also unzips to the hidden files.
+    Zip,
+}
+
+/// Where a ZIP archive was found inside a PNG file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ZipLocation {
+    /// Inside the chunk of the given type.
+    Chunk(String),
+    /// After the `IEND` chunk, e.g. from `cat image.png archive.zip`.
+    Trailing,
+    /// Anywhere else (overlapping chunk headers, or not a parsable PNG).
+    Unknown,
+}
+
+/// A ZIP archive found inside a file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ZipReport {
+    pub location: ZipLocation,
+    pub archive: zip::Archive,
+}
+
+/// Stores the entries as a ZIP archive in a new chunk before `IEND`.
+///
+/// Any archive chunk added earlier is replaced.
+pub fn embed_zip(png: &mut Png, entries: &[zip::Entry]) -> Result<()> {
+    png.check_critical_chunks()?;
+
+    while png.remove_first_chunk(ZIP_CHUNK).is_ok() {}
+
+    let index = png
+        .chunks()
+        .iter()
+        .position(|chunk| chunk.chunk_type().to_string() == "IEND")
+        .unwrap_or(png.chunks().len());
+
+    let preceding: usize = png.chunks()[..index]
+        .iter()
+        .map(|chunk| chunk.as_bytes().len())
+        .sum();
+    let following: usize = png.chunks()[index..]
+        .iter()
+        .map(|chunk| chunk.as_bytes().len())
+        .sum::<usize>()
+        + png.trailing_data().len();
+
+    // Archive data starts after the signature, the preceding chunks and our chunk's length and type
+    let base_offset = png.header().len() + preceding + 8;
+    let comment_len: u16 = (4 + following)
+        .try_into()
+        .map_err(|_| "Too much data after the ZIP chunk for the archive comment")?;
+
+    let archive = zip::write(entries, base_offset, comment_len)?;
+    png.insert_chunk(index, Chunk::new(ChunkType::from_str(ZIP_CHUNK)?, archive));
+    Ok(())
+}
+
+/// Looks for a ZIP archive in the raw bytes of a PNG file.
+pub fn detect_zip(bytes: &[u8]) -> Option<ZipReport> {
+    let archive = zip::find(bytes)?;
+    let location = locate(bytes, archive.eocd_offset);
+    Some(ZipReport { location, archive })
+}
+
+/// Walks the chunk headers to find the chunk whose data contains the given offset.
+///
+/// This does not parse the PNG fully, so it also works when data that is not
+/// a chunk follows `IEND`.
+fn locate(bytes: &[u8], offset: usize) -> ZipLocation {
+    if !bytes.starts_with(&Png::STANDARD_HEADER) {
+        return ZipLocation::Unknown;
+    }
+
+    let mut position = Png::STANDARD_HEADER.len();
+    while let Some(header) = bytes.get(position..position + 8) {
+        let length = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+        let data_start = position + 8;
+        let data_end = data_start + length;
+        if (data_start..data_end).contains(&offset) {
+            return ZipLocation::Chunk(String::from_utf8_lossy(&header[4..8]).into_owned());
+        }
+        position = data_end + 4;
+        if &header[4..8] == b"IEND" {
+            return if offset >= position {
+                ZipLocation::Trailing
+            } else {
+                ZipLocation::Unknown
+            };
+        }
+    }
+    ZipLocation::Unknown
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn testing_png() -> Png {
+        let chunk = |t: &str, d: &[u8]| Chunk::new(ChunkType::from_str(t).unwrap(), d.to_vec());
+        Png::from_chunks(vec![
+            chunk("IHDR", &[0; 13]),
+            chunk("IDAT", b"pixels"),
+            chunk("IEND", b""),
+        ])
+    }
+
+    fn entries() -> Vec<zip::Entry> {
+        vec![zip::Entry {
+            name: "secret.txt".to_string(),
+            data: b"hidden in plain sight".to_vec(),
+        }]
+    }
+
+    #[test]
+    fn test_embed_zip_is_valid_png_and_zip() {
+        let mut png = testing_png();
+        embed_zip(&mut png, &entries()).unwrap();
+        let bytes = png.as_bytes();
+
+        let parsed = Png::try_from(bytes.as_slice()).unwrap();
+        assert_eq!(parsed.chunks()[2].chunk_type().to_string(), ZIP_CHUNK);
+        assert_eq!(parsed.chunks()[3].chunk_type().to_string(), "IEND");
+
+        let report = detect_zip(&bytes).unwrap();
+        assert_eq!(report.location, ZipLocation::Chunk(ZIP_CHUNK.to_string()));
+        assert!(report.archive.offsets_valid);
+        assert_eq!(
+            zip::read_entries(&bytes, &report.archive).unwrap(),
+            entries()
+        );
+
+        // The comment length covers exactly the bytes after the end record
+        let eocd = report.archive.eocd_offset;
+        let comment_len = u16::from_le_bytes([bytes[eocd + 20], bytes[eocd + 21]]) as usize;
+        assert_eq!(eocd + zip::EOCD_LEN + comment_len, bytes.len());
+    }
+
+    #[test]
+    fn test_embed_zip_replaces_previous_archive() {
+        let mut png = testing_png();
+        embed_zip(&mut png, &entries()).unwrap();
+        embed_zip(&mut png, &entries()).unwrap();
+
+        assert_eq!(png.chunks().len(), 4);
+        assert!(detect_zip(&png.as_bytes()).unwrap().archive.offsets_valid);
+    }
+
+    #[test]
+    fn test_output_passes_validation() {
+        use crate::pixels::PixelBuffer;
+        use crate::png::raster;
+
+        let samples = (0..4 * 4 * 3).map(|i| (i * 5 % 256) as u16).collect();
+        let mut png = raster::to_png(&PixelBuffer::new(4, 4, 3, 255, samples).unwrap()).unwrap();
+        embed_zip(&mut png, &entries()).unwrap();
+        assert_eq!(png.validate(), []);
+    }
+
+    #[test]
+    fn test_detect_appended_zip() {
+        let mut bytes = testing_png().as_bytes();
+        let base = bytes.len();
+        bytes.extend(zip::write(&entries(), base, 0).unwrap());
+
+        let report = detect_zip(&bytes).unwrap();
+        assert_eq!(report.location, ZipLocation::Trailing);
+        assert!(report.archive.offsets_valid);
+    }
+
+    #[test]
+    fn test_no_zip() {
+        assert!(detect_zip(&testing_png().as_bytes()).is_none());
+    }
+}
@@ -0,0 +1,266 @@
+//! Minimal ZIP reading and writing with stored (uncompressed) entries.
+
+use crate::Result;
+use crc::Crc;
+
+pub const LOCAL_HEADER_SIGNATURE: u32 = 0x0403_4b50;
+pub const CENTRAL_HEADER_SIGNATURE: u32 = 0x0201_4b50;
+pub const END_OF_CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x0605_4b50;
+
+/// Size of the end-of-central-directory record without its comment.
+pub const EOCD_LEN: usize = 22;
+
+/// DOS date for 1980-01-01, the earliest date ZIP can express.
+const DOS_DATE_1980: u16 = (1 << 5) | 1;
+/// General purpose flag marking file names as UTF-8.
+const FLAG_UTF8: u16 = 1 << 11;
+
+/// A file stored in a ZIP archive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub name: String,
+    pub data: Vec<u8>,
+}
+
+/// Builds a ZIP archive of stored entries.
+///
+/// `base_offset` is added to every offset in the central directory, which lets
+/// the archive be placed at that position inside another file. `comment_len`
+/// is written to the end record without any comment bytes, so that data
+/// following the archive in the host file is treated as the archive comment.
+pub fn write(entries: &[Entry], base_offset: usize, comment_len: u16) -> Result<Vec<u8>> {
+    let crc = Crc::<u32>::new(&crc::CRC_32_ISO_HDLC);
+    let mut archive = Vec::new();
+    let mut central = Vec::new();
+
+    for entry in entries {
+        let name = entry.name.as_bytes();
+        let size: u32 = entry
+            .data
+            .len()
+            .try_into()
+            .map_err(|_| "ZIP entry is too large")?;
+        let offset: u32 = (base_offset + archive.len())
+            .try_into()
+            .map_err(|_| "ZIP archive is too large")?;
+        let checksum = crc.checksum(&entry.data);
+
+        // Fields shared by the local and central headers, from "version needed" on
+        let mut common = Vec::new();
+        push_u16(&mut common, 10); // version needed to extract
+        push_u16(&mut common, FLAG_UTF8);
+        push_u16(&mut common, 0); // method: stored
+        push_u16(&mut common, 0); // modification time
+        push_u16(&mut common, DOS_DATE_1980);
+        push_u32(&mut common, checksum);
+        push_u32(&mut common, size); // compressed size
+        push_u32(&mut common, size); // uncompressed size
+        push_u16(&mut common, name.len() as u16);
+        push_u16(&mut common, 0); // extra field length
+
+        push_u32(&mut archive, LOCAL_HEADER_SIGNATURE);
+        archive.extend_from_slice(&common);
+        archive.extend_from_slice(name);
+        archive.extend_from_slice(&entry.data);
+
+        push_u32(&mut central, CENTRAL_HEADER_SIGNATURE);
+        push_u16(&mut central, 20); // version made by
+        central.extend_from_slice(&common);
+        push_u16(&mut central, 0); // file comment length
+        push_u16(&mut central, 0); // disk number start
+        push_u16(&mut central, 0); // internal attributes
+        push_u32(&mut central, 0); // external attributes
+        push_u32(&mut central, offset);
+        central.extend_from_slice(name);
+    }
+
+    let central_offset = (base_offset + archive.len()) as u32;
+    let count = entries.len() as u16;
+    archive.extend_from_slice(&central);
+
+    push_u32(&mut archive, END_OF_CENTRAL_DIRECTORY_SIGNATURE);
+    push_u16(&mut archive, 0); // this disk
+    push_u16(&mut archive, 0); // disk with central directory
+    push_u16(&mut archive, count);
+    push_u16(&mut archive, count);
+    push_u32(&mut archive, central.len() as u32);
+    push_u32(&mut archive, central_offset);
+    push_u16(&mut archive, comment_len);
+    Ok(archive)
+}
+
+/// Location and contents of a ZIP archive found inside a file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Archive {
+    /// Offset of the end-of-central-directory record.
+    pub eocd_offset: usize,
+    /// Offset of the central directory as recorded in the end record.
+    pub central_offset: usize,
+    /// True if the recorded offsets point at the headers in this file.
+    pub offsets_valid: bool,
+    /// Names of the entries listed in the central directory.
+    pub names: Vec<String>,
+}
+
+/// Searches a file backwards for a ZIP end record with a parsable central directory.
+pub fn find(bytes: &[u8]) -> Option<Archive> {
+    let signature = END_OF_CENTRAL_DIRECTORY_SIGNATURE.to_le_bytes();
+    (0..bytes.len().saturating_sub(EOCD_LEN - 1))
+        .rev()
+        .filter(|&i| bytes[i..i + 4] == signature)
+        .find_map(|i| parse_at(bytes, i))
+}
+
+/// Extracts the stored entries of an archive found in the file.
+pub fn read_entries(bytes: &[u8], archive: &Archive) -> Result<Vec<Entry>> {
+    if !archive.offsets_valid {
+        return Err("ZIP offsets do not match this file".into());
+    }
+
+    let mut entries = Vec::new();
+    let mut offset = archive.central_offset;
+    for name in &archive.names {
+        let local = read_u32(bytes, offset + 42).ok_or("Truncated ZIP central directory")?;
+        let name_len = read_u16(bytes, offset + 28).unwrap_or(0) as usize;
+        let extra_len = read_u16(bytes, offset + 30).unwrap_or(0) as usize;
+        let comment_len = read_u16(bytes, offset + 32).unwrap_or(0) as usize;
+        let method = read_u16(bytes, offset + 10).unwrap_or(0);
+        let size = read_u32(bytes, offset + 20).unwrap_or(0) as usize;
+        if method != 0 {
+            return Err(format!("ZIP entry '{}' is compressed", name).into());
+        }
+
+        let local = local as usize;
+        let data_start = local
+            + 30
+            + read_u16(bytes, local + 26).ok_or("Truncated ZIP local header")? as usize
+            + read_u16(bytes, local + 28).ok_or("Truncated ZIP local header")? as usize;
+        let data = bytes
+            .get(data_start..data_start + size)
+            .ok_or("Truncated ZIP entry data")?;
+        entries.push(Entry {
+            name: name.clone(),
+            data: data.to_vec(),
+        });
+        offset += 46 + name_len + extra_len + comment_len;
+    }
+    Ok(entries)
+}
+
+fn parse_at(bytes: &[u8], eocd: usize) -> Option<Archive> {
+    let count = read_u16(bytes, eocd + 10)? as usize;
+    let central_size = read_u32(bytes, eocd + 12)? as usize;
+    let recorded_offset = read_u32(bytes, eocd + 16)? as usize;
+
+    // The central directory directly precedes the end record
+    let central_offset = eocd.checked_sub(central_size)?;
+    let mut names = Vec::with_capacity(count);
+    let mut offset = central_offset;
+    for _ in 0..count {
+        if read_u32(bytes, offset)? != CENTRAL_HEADER_SIGNATURE {
+            return None;
+        }
+        let name_len = read_u16(bytes, offset + 28)? as usize;
+        let extra_len = read_u16(bytes, offset + 30)? as usize;
+        let comment_len = read_u16(bytes, offset + 32)? as usize;
+        let name = bytes.get(offset + 46..offset + 46 + name_len)?;
+        names.push(String::from_utf8_lossy(name).into_owned());
+        offset += 46 + name_len + extra_len + comment_len;
+    }
+    if offset != eocd {
+        return None;
+    }
+
+    let first_local = match count {
+        0 => None,
+        _ => read_u32(bytes, central_offset + 42).map(|o| o as usize),
+    };
+    let offsets_valid = recorded_offset == central_offset
+        && first_local.is_none_or(|o| read_u32(bytes, o) == Some(LOCAL_HEADER_SIGNATURE));
+
+    Some(Archive {
+        eocd_offset: eocd,
+        central_offset,
+        offsets_valid,
+        names,
+    })
+}
+
+fn push_u16(buffer: &mut Vec<u8>, value: u16) {
+    buffer.extend_from_slice(&value.to_le_bytes());
+}
+
+fn push_u32(buffer: &mut Vec<u8>, value: u32) {
+    buffer.extend_from_slice(&value.to_le_bytes());
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(
+        bytes.get(offset..offset + 2)?.try_into().ok()?,
+    ))
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(
+        bytes.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries() -> Vec<Entry> {
+        vec![
+            Entry {
+                name: "a.txt".to_string(),
+                data: b"first".to_vec(),
+            },
+            Entry {
+                name: "dir/b.bin".to_string(),
+                data: vec![0, 1, 2, 3],
+            },
+        ]
+    }
+
+    #[test]
+    fn test_write_find_roundtrip() {
+        let archive = write(&entries(), 0, 0).unwrap();
+        let found = find(&archive).unwrap();
+
+        assert_eq!(found.eocd_offset, archive.len() - EOCD_LEN);
+        assert!(found.offsets_valid);
+        assert_eq!(found.names, vec!["a.txt", "dir/b.bin"]);
+        assert_eq!(read_entries(&archive, &found).unwrap(), entries());
+    }
+
+    #[test]
+    fn test_offsets_relative_to_host() {
+        let mut host = vec![0xaa; 100];
+        host.extend(write(&entries(), 100, 3).unwrap());
+        host.extend_from_slice(b"end");
+
+        let found = find(&host).unwrap();
+        assert!(found.offsets_valid);
+        assert_eq!(read_entries(&host, &found).unwrap(), entries());
+    }
+
+    #[test]
+    fn test_unfixed_offsets_detected() {
+        let mut host = vec![0xaa; 100];
+        host.extend(write(&entries(), 0, 0).unwrap());
+
+        let found = find(&host).unwrap();
+        assert!(!found.offsets_valid);
+        assert!(read_entries(&host, &found).is_err());
+    }
+
+    #[test]
+    fn test_known_local_header() {
+        let archive = write(&entries()[..1], 0, 0).unwrap();
+        // Signature, version 1.0, UTF-8 flag, stored, CRC-32 of "first"
+        assert_eq!(&archive[..10], &[0x50, 0x4b, 0x03, 0x04, 10, 0, 0, 8, 0, 0]);
+        let crc = Crc::<u32>::new(&crc::CRC_32_ISO_HDLC).checksum(b"first");
+        assert_eq!(read_u32(&archive, 14), Some(crc));
+    }
+}
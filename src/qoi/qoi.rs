@@ -0,0 +1,314 @@
+use crate::pixels::PixelBuffer;
+use crate::{Error, Result};
+use std::fmt;
+
+const QOI_OP_INDEX: u8 = 0x00;
+const QOI_OP_DIFF: u8 = 0x40;
+const QOI_OP_LUMA: u8 = 0x80;
+const QOI_OP_RUN: u8 = 0xc0;
+const QOI_OP_RGB: u8 = 0xfe;
+const QOI_OP_RGBA: u8 = 0xff;
+const QOI_MASK_2: u8 = 0xc0;
+
+/// Represents a QOI ("Quite OK Image") image with its decoded pixels.
+#[derive(Debug, Clone)]
+pub struct Qoi {
+    colorspace: u8,
+    pixels: PixelBuffer,
+}
+
+impl Qoi {
+    pub const MAGIC: [u8; 4] = *b"qoif";
+    pub const END_MARKER: [u8; 8] = [0, 0, 0, 0, 0, 0, 0, 1];
+
+    /// Reads a QOI image from a file at the given path.
+    pub fn from_file<P: AsRef<std::path::Path>>(path: P) -> Result<Self> {
+        let data = std::fs::read(path)?;
+        Self::try_from(data.as_slice())
+    }
+
+    /// Creates an image from 8-bit RGB or RGBA pixels.
+    pub fn from_pixels(pixels: PixelBuffer, colorspace: u8) -> Result<Self> {
+        if !matches!(pixels.channels(), 3 | 4) || pixels.max_value() != 255 {
+            return Err("QOI images must have 3 or 4 channels with 8-bit samples".into());
+        }
+        Ok(Self { colorspace, pixels })
+    }
+
+    /// Returns the colorspace byte (0 = sRGB with linear alpha, 1 = all linear).
+    pub fn colorspace(&self) -> u8 {
+        self.colorspace
+    }
+
+    /// Returns the decoded pixels.
+    pub fn pixels(&self) -> &PixelBuffer {
+        &self.pixels
+    }
+
+    /// Returns the decoded pixels for modification.
+    pub fn pixels_mut(&mut self) -> &mut PixelBuffer {
+        &mut self.pixels
+    }
+
+    /// Encodes the image to a vector of bytes.
+    pub fn as_bytes(&self) -> Vec<u8> {
+        let channels = self.pixels.channels();
+        let mut result = Self::MAGIC.to_vec();
+        result.extend_from_slice(&self.pixels.width().to_be_bytes());
+        result.extend_from_slice(&self.pixels.height().to_be_bytes());
+        result.push(channels);
+        result.push(self.colorspace);
+
+        let mut index = [[0u8; 4]; 64];
+        let mut previous = [0u8, 0, 0, 255];
+        let mut run = 0u8;
+
+        let pixels: Vec<[u8; 4]> = self
+            .pixels
+            .samples()
+            .chunks_exact(channels as usize)
+            .map(|p| {
+                [
+                    p[0] as u8,
+                    p[1] as u8,
+                    p[2] as u8,
+                    p.get(3).map_or(255, |&a| a as u8),
+                ]
+            })
+            .collect();
+        let last = pixels.len().saturating_sub(1);
+
+        for (i, &pixel) in pixels.iter().enumerate() {
+            if pixel == previous {
+                run += 1;
+                if run == 62 || i == last {
+                    result.push(QOI_OP_RUN | (run - 1));
+                    run = 0;
+                }
+                continue;
+            }
+
+            if run > 0 {
+                result.push(QOI_OP_RUN | (run - 1));
+                run = 0;
+            }
+
+            let position = hash(pixel);
+            if index[position] == pixel {
+                result.push(QOI_OP_INDEX | position as u8);
+            } else {
+                index[position] = pixel;
+
+                if pixel[3] == previous[3] {
+                    let dr = pixel[0].wrapping_sub(previous[0]) as i8;
+                    let dg = pixel[1].wrapping_sub(previous[1]) as i8;
+                    let db = pixel[2].wrapping_sub(previous[2]) as i8;
+                    let dr_dg = dr.wrapping_sub(dg);
+                    let db_dg = db.wrapping_sub(dg);
+
+                    if (-2..2).contains(&dr) && (-2..2).contains(&dg) && (-2..2).contains(&db) {
+                        result.push(
+                            QOI_OP_DIFF
+                                | ((dr + 2) as u8) << 4
+                                | ((dg + 2) as u8) << 2
+                                | (db + 2) as u8,
+                        );
+                    } else if (-32..32).contains(&dg)
+                        && (-8..8).contains(&dr_dg)
+                        && (-8..8).contains(&db_dg)
+                    {
+                        result.push(QOI_OP_LUMA | (dg + 32) as u8);
+                        result.push(((dr_dg + 8) as u8) << 4 | (db_dg + 8) as u8);
+                    } else {
+                        result.extend_from_slice(&[QOI_OP_RGB, pixel[0], pixel[1], pixel[2]]);
+                    }
+                } else {
+                    result.push(QOI_OP_RGBA);
+                    result.extend_from_slice(&pixel);
+                }
+            }
+            previous = pixel;
+        }
+
+        result.extend_from_slice(&Self::END_MARKER);
+        result
+    }
+
+    /// Writes the image to a file at the given path.
+    pub fn to_file<P: AsRef<std::path::Path>>(&self, path: P) -> Result<()> {
+        std::fs::write(path, self.as_bytes())?;
+        Ok(())
+    }
+}
+
+/// Implements conversion from a byte slice to a QOI image, decoding all pixels.
+impl TryFrom<&[u8]> for Qoi {
+    type Error = Error;
+
+    fn try_from(value: &[u8]) -> Result<Self> {
+        if value.len() < 14 + Self::END_MARKER.len() || value[..4] != Self::MAGIC {
+            return Err("Invalid QOI header".into());
+        }
+
+        let width = u32::from_be_bytes(value[4..8].try_into()?);
+        let height = u32::from_be_bytes(value[8..12].try_into()?);
+        let channels = value[12];
+        let colorspace = value[13];
+        if !matches!(channels, 3 | 4) || colorspace > 1 {
+            return Err("Invalid QOI channels or colorspace".into());
+        }
+
+        // A run covers at most 62 pixels per byte, so the data bounds the image size
+        let data = &value[14..value.len() - Self::END_MARKER.len()];
+        let pixel_count = (width as usize)
+            .checked_mul(height as usize)
+            .filter(|&count| count <= data.len().saturating_mul(62))
+            .ok_or("QOI image is larger than its data can encode")?;
+        let mut samples = Vec::with_capacity(pixel_count.min(data.len()) * channels as usize);
+
+        let mut index = [[0u8; 4]; 64];
+        let mut pixel = [0u8, 0, 0, 255];
+        let mut run = 0u8;
+        let mut offset = 0;
+        let mut next = |count: usize| -> Result<&[u8]> {
+            let bytes = data
+                .get(offset..offset + count)
+                .ok_or("QOI data is truncated")?;
+            offset += count;
+            Ok(bytes)
+        };
+
+        for _ in 0..pixel_count {
+            if run > 0 {
+                run -= 1;
+            } else {
+                let op = next(1)?[0];
+                match op {
+                    QOI_OP_RGB => pixel[..3].copy_from_slice(next(3)?),
+                    QOI_OP_RGBA => pixel.copy_from_slice(next(4)?),
+                    _ => match op & QOI_MASK_2 {
+                        QOI_OP_INDEX => pixel = index[op as usize],
+                        QOI_OP_DIFF => {
+                            pixel[0] = pixel[0].wrapping_add((op >> 4 & 0x03).wrapping_sub(2));
+                            pixel[1] = pixel[1].wrapping_add((op >> 2 & 0x03).wrapping_sub(2));
+                            pixel[2] = pixel[2].wrapping_add((op & 0x03).wrapping_sub(2));
+                        }
+                        QOI_OP_LUMA => {
+                            let second = next(1)?[0];
+                            let dg = (op & 0x3f).wrapping_sub(32);
+                            pixel[0] = pixel[0]
+                                .wrapping_add(dg.wrapping_sub(8).wrapping_add(second >> 4 & 0x0f));
+                            pixel[1] = pixel[1].wrapping_add(dg);
+                            pixel[2] = pixel[2]
+                                .wrapping_add(dg.wrapping_sub(8).wrapping_add(second & 0x0f));
+                        }
+                        _ => run = op & 0x3f,
+                    },
+                }
+                index[hash(pixel)] = pixel;
+            }
+            samples.extend(pixel[..channels as usize].iter().map(|&s| s as u16));
+        }
+
+        let pixels = PixelBuffer::new(width, height, channels, 255, samples)?;
+        Self::from_pixels(pixels, colorspace)
+    }
+}
+
+impl fmt::Display for Qoi {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "QOI {{")?;
+        writeln!(
+            f,
+            "  Size: {}x{}",
+            self.pixels.width(),
+            self.pixels.height()
+        )?;
+        writeln!(f, "  Channels: {}", self.pixels.channels())?;
+        writeln!(f, "  Colorspace: {}", self.colorspace)?;
+        writeln!(f, "}}")?;
+        Ok(())
+    }
+}
+
+fn hash(pixel: [u8; 4]) -> usize {
+    let [r, g, b, a] = pixel.map(|c| c as usize);
+    (r * 3 + g * 5 + b * 7 + a * 11) % 64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gradient(channels: u8) -> PixelBuffer {
+        let (width, height) = (16u32, 8u32);
+        let samples = (0..width * height)
+            .flat_map(|i| {
+                let (x, y) = (i % width, i / width);
+                let pixel = [x * 16, y * 32, (x * y) % 256, if x < 8 { 255 } else { 128 }];
+                pixel.into_iter().take(channels as usize).map(|s| s as u16)
+            })
+            .collect();
+        PixelBuffer::new(width, height, channels, 255, samples).unwrap()
+    }
+
+    #[test]
+    fn test_rgb_roundtrip() {
+        let image = Qoi::from_pixels(gradient(3), 0).unwrap();
+        let decoded = Qoi::try_from(image.as_bytes().as_slice()).unwrap();
+        assert_eq!(decoded.pixels(), image.pixels());
+    }
+
+    #[test]
+    fn test_rgba_roundtrip_with_runs() {
+        let mut pixels = gradient(4);
+        for sample in &mut pixels.samples_mut()[..100] {
+            *sample = 7;
+        }
+        let image = Qoi::from_pixels(pixels, 1).unwrap();
+        let bytes = image.as_bytes();
+        let decoded = Qoi::try_from(bytes.as_slice()).unwrap();
+
+        assert_eq!(decoded.pixels(), image.pixels());
+        assert_eq!(decoded.colorspace(), 1);
+        assert_eq!(decoded.as_bytes(), bytes);
+    }
+
+    #[test]
+    fn test_known_encoding() {
+        // Two identical black pixels encode as a single run of two
+        let pixels = PixelBuffer::new(2, 1, 4, 255, vec![0, 0, 0, 255, 0, 0, 0, 255]).unwrap();
+        let bytes = Qoi::from_pixels(pixels, 0).unwrap().as_bytes();
+        assert_eq!(bytes[14..bytes.len() - 8], [QOI_OP_RUN | 1]);
+    }
+
+    #[test]
+    fn test_invalid_header() {
+        assert!(Qoi::try_from(&b"qoix\0\0\0\x01\0\0\0\x01\x03\0\0\0\0\0\0\0\0\x01"[..]).is_err());
+    }
+
+    #[test]
+    fn test_truncated_data() {
+        let image = Qoi::from_pixels(gradient(3), 0).unwrap();
+        let mut bytes = image.as_bytes();
+        bytes.drain(20..40);
+        assert!(Qoi::try_from(bytes.as_slice()).is_err());
+    }
+
+    #[test]
+    fn test_oversized_header() {
+        // 100000x100000 pixels declared with eight bytes of data
+        let mut bytes = Qoi::MAGIC.to_vec();
+        bytes.extend_from_slice(&100_000u32.to_be_bytes());
+        bytes.extend_from_slice(&100_000u32.to_be_bytes());
+        bytes.extend_from_slice(&[4, 0]);
+        bytes.extend_from_slice(&[0xfd; 8]);
+        bytes.extend_from_slice(&Qoi::END_MARKER);
+
+        let error = Qoi::try_from(bytes.as_slice()).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "QOI image is larger than its data can encode"
+        );
+    }
+}
@@ -0,0 +1,231 @@
+//! Shamir secret sharing of payloads across several carriers.
+//!
+//! Every byte of the secret is the constant term of its own random polynomial
+//! of degree `threshold - 1` over GF(2^8), and share `x` holds the values of
+//! all polynomials at `x`. Any `threshold` shares determine the polynomials
+//! by Lagrange interpolation, while fewer are consistent with every possible
+//! secret and so reveal nothing but its length.
+//!
+//! A short SHA-256 digest is shared along with the secret, so combining the
+//! wrong shares (or damaged ones) is detected instead of returning garbage.
+
+use crate::{Result, gf};
+use rand::Rng;
+use sha2::{Digest, Sha256};
+
+/// Marker at the start of every serialized share.
+pub const MAGIC: [u8; 4] = *b"vSHR";
+
+/// Size of the serialized share header in bytes.
+pub const HEADER_LEN: usize = 10;
+
+/// Number of digest bytes appended to the secret before it is split.
+const DIGEST_LEN: usize = 8;
+
+/// One share of a split secret.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Share {
+    /// Random identifier common to all shares of one secret.
+    pub set_id: [u8; 4],
+    /// Number of shares needed to rebuild the secret.
+    pub threshold: u8,
+    /// Evaluation point of this share, from 1 to the number of shares.
+    pub index: u8,
+    pub data: Vec<u8>,
+}
+
+impl Share {
+    /// Serializes the share for embedding.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_LEN + self.data.len());
+        bytes.extend_from_slice(&MAGIC);
+        bytes.extend_from_slice(&self.set_id);
+        bytes.push(self.threshold);
+        bytes.push(self.index);
+        bytes.extend_from_slice(&self.data);
+        bytes
+    }
+
+    /// Returns true if the bytes look like a serialized share.
+    pub fn is_share(bytes: &[u8]) -> bool {
+        Self::try_from(bytes).is_ok()
+    }
+}
+
+impl TryFrom<&[u8]> for Share {
+    type Error = crate::Error;
+
+    fn try_from(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() < HEADER_LEN || bytes[..4] != MAGIC {
+            return Err("Data is not a secret share".into());
+        }
+        let share = Share {
+            set_id: bytes[4..8].try_into()?,
+            threshold: bytes[8],
+            index: bytes[9],
+            data: bytes[HEADER_LEN..].to_vec(),
+        };
+        if share.threshold < 2 || share.index == 0 {
+            return Err("Invalid secret share header".into());
+        }
+        Ok(share)
+    }
+}
+
+/// Splits a secret into `shares` shares, any `threshold` of which rebuild it.
+pub fn split(secret: &[u8], shares: u8, threshold: u8) -> Result<Vec<Share>> {
+    if threshold < 2 {
+        return Err("The threshold must be at least 2".into());
+    }
+    if shares < threshold {
+        return Err(format!(
+            "Cannot split into {} shares with a threshold of {}",
+            shares, threshold
+        )
+        .into());
+    }
+
+    let mut protected = secret.to_vec();
+    protected.extend_from_slice(&digest(secret));
+
+    let mut rng = rand::rng();
+    let set_id: [u8; 4] = rng.random();
+    let mut result: Vec<Share> = (1..=shares)
+        .map(|index| Share {
+            set_id,
+            threshold,
+            index,
+            data: Vec::with_capacity(protected.len()),
+        })
+        .collect();
+
+    for &byte in &protected {
+        // Highest-degree coefficient first, the secret byte last
+        let mut polynomial = vec![0u8; threshold as usize];
+        rng.fill(&mut polynomial[..threshold as usize - 1]);
+        polynomial[threshold as usize - 1] = byte;
+
+        for share in &mut result {
+            share.data.push(gf::poly_eval(&polynomial, share.index));
+        }
+    }
+    Ok(result)
+}
+
+/// Rebuilds a secret from shares of one set, failing if there are too few.
+pub fn combine(shares: &[Share]) -> Result<Vec<u8>> {
+    let first = shares.first().ok_or("No secret shares given")?;
+    let mut distinct: Vec<&Share> = Vec::new();
+    for share in shares {
+        if share.set_id != first.set_id || share.threshold != first.threshold {
+            return Err("Secret shares belong to different sets".into());
+        }
+        if share.data.len() != first.data.len() {
+            return Err("Secret shares differ in length".into());
+        }
+        if !distinct.iter().any(|s| s.index == share.index) {
+            distinct.push(share);
+        }
+    }
+    if distinct.len() < first.threshold as usize {
+        return Err(format!(
+            "Need {} shares to rebuild the secret, found {}",
+            first.threshold,
+            distinct.len()
+        )
+        .into());
+    }
+    distinct.truncate(first.threshold as usize);
+
+    // Lagrange basis polynomials evaluated at zero
+    let weights: Vec<u8> = distinct
+        .iter()
+        .map(|share| {
+            distinct
+                .iter()
+                .filter(|other| other.index != share.index)
+                .fold(1, |acc, other| {
+                    gf::mul(acc, gf::div(other.index, other.index ^ share.index))
+                })
+        })
+        .collect();
+
+    let mut protected: Vec<u8> = (0..first.data.len())
+        .map(|i| {
+            distinct
+                .iter()
+                .zip(&weights)
+                .fold(0, |acc, (share, &weight)| {
+                    acc ^ gf::mul(share.data[i], weight)
+                })
+        })
+        .collect();
+
+    if protected.len() < DIGEST_LEN {
+        return Err("Secret shares are too short".into());
+    }
+    let check = protected.split_off(protected.len() - DIGEST_LEN);
+    if check != digest(&protected) {
+        return Err("Secret shares do not combine to a valid secret".into());
+    }
+    Ok(protected)
+}
+
+fn digest(secret: &[u8]) -> [u8; DIGEST_LEN] {
+    let hash = Sha256::digest(secret);
+    let mut digest = [0u8; DIGEST_LEN];
+    digest.copy_from_slice(&hash[..DIGEST_LEN]);
+    digest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_any_threshold_subset_combines() {
+        let shares = split(b"the launch code", 5, 3).unwrap();
+        assert_eq!(shares.len(), 5);
+
+        for subset in [[0, 1, 2], [4, 2, 0], [1, 3, 4]] {
+            let chosen: Vec<Share> = subset.iter().map(|&i| shares[i].clone()).collect();
+            assert_eq!(combine(&chosen).unwrap(), b"the launch code");
+        }
+    }
+
+    #[test]
+    fn test_too_few_shares() {
+        let shares = split(b"secret", 4, 3).unwrap();
+        let error = combine(&shares[..2]).unwrap_err().to_string();
+        assert!(error.contains("Need 3 shares"));
+
+        // A duplicate does not count twice
+        assert!(combine(&[shares[0].clone(), shares[0].clone(), shares[1].clone()]).is_err());
+    }
+
+    #[test]
+    fn test_serialization_roundtrip() {
+        let share = &split(b"secret", 2, 2).unwrap()[1];
+        let bytes = share.to_bytes();
+        assert!(Share::is_share(&bytes));
+        assert_eq!(&Share::try_from(bytes.as_slice()).unwrap(), share);
+        assert!(!Share::is_share(b"vEiL0000"));
+    }
+
+    #[test]
+    fn test_mixed_or_damaged_shares() {
+        let a = split(b"one", 3, 2).unwrap();
+        let b = split(b"two", 3, 2).unwrap();
+        assert!(combine(&[a[0].clone(), b[1].clone()]).is_err());
+
+        let mut damaged = a[1].clone();
+        damaged.data[0] ^= 1;
+        assert!(combine(&[a[0].clone(), damaged]).is_err());
+    }
+
+    #[test]
+    fn test_invalid_parameters() {
+        assert!(split(b"x", 3, 1).is_err());
+        assert!(split(b"x", 2, 3).is_err());
+    }
+}
@@ -0,0 +1,334 @@
+//! The chi-square attack of Westfeld and Pfitzmann.
+//!
+//! Overwriting least significant bits with random message bits moves samples
+//! between the values `2i` and `2i + 1`, so after embedding both values of
+//! each pair occur about equally often. The test compares the observed count
+//! of even values with the pair average it would have after full embedding;
+//! the closer they agree, the higher the embedding probability. Pairs are
+//! pooled until every category expects at least five samples, as the test
+//! requires.
+//!
+//! The probability is computed for each channel as a whole and for every row
+//! and column on their own, giving a heatmap of where the data sits.
+
+use crate::pixels::PixelBuffer;
+use crate::steganalysis::{SHADES, json_array, shade};
+
+/// Smallest expected count per category.
+const MIN_EXPECTED: f64 = 5.0;
+
+/// Largest number of cells in a drawn heatmap.
+const HEATMAP_WIDTH: usize = 64;
+
+/// Result of one chi-square test.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Test {
+    pub chi_square: f64,
+    pub degrees_of_freedom: usize,
+    /// Probability that the samples carry LSB-embedded data, from 0 to 1.
+    pub probability: f64,
+}
+
+/// Chi-square results for one channel.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChannelReport {
+    pub channel: u8,
+    pub test: Test,
+    /// Embedding probability of every row.
+    pub rows: Vec<f64>,
+    /// Embedding probability of every column.
+    pub columns: Vec<f64>,
+}
+
+/// Chi-square results for a whole image.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report {
+    pub width: u32,
+    pub height: u32,
+    pub channels: Vec<ChannelReport>,
+}
+
+impl Report {
+    /// Returns the highest embedding probability of all channels.
+    pub fn probability(&self) -> f64 {
+        self.channels
+            .iter()
+            .map(|c| c.test.probability)
+            .fold(0.0, f64::max)
+    }
+
+    /// Renders the report as text with character heatmaps.
+    pub fn to_text(&self) -> String {
+        let mut text = format!(
+            "Chi-square analysis ({}x{}, {} channels)\n",
+            self.width,
+            self.height,
+            self.channels.len()
+        );
+        for channel in &self.channels {
+            text += &format!(
+                "  Channel {}: {:.1}% embedding probability (chi-square {:.2}, {} degrees of freedom)\n",
+                channel.channel,
+                channel.test.probability * 100.0,
+                channel.test.chi_square,
+                channel.test.degrees_of_freedom
+            );
+            text += &format!("    Rows:    [{}]\n", heatmap(&channel.rows));
+            text += &format!("    Columns: [{}]\n", heatmap(&channel.columns));
+        }
+        text += &format!(
+            "  Scale: [{}] from 0% to 100%",
+            String::from_utf8_lossy(SHADES)
+        );
+        text
+    }
+
+    /// Renders the report as a JSON object.
+    pub fn to_json(&self) -> String {
+        let channels: Vec<String> = self
+            .channels
+            .iter()
+            .map(|c| {
+                format!(
+                    "{{\"channel\":{},\"chi_square\":{:.4},\"degrees_of_freedom\":{},\"probability\":{:.4},\"rows\":{},\"columns\":{}}}",
+                    c.channel,
+                    c.test.chi_square,
+                    c.test.degrees_of_freedom,
+                    c.test.probability,
+                    json_array(&c.rows),
+                    json_array(&c.columns)
+                )
+            })
+            .collect();
+        format!(
+            "{{\"width\":{},\"height\":{},\"probability\":{:.4},\"channels\":[{}]}}",
+            self.width,
+            self.height,
+            self.probability(),
+            channels.join(",")
+        )
+    }
+}
+
+/// Runs the chi-square attack on every channel, row and column of an image.
+pub fn analyze(pixels: &PixelBuffer) -> Report {
+    let (width, height) = (pixels.width() as usize, pixels.height() as usize);
+    let channels = pixels.channels() as usize;
+    let samples = pixels.samples();
+    let max_value = pixels.max_value();
+    let sample = |x: usize, y: usize, c: usize| samples[(y * width + x) * channels + c];
+
+    let channels = (0..channels)
+        .map(|c| ChannelReport {
+            channel: c as u8,
+            test: test(samples.iter().skip(c).step_by(channels).copied(), max_value),
+            rows: (0..height)
+                .map(|y| test((0..width).map(|x| sample(x, y, c)), max_value).probability)
+                .collect(),
+            columns: (0..width)
+                .map(|x| test((0..height).map(|y| sample(x, y, c)), max_value).probability)
+                .collect(),
+        })
+        .collect();
+
+    Report {
+        width: pixels.width(),
+        height: pixels.height(),
+        channels,
+    }
+}
+
+/// Tests whether the values of each pair `(2i, 2i + 1)` occur equally often.
+pub fn test(samples: impl IntoIterator<Item = u16>, max_value: u16) -> Test {
+    let mut histogram = vec![0u64; max_value as usize + 2];
+    for sample in samples {
+        histogram[sample as usize] += 1;
+    }
+
+    // Pool consecutive pairs until each category expects enough samples
+    let mut categories = Vec::new();
+    let (mut observed, mut expected) = (0.0, 0.0);
+    for pair in histogram.chunks_exact(2) {
+        observed += pair[0] as f64;
+        expected += (pair[0] + pair[1]) as f64 / 2.0;
+        if expected >= MIN_EXPECTED {
+            categories.push((observed, expected));
+            (observed, expected) = (0.0, 0.0);
+        }
+    }
+    if let Some(last) = categories.last_mut() {
+        last.0 += observed;
+        last.1 += expected;
+    }
+
+    let chi_square: f64 = categories
+        .iter()
+        .map(|(observed, expected)| (observed - expected).powi(2) / expected)
+        .sum();
+    let degrees_of_freedom = categories.len().saturating_sub(1);
+    let probability = if degrees_of_freedom == 0 {
+        0.0
+    } else {
+        upper_gamma(degrees_of_freedom as f64 / 2.0, chi_square / 2.0)
+    };
+
+    Test {
+        chi_square,
+        degrees_of_freedom,
+        probability,
+    }
+}
+
+/// Draws probabilities as shade characters, averaging them into at most
+/// [`HEATMAP_WIDTH`] cells.
+fn heatmap(values: &[f64]) -> String {
+    let cells = values.len().min(HEATMAP_WIDTH);
+    (0..cells)
+        .map(|i| {
+            let cell = &values[i * values.len() / cells..(i + 1) * values.len() / cells];
+            shade(cell.iter().sum::<f64>() / cell.len() as f64)
+        })
+        .collect()
+}
+
+/// Regularized upper incomplete gamma function Q(a, x), which gives the
+/// chi-square survival function as Q(df / 2, chi / 2).
+fn upper_gamma(a: f64, x: f64) -> f64 {
+    const EPSILON: f64 = 1e-12;
+    const TINY: f64 = 1e-300;
+    if x <= 0.0 {
+        return 1.0;
+    }
+    let prefactor = (-x + a * x.ln() - ln_gamma(a)).exp();
+
+    if x < a + 1.0 {
+        // Series for the lower function P(a, x)
+        let (mut term, mut sum, mut n) = (1.0 / a, 1.0 / a, a);
+        for _ in 0..1000 {
+            n += 1.0;
+            term *= x / n;
+            sum += term;
+            if term.abs() < sum.abs() * EPSILON {
+                break;
+            }
+        }
+        (1.0 - sum * prefactor).clamp(0.0, 1.0)
+    } else {
+        // Continued fraction for Q(a, x), evaluated with Lentz's method
+        let mut b = x + 1.0 - a;
+        let mut c = 1.0 / TINY;
+        let mut d = 1.0 / b;
+        let mut h = d;
+        for i in 1..1000 {
+            let an = -(i as f64) * (i as f64 - a);
+            b += 2.0;
+            d = an * d + b;
+            if d.abs() < TINY {
+                d = TINY;
+            }
+            c = b + an / c;
+            if c.abs() < TINY {
+                c = TINY;
+            }
+            d = 1.0 / d;
+            let delta = d * c;
+            h *= delta;
+            if (delta - 1.0).abs() < EPSILON {
+                break;
+            }
+        }
+        (prefactor * h).clamp(0.0, 1.0)
+    }
+}
+
+/// Natural logarithm of the gamma function (Lanczos approximation).
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 6] = [
+        76.18009172947146,
+        -86.50532032941677,
+        24.01409824083091,
+        -1.231739572450155,
+        0.1208650973866179e-2,
+        -0.5395239384953e-5,
+    ];
+    let mut y = x;
+    let tmp = x + 5.5;
+    let tmp = tmp - (x + 0.5) * tmp.ln();
+    let mut series = 1.000000000190015;
+    for coefficient in COEFFICIENTS {
+        y += 1.0;
+        series += coefficient / y;
+    }
+    -tmp + (2.5066282746310005 * series / x).ln()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pixels::lsb::{self, LsbMode};
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+
+    /// A cover whose even values are three times as common as odd ones.
+    fn cover(width: u32, height: u32) -> PixelBuffer {
+        let mut rng = ChaCha8Rng::seed_from_u64(7);
+        let samples = (0..width * height)
+            .map(|_| 2 * rng.random_range(20..100u16) + rng.random_bool(0.25) as u16)
+            .collect();
+        PixelBuffer::new(width, height, 1, 255, samples).unwrap()
+    }
+
+    #[test]
+    fn test_upper_gamma() {
+        // Chi-square survival function with 2 degrees of freedom is exp(-x / 2)
+        assert!((upper_gamma(1.0, 1.5) - (-1.5f64).exp()).abs() < 1e-9);
+        assert!((upper_gamma(5.0, 3.0) - 0.815263).abs() < 1e-6);
+        assert!((ln_gamma(5.0) - 24f64.ln()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_clean_cover_scores_low() {
+        let report = analyze(&cover(64, 64));
+        assert!(report.probability() < 0.01);
+    }
+
+    #[test]
+    fn test_full_embedding_scores_high() {
+        let mut pixels = cover(64, 64);
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        let message: Vec<u8> = (0..lsb::capacity(&pixels)).map(|_| rng.random()).collect();
+        lsb::embed(&mut pixels, &message, LsbMode::Replace).unwrap();
+
+        let report = analyze(&pixels);
+        assert!(report.probability() > 0.5);
+    }
+
+    #[test]
+    fn test_heatmap_locates_partial_embedding() {
+        // Equalize the pairs in the top half only
+        let mut pixels = cover(64, 64);
+        let mut rng = ChaCha8Rng::seed_from_u64(2);
+        for sample in &mut pixels.samples_mut()[..64 * 32] {
+            *sample = (*sample & !1) | rng.random_range(0..2);
+        }
+
+        let channel = &analyze(&pixels).channels[0];
+        let top = channel.rows[..32].iter().sum::<f64>() / 32.0;
+        let bottom = channel.rows[32..].iter().sum::<f64>() / 32.0;
+        assert!(top > 0.5 && bottom < 0.25);
+    }
+
+    #[test]
+    fn test_report_formats() {
+        let report = analyze(&cover(8, 8));
+        assert!(
+            report
+                .to_text()
+                .starts_with("Chi-square analysis (8x8, 1 channels)")
+        );
+
+        let json = report.to_json();
+        assert!(json.starts_with("{\"width\":8,\"height\":8,"));
+        assert_eq!(json.matches("\"rows\":[").count(), 1);
+    }
+}
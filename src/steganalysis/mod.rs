@@ -0,0 +1,196 @@
+//! Statistical detection of LSB embedding in decoded pixels.
+//!
+//! Finding a `vEiL` chunk only tells whether this tool hid something. The
+//! detectors here look at the samples themselves, so they also notice data
+//! hidden by other tools that overwrite least significant bits. The
+//! [`signatures`] scanner adds the footprints such tools leave in the file.
+
+pub mod chi_square;
+pub mod rs;
+pub mod signatures;
+pub mod spa;
+pub mod stats;
+
+use crate::pixels::PixelBuffer;
+
+/// Output format of analysis reports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum ReportFormat {
+    /// Human-readable text with heatmaps drawn in characters.
+    #[default]
+    Text,
+    /// A single JSON object.
+    Json,
+}
+
+/// Embedding rate estimates for one channel, as fractions of its samples.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateEstimate {
+    pub channel: u8,
+    pub rs: Option<f64>,
+    pub spa: Option<f64>,
+}
+
+/// Results of all detectors for one image.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Analysis {
+    pub chi_square: chi_square::Report,
+    pub rates: Vec<RateEstimate>,
+}
+
+impl Analysis {
+    /// Renders the results as text.
+    pub fn to_text(&self) -> String {
+        let percent =
+            |rate: Option<f64>| rate.map_or("n/a".to_string(), |r| format!("{:.1}%", r * 100.0));
+        let mut text = self.chi_square.to_text();
+        text += "\nEstimated embedding rate";
+        for rate in &self.rates {
+            text += &format!(
+                "\n  Channel {}: RS analysis {}, sample pair analysis {}",
+                rate.channel,
+                percent(rate.rs),
+                percent(rate.spa)
+            );
+        }
+        text
+    }
+
+    /// Renders the results as a JSON object.
+    pub fn to_json(&self) -> String {
+        let number = |rate: Option<f64>| rate.map_or("null".to_string(), |r| format!("{:.4}", r));
+        let rates: Vec<String> = self
+            .rates
+            .iter()
+            .map(|rate| {
+                format!(
+                    "{{\"channel\":{},\"rs\":{},\"spa\":{}}}",
+                    rate.channel,
+                    number(rate.rs),
+                    number(rate.spa)
+                )
+            })
+            .collect();
+        format!(
+            "{{\"chi_square\":{},\"rates\":[{}]}}",
+            self.chi_square.to_json(),
+            rates.join(",")
+        )
+    }
+}
+
+/// Runs every detector on the decoded pixels of an image.
+pub fn analyze(pixels: &PixelBuffer) -> Analysis {
+    Analysis {
+        chi_square: chi_square::analyze(pixels),
+        rates: (0..pixels.channels())
+            .map(|channel| RateEstimate {
+                channel,
+                rs: rs::estimate(pixels, channel),
+                spa: spa::estimate(pixels, channel),
+            })
+            .collect(),
+    }
+}
+
+/// Returns the Shannon entropy of the bytes in bits per byte.
+pub fn entropy(bytes: &[u8]) -> f64 {
+    let mut counts = [0usize; 256];
+    for &byte in bytes {
+        counts[byte as usize] += 1;
+    }
+    let total = bytes.len() as f64;
+    counts
+        .iter()
+        .filter(|&&count| count > 0)
+        .map(|&count| {
+            let p = count as f64 / total;
+            p * (1.0 / p).log2()
+        })
+        .sum()
+}
+
+/// Characters used to draw heatmaps and histograms, from empty to full.
+const SHADES: &[u8] = b" .:-=+*#%@";
+
+/// Returns the shade character for a fraction between 0 and 1.
+fn shade(fraction: f64) -> char {
+    let level = (fraction * (SHADES.len() - 1) as f64).round() as usize;
+    SHADES[level.min(SHADES.len() - 1)] as char
+}
+
+/// Formats a list of numbers as a JSON array.
+fn json_array(values: &[f64]) -> String {
+    let items: Vec<String> = values.iter().map(|v| format!("{:.4}", v)).collect();
+    format!("[{}]", items.join(","))
+}
+
+/// Iterates over the rows of one channel.
+fn channel_rows(pixels: &PixelBuffer, channel: u8) -> impl Iterator<Item = Vec<u16>> + '_ {
+    let channels = pixels.channels() as usize;
+    pixels
+        .samples()
+        .chunks_exact(pixels.width().max(1) as usize * channels)
+        .map(move |row| {
+            row.iter()
+                .skip(channel as usize)
+                .step_by(channels)
+                .copied()
+                .collect()
+        })
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use crate::pixels::PixelBuffer;
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+
+    /// A smooth 8-bit graymap with mild noise, standing in for a photograph.
+    pub fn cover() -> PixelBuffer {
+        let mut rng = ChaCha8Rng::seed_from_u64(42);
+        let (width, height) = (256u32, 256u32);
+        let samples = (0..width * height)
+            .map(|i| {
+                let (x, y) = ((i % width) as f64, (i / width) as f64);
+                let value = 128.0
+                    + 60.0 * (x / 23.0).sin() * (y / 31.0).cos()
+                    + 20.0 * (x / 7.0 + y / 11.0).sin();
+                (value + rng.random_range(-2.5..2.5) + rng.random_range(-2.5..2.5))
+                    .round()
+                    .clamp(0.0, 255.0) as u16
+            })
+            .collect();
+        PixelBuffer::new(width, height, 1, 255, samples).unwrap()
+    }
+
+    #[test]
+    fn test_analysis_report() {
+        let analysis = super::analyze(&cover());
+        assert_eq!(analysis.rates.len(), 1);
+        assert!(analysis.to_text().contains("Channel 0: RS analysis"));
+
+        let json = analysis.to_json();
+        assert!(json.starts_with("{\"chi_square\":{\"width\":256"));
+        assert!(json.ends_with("}]}"));
+    }
+
+    #[test]
+    fn test_entropy() {
+        assert_eq!(super::entropy(b""), 0.0);
+        assert_eq!(super::entropy(b"aaaa"), 0.0);
+        assert_eq!(super::entropy(b"abab"), 1.0);
+        let all: Vec<u8> = (0..=255).collect();
+        assert_eq!(super::entropy(&all), 8.0);
+    }
+
+    /// Replaces the LSBs of the given fraction of samples with random bits.
+    pub fn embed_at_rate(pixels: &mut PixelBuffer, rate: f64) {
+        let mut rng = ChaCha8Rng::seed_from_u64(9);
+        for sample in pixels.samples_mut() {
+            if rng.random_bool(rate) {
+                *sample = (*sample & !1) | rng.random_range(0..2);
+            }
+        }
+    }
+}
@@ -0,0 +1,118 @@
+//! RS analysis (Fridrich, Goljan and Du).
+//!
+//! Samples are split into groups of four along each row and scored by their
+//! smoothness, the sum of absolute differences between neighbours. Applying
+//! the flipping `F1` (`2i <-> 2i + 1`) or the shifted flipping `F-1`
+//! (`2i - 1 <-> 2i`) to the middle two samples of a group makes it Regular
+//! when the score increases and Singular when it decreases. In a natural
+//! image both flippings produce more regular than singular groups, by about
+//! the same margin. LSB embedding drives the `F1` counts together while the
+//! `F-1` counts drift apart, and measuring the counts again with every LSB
+//! inverted gives enough equations to solve for the embedding rate.
+
+use crate::pixels::PixelBuffer;
+use crate::steganalysis::channel_rows;
+
+/// Which samples of a group are flipped.
+const MASK: [i32; 4] = [0, 1, 1, 0];
+
+/// Estimates the fraction of samples of a channel that carry message bits.
+///
+/// Returns `None` when the image has too few groups or the equations have
+/// no solution, which happens for flat or synthetic images.
+pub fn estimate(pixels: &PixelBuffer, channel: u8) -> Option<f64> {
+    let rows: Vec<Vec<i32>> = channel_rows(pixels, channel)
+        .map(|row| row.into_iter().map(i32::from).collect())
+        .collect();
+    let inverted: Vec<Vec<i32>> = rows
+        .iter()
+        .map(|row| row.iter().map(|&x| x ^ 1).collect())
+        .collect();
+
+    let d0 = difference(&rows, 1)?;
+    let d1 = difference(&inverted, 1)?;
+    let n0 = difference(&rows, -1)?;
+    let n1 = difference(&inverted, -1)?;
+
+    // Roots of 2(d1 + d0)x^2 + (n0 - n1 - d1 - 3d0)x + d0 - n0, the smaller one
+    let x = smaller_root(2.0 * (d1 + d0), n0 - n1 - d1 - 3.0 * d0, d0 - n0)?;
+    let rate = x / (x - 0.5);
+    rate.is_finite().then(|| rate.clamp(0.0, 1.0))
+}
+
+/// Returns the fraction of regular minus the fraction of singular groups.
+fn difference(rows: &[Vec<i32>], sign: i32) -> Option<f64> {
+    let (mut regular, mut singular, mut groups) = (0usize, 0usize, 0usize);
+    for group in rows.iter().flat_map(|row| row.chunks_exact(MASK.len())) {
+        let flipped: Vec<i32> = group
+            .iter()
+            .zip(MASK)
+            .map(|(&x, m)| flip(x, m * sign))
+            .collect();
+        let (before, after) = (smoothness(group), smoothness(&flipped));
+        if after > before {
+            regular += 1;
+        } else if after < before {
+            singular += 1;
+        }
+        groups += 1;
+    }
+    (groups > 0).then(|| (regular as f64 - singular as f64) / groups as f64)
+}
+
+/// Applies `F1`, `F-1` or the identity to a sample.
+fn flip(x: i32, mask: i32) -> i32 {
+    match mask {
+        1 => x ^ 1,
+        -1 => ((x + 1) ^ 1) - 1,
+        _ => x,
+    }
+}
+
+fn smoothness(group: &[i32]) -> i32 {
+    group.windows(2).map(|pair| (pair[1] - pair[0]).abs()).sum()
+}
+
+/// Returns the root of `ax^2 + bx + c` with the smaller magnitude.
+pub(crate) fn smaller_root(a: f64, b: f64, c: f64) -> Option<f64> {
+    if a.abs() < f64::EPSILON {
+        return (b.abs() > f64::EPSILON).then(|| -c / b);
+    }
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+    let root = discriminant.sqrt();
+    let (x1, x2) = ((-b + root) / (2.0 * a), (-b - root) / (2.0 * a));
+    Some(if x1.abs() < x2.abs() { x1 } else { x2 })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::steganalysis::tests::{cover, embed_at_rate};
+
+    #[test]
+    fn test_flippings() {
+        assert_eq!((flip(4, 1), flip(5, 1)), (5, 4));
+        assert_eq!((flip(4, -1), flip(3, -1)), (3, 4));
+        assert_eq!(flip(7, 0), 7);
+    }
+
+    #[test]
+    fn test_recovers_embedding_rates() {
+        for rate in [0.0, 0.25, 0.5, 0.75] {
+            let mut pixels = cover();
+            embed_at_rate(&mut pixels, rate);
+            let estimate = estimate(&pixels, 0).unwrap();
+            assert!((estimate - rate).abs() < 0.06, "{} for {}", estimate, rate);
+        }
+    }
+
+    #[test]
+    fn test_smaller_root() {
+        assert_eq!(smaller_root(1.0, -3.0, 2.0), Some(1.0));
+        assert_eq!(smaller_root(0.0, 2.0, -1.0), Some(0.5));
+        assert_eq!(smaller_root(1.0, 0.0, 1.0), None);
+    }
+}
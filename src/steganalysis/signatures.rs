@@ -0,0 +1,656 @@
+//! Footprints left by other steganography tools.
+//!
+//! Each [`Detector`] looks for one kind of artifact: readable text in a
+//! least significant bit plane (as `zsteg` reports it), marker strings that
+//! tools write next to their payload, chunk names and `tEXt` keywords that
+//! no common encoder produces, ancillary chunks that look like ciphertext,
+//! and bytes after `IEND`. A [`Scanner`] runs a set of detectors and combines
+//! their findings into one [`Verdict`].
+//!
+//! Some tools leave no fixed footprint. steghide, for example, encrypts its
+//! header and scatters it over positions chosen by the passphrase, so only
+//! the statistical detectors of this module's parent can notice it.
+
+use crate::pixels::PixelBuffer;
+use crate::png::Png;
+use crate::steganalysis::stats::{COMPRESSED_CHUNKS, Stats};
+
+/// Everything a detector may look at.
+#[derive(Debug, Clone, Copy)]
+pub struct Target<'a> {
+    /// The raw bytes of the file.
+    pub bytes: &'a [u8],
+    /// The parsed PNG, if the file is one.
+    pub png: Option<&'a Png>,
+    /// The decoded pixels, if the file is an image.
+    pub pixels: Option<&'a PixelBuffer>,
+}
+
+/// One artifact found by a detector.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Finding {
+    /// Name of the detector that reported it.
+    pub detector: &'static str,
+    /// How sure the detector is that the artifact means hidden data, from 0 to 1.
+    pub confidence: f64,
+    pub description: String,
+}
+
+/// Looks for one kind of artifact.
+pub trait Detector {
+    /// Short name shown with every finding.
+    fn name(&self) -> &'static str;
+
+    /// Returns everything suspicious found in the target.
+    fn detect(&self, target: &Target) -> Vec<Finding>;
+}
+
+/// A registry of detectors that are run together.
+pub struct Scanner {
+    detectors: Vec<Box<dyn Detector>>,
+}
+
+impl Scanner {
+    /// Creates a scanner without any detectors.
+    pub fn empty() -> Self {
+        Self {
+            detectors: Vec::new(),
+        }
+    }
+
+    /// Adds a detector to the scanner.
+    pub fn register(&mut self, detector: impl Detector + 'static) {
+        self.detectors.push(Box::new(detector));
+    }
+
+    /// Returns the names of all registered detectors.
+    pub fn detectors(&self) -> Vec<&'static str> {
+        self.detectors.iter().map(|d| d.name()).collect()
+    }
+
+    /// Runs all detectors on the target.
+    pub fn scan(&self, target: &Target) -> Verdict {
+        let mut findings: Vec<Finding> = self
+            .detectors
+            .iter()
+            .flat_map(|detector| detector.detect(target))
+            .collect();
+        findings.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+        Verdict { findings }
+    }
+}
+
+/// A scanner with all built-in detectors.
+impl Default for Scanner {
+    fn default() -> Self {
+        let mut scanner = Self::empty();
+        scanner.register(LsbText);
+        scanner.register(ToolMarkers);
+        scanner.register(KnownChunks);
+        scanner.register(TextKeywords);
+        scanner.register(EncryptedChunks);
+        scanner.register(TrailingData);
+        scanner
+    }
+}
+
+/// The combined result of a scan.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Verdict {
+    /// All findings, the most confident first.
+    pub findings: Vec<Finding>,
+}
+
+impl Verdict {
+    /// Returns the probability that the file carries hidden data, treating
+    /// the findings as independent pieces of evidence.
+    pub fn confidence(&self) -> f64 {
+        1.0 - self
+            .findings
+            .iter()
+            .map(|f| 1.0 - f.confidence)
+            .product::<f64>()
+    }
+
+    /// Summarizes the confidence in words.
+    pub fn summary(&self) -> &'static str {
+        match self.confidence() {
+            c if c >= 0.75 => "hidden data likely",
+            c if c >= 0.4 => "hidden data possible",
+            c if c > 0.0 => "hidden data unlikely",
+            _ => "no tool signatures found",
+        }
+    }
+
+    /// Renders the verdict and its findings as text.
+    pub fn to_text(&self) -> String {
+        let mut text = format!(
+            "Signature scan: {} ({:.0}% confidence)",
+            self.summary(),
+            self.confidence() * 100.0
+        );
+        for finding in &self.findings {
+            text += &format!(
+                "\n  [{:>3.0}%] {}: {}",
+                finding.confidence * 100.0,
+                finding.detector,
+                finding.description
+            );
+        }
+        text
+    }
+}
+
+/// Bytes of each bit stream that are searched for text and markers.
+const STREAM_LEN: usize = 4096;
+
+/// Shortest run of printable characters reported as LSB text.
+const MIN_TEXT_LEN: usize = 12;
+
+/// Readable text at the start of a least significant bit plane.
+///
+/// Like `zsteg`, this reads bit 0 of all channels together and of every
+/// channel alone, row by row, packing bits both MSB and LSB first.
+pub struct LsbText;
+
+impl Detector for LsbText {
+    fn name(&self) -> &'static str {
+        "lsb-text"
+    }
+
+    fn detect(&self, target: &Target) -> Vec<Finding> {
+        let Some(pixels) = target.pixels else {
+            return Vec::new();
+        };
+        lsb_streams(pixels)
+            .into_iter()
+            .filter_map(|(label, stream)| {
+                let text_len = stream.iter().take_while(|&&b| is_text(b)).count();
+                (text_len >= MIN_TEXT_LEN).then(|| Finding {
+                    detector: self.name(),
+                    confidence: if text_len >= 32 { 0.9 } else { 0.6 },
+                    description: format!(
+                        "{} starts with {} characters of text: \"{}\"",
+                        label,
+                        text_len,
+                        preview(&stream[..text_len])
+                    ),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Strings that tools write next to their payload, with the tool's name.
+pub const TOOL_MARKERS: &[(&[u8], &str)] = &[
+    (b"OPENSTEGO", "OpenStego"),
+    (
+        b"JPEG Encoder Copyright 1998, James R. Weeks and BioElectroMedical Systems",
+        "F5",
+    ),
+];
+
+/// Marker strings of known tools, in the raw file or an LSB plane.
+pub struct ToolMarkers;
+
+impl Detector for ToolMarkers {
+    fn name(&self) -> &'static str {
+        "tool-marker"
+    }
+
+    fn detect(&self, target: &Target) -> Vec<Finding> {
+        let mut sources = vec![("the file".to_string(), target.bytes.to_vec())];
+        if let Some(pixels) = target.pixels {
+            sources.extend(lsb_streams(pixels));
+        }
+
+        let mut findings = Vec::new();
+        for (marker, tool) in TOOL_MARKERS {
+            for (source, bytes) in &sources {
+                if let Some(offset) = find(bytes, marker) {
+                    findings.push(Finding {
+                        detector: self.name(),
+                        confidence: 0.95,
+                        description: format!("{} marker in {} at byte {}", tool, source, offset),
+                    });
+                }
+            }
+        }
+        findings
+    }
+}
+
+/// Private chunk types written by steganography tools, with the tool's name.
+pub const STEGO_CHUNKS: &[(&str, &str)] = &[
+    ("veIl", "veil"),
+    ("vEiL", "veil"),
+    ("pkZp", "veil ZIP polyglot"),
+];
+
+/// Registered extensions and private chunks of common encoders, which are not suspicious.
+pub const KNOWN_CHUNKS: &[&str] = &[
+    "sTER", "gIFg", "gIFx", "gIFt", "fRAc", "dSIG", "iDOT", "CgBI", "vpAg", "caNv", "mkBF", "mkBS",
+    "mkBT", "mkTS", "npTc", "npLb", "npOl",
+];
+
+/// Chunk types that tools are known for, and ones no common encoder writes.
+pub struct KnownChunks;
+
+impl Detector for KnownChunks {
+    fn name(&self) -> &'static str {
+        "chunk-name"
+    }
+
+    fn detect(&self, target: &Target) -> Vec<Finding> {
+        let Some(png) = target.png else {
+            return Vec::new();
+        };
+        png.custom_chunks()
+            .into_iter()
+            .filter_map(|chunk| {
+                let name = chunk.chunk_type().to_string();
+                if let Some((_, tool)) = STEGO_CHUNKS.iter().find(|(n, _)| *n == name) {
+                    Some(Finding {
+                        detector: self.name(),
+                        confidence: 0.95,
+                        description: format!("'{}' chunk written by {}", name, tool),
+                    })
+                } else if KNOWN_CHUNKS.contains(&name.as_str()) {
+                    None
+                } else {
+                    Some(Finding {
+                        detector: self.name(),
+                        confidence: 0.4,
+                        description: format!(
+                            "Unknown '{}' chunk of {} bytes",
+                            name,
+                            chunk.length()
+                        ),
+                    })
+                }
+            })
+            .collect()
+    }
+}
+
+/// Keywords defined by the PNG specification and written by common software.
+pub const KNOWN_KEYWORDS: &[&str] = &[
+    "Title",
+    "Author",
+    "Description",
+    "Copyright",
+    "Creation Time",
+    "Software",
+    "Disclaimer",
+    "Warning",
+    "Source",
+    "Comment",
+    "XML:com.adobe.xmp",
+    "date:create",
+    "date:modify",
+    "date:timestamp",
+    "Raw profile type exif",
+    "Raw profile type icc",
+    "Raw profile type iptc",
+    "Raw profile type xmp",
+    "parameters",
+    "prompt",
+    "workflow",
+];
+
+/// Words in a keyword that suggest it labels a payload.
+const SUSPICIOUS_WORDS: &[&str] = &[
+    "secret", "hidden", "flag", "password", "passwd", "key", "payload", "stego", "cipher",
+];
+
+/// Text chunks with telling keywords or values that look like encoded binary data.
+pub struct TextKeywords;
+
+impl Detector for TextKeywords {
+    fn name(&self) -> &'static str {
+        "text-keyword"
+    }
+
+    fn detect(&self, target: &Target) -> Vec<Finding> {
+        let Some(png) = target.png else {
+            return Vec::new();
+        };
+
+        let mut findings = Vec::new();
+        for chunk in png.chunks() {
+            let chunk_type = chunk.chunk_type().to_string();
+            let Some((keyword, value)) = text_entry(&chunk_type, chunk.data()) else {
+                continue;
+            };
+
+            let words: Vec<String> = keyword
+                .split(|c: char| !c.is_ascii_alphanumeric())
+                .map(|word| word.to_ascii_lowercase())
+                .collect();
+            if words.iter().any(|w| SUSPICIOUS_WORDS.contains(&w.as_str())) {
+                findings.push(Finding {
+                    detector: self.name(),
+                    confidence: 0.6,
+                    description: format!("{} keyword \"{}\"", chunk_type, keyword),
+                });
+            } else if !KNOWN_KEYWORDS.contains(&keyword.as_str()) {
+                findings.push(Finding {
+                    detector: self.name(),
+                    confidence: 0.2,
+                    description: format!("Uncommon {} keyword \"{}\"", chunk_type, keyword),
+                });
+            }
+
+            if let Some(encoding) = value.as_deref().and_then(binary_encoding) {
+                findings.push(Finding {
+                    detector: self.name(),
+                    confidence: 0.5,
+                    description: format!(
+                        "{} \"{}\" holds {} bytes of {}",
+                        chunk_type,
+                        keyword,
+                        value.as_deref().map_or(0, str::len),
+                        encoding
+                    ),
+                });
+            }
+        }
+        findings
+    }
+}
+
+/// Ancillary chunks whose data is as random as ciphertext.
+///
+/// Chunks that are compressed by definition (`zTXt`, `iTXt`, `iCCP` and
+/// `fdAT`) are skipped, since deflate output is close to random as well.
+pub struct EncryptedChunks;
+
+impl Detector for EncryptedChunks {
+    fn name(&self) -> &'static str {
+        "entropy"
+    }
+
+    fn detect(&self, target: &Target) -> Vec<Finding> {
+        let Some(png) = target.png else {
+            return Vec::new();
+        };
+        png.chunks()
+            .iter()
+            .filter(|chunk| {
+                let chunk_type = chunk.chunk_type();
+                !chunk_type.is_critical()
+                    && !COMPRESSED_CHUNKS.contains(&chunk_type.to_string().as_str())
+            })
+            .filter_map(|chunk| {
+                let stats = Stats::of(chunk.data());
+                stats.looks_random().then(|| Finding {
+                    detector: self.name(),
+                    confidence: if stats.length >= 256 && stats.entropy_ratio() >= 0.97 {
+                        0.8
+                    } else {
+                        0.5
+                    },
+                    description: format!(
+                        "'{}' chunk of {} bytes has {:.2} bits of entropy per byte",
+                        chunk.chunk_type(),
+                        stats.length,
+                        stats.entropy
+                    ),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Data appended after the end of a PNG.
+pub struct TrailingData;
+
+impl Detector for TrailingData {
+    fn name(&self) -> &'static str {
+        "trailing-data"
+    }
+
+    fn detect(&self, target: &Target) -> Vec<Finding> {
+        match target.png.map(Png::trailing_data) {
+            Some(trailing) if !trailing.is_empty() => vec![Finding {
+                detector: self.name(),
+                confidence: 0.7,
+                description: format!("{} bytes after IEND", trailing.len()),
+            }],
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Returns the least significant bit streams read by [`LsbText`], with zsteg-style labels.
+fn lsb_streams(pixels: &PixelBuffer) -> Vec<(String, Vec<u8>)> {
+    let channels = pixels.channels() as usize;
+    let names: &[&str] = match channels {
+        1 => &["y"],
+        2 => &["y", "a"],
+        3 => &["r", "g", "b"],
+        _ => &["r", "g", "b", "a"],
+    };
+
+    let mut selections = vec![(names.concat(), (0..channels).collect::<Vec<_>>())];
+    if channels > 1 {
+        selections.extend((0..channels).map(|c| (names[c.min(3)].to_string(), vec![c])));
+    }
+
+    let mut streams = Vec::new();
+    for (name, selected) in selections {
+        let bits: Vec<u8> = pixels
+            .samples()
+            .chunks_exact(channels)
+            .flat_map(|pixel| selected.iter().map(move |&c| (pixel[c] & 1) as u8))
+            .take(STREAM_LEN * 8)
+            .collect();
+        for msb_first in [true, false] {
+            let bytes = bits
+                .chunks_exact(8)
+                .map(|byte| {
+                    byte.iter().enumerate().fold(0u8, |acc, (i, &bit)| {
+                        acc | bit << if msb_first { 7 - i } else { i }
+                    })
+                })
+                .collect();
+            let order = if msb_first { "msb" } else { "lsb" };
+            streams.push((format!("b1,{},{},xy", name, order), bytes));
+        }
+    }
+    streams
+}
+
+/// Returns the keyword and (if readable) the text of a `tEXt`, `zTXt` or `iTXt` chunk.
+fn text_entry(chunk_type: &str, data: &[u8]) -> Option<(String, Option<String>)> {
+    let separator = data.iter().position(|&b| b == 0)?;
+    let keyword = String::from_utf8_lossy(&data[..separator]).into_owned();
+    let rest = &data[separator + 1..];
+    let inflate = |bytes: &[u8]| miniz_oxide::inflate::decompress_to_vec_zlib(bytes).ok();
+
+    let value = match chunk_type {
+        "tEXt" => Some(rest.to_vec()),
+        "zTXt" => rest.get(1..).and_then(inflate),
+        "iTXt" => {
+            // Compression flag and method, then language tag and translated keyword
+            let compressed = *rest.first()? == 1;
+            let mut fields = rest.get(2..)?.splitn(3, |&b| b == 0);
+            let text = fields.nth(2)?;
+            if compressed {
+                inflate(text)
+            } else {
+                Some(text.to_vec())
+            }
+        }
+        _ => return None,
+    };
+    Some((
+        keyword,
+        value.map(|v| String::from_utf8_lossy(&v).into_owned()),
+    ))
+}
+
+/// Names the encoding if the text looks like hex or base64 encoded binary data.
+fn binary_encoding(text: &str) -> Option<&'static str> {
+    let text = text.trim();
+    if text.len() >= 32
+        && text.len().is_multiple_of(2)
+        && text.bytes().all(|b| b.is_ascii_hexdigit())
+    {
+        Some("hex")
+    } else if text.len() >= 40
+        && text
+            .trim_end_matches('=')
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'+' || b == b'/')
+        && text.bytes().any(|b| b.is_ascii_digit())
+        && text.bytes().any(|b| b.is_ascii_uppercase())
+    {
+        Some("base64")
+    } else {
+        None
+    }
+}
+
+fn is_text(byte: u8) -> bool {
+    matches!(byte, b' '..=b'~' | b'\t' | b'\n' | b'\r')
+}
+
+/// Shortens text for display, escaping line breaks.
+fn preview(text: &[u8]) -> String {
+    let text = String::from_utf8_lossy(&text[..text.len().min(40)]);
+    let escaped = text.escape_default().to_string();
+    if text.len() == 40 {
+        escaped + "..."
+    } else {
+        escaped
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::png::{Chunk, ChunkType};
+    use crate::steganalysis::tests::cover;
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+    use std::str::FromStr;
+
+    fn chunk(chunk_type: &str, data: &[u8]) -> Chunk {
+        Chunk::new(ChunkType::from_str(chunk_type).unwrap(), data.to_vec())
+    }
+
+    /// Writes bytes MSB first into the LSBs of the pixels.
+    fn embed_lsb(pixels: &mut PixelBuffer, data: &[u8]) {
+        let bits = data
+            .iter()
+            .flat_map(|&b| (0..8).rev().map(move |i| (b >> i) & 1));
+        for (sample, bit) in pixels.samples_mut().iter_mut().zip(bits) {
+            *sample = (*sample & !1) | bit as u16;
+        }
+    }
+
+    fn scan(png: Option<&Png>, pixels: Option<&PixelBuffer>) -> Verdict {
+        let bytes = png.map(Png::as_bytes).unwrap_or_default();
+        Scanner::default().scan(&Target {
+            bytes: &bytes,
+            png,
+            pixels,
+        })
+    }
+
+    #[test]
+    fn test_clean_image() {
+        let verdict = scan(None, Some(&cover()));
+        assert!(verdict.findings.is_empty());
+        assert_eq!(verdict.confidence(), 0.0);
+        assert_eq!(verdict.summary(), "no tool signatures found");
+    }
+
+    #[test]
+    fn test_lsb_text_and_marker() {
+        let mut pixels = cover();
+        embed_lsb(
+            &mut pixels,
+            b"OPENSTEGO and a message hidden by another tool",
+        );
+
+        let verdict = scan(None, Some(&pixels));
+        let detectors: Vec<&str> = verdict.findings.iter().map(|f| f.detector).collect();
+        assert_eq!(detectors, ["tool-marker", "lsb-text"]);
+        let description = &verdict.findings[1].description;
+        assert!(description.starts_with("b1,y,msb,xy starts with"));
+        assert!(description.ends_with("\"OPENSTEGO and a message hidden by anothe...\""));
+        assert!(verdict.confidence() > 0.99);
+    }
+
+    #[test]
+    fn test_chunks_and_keywords() {
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        let random: Vec<u8> = (0..1024).map(|_| rng.random()).collect();
+        let png = Png::from_chunks(vec![
+            chunk("tEXt", b"Software\0GIMP 2.10"),
+            chunk(
+                "tEXt",
+                b"Secret-Key\0c2VjcmV0IGRhdGEgZW5jb2RlZCBpbiBiYXNlNjQgZm9y",
+            ),
+            chunk("zTXt", &[b"Comment\0\0".as_slice(), &random].concat()),
+            chunk("iDOT", &random[..28]),
+            chunk("prVt", &random),
+            chunk("vEiL", b"payload"),
+        ]);
+
+        let verdict = scan(Some(&png), None);
+        let descriptions: Vec<&str> = verdict
+            .findings
+            .iter()
+            .map(|f| f.description.as_str())
+            .collect();
+        assert_eq!(
+            descriptions,
+            [
+                "'vEiL' chunk written by veil",
+                "'prVt' chunk of 1024 bytes has 7.79 bits of entropy per byte",
+                "tEXt keyword \"Secret-Key\"",
+                "tEXt \"Secret-Key\" holds 44 bytes of base64",
+                "Unknown 'prVt' chunk of 1024 bytes",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_custom_detector() {
+        struct Always;
+        impl Detector for Always {
+            fn name(&self) -> &'static str {
+                "always"
+            }
+            fn detect(&self, _: &Target) -> Vec<Finding> {
+                vec![Finding {
+                    detector: self.name(),
+                    confidence: 0.5,
+                    description: "test".into(),
+                }]
+            }
+        }
+
+        let mut scanner = Scanner::empty();
+        scanner.register(Always);
+        scanner.register(Always);
+        assert_eq!(scanner.detectors(), ["always", "always"]);
+
+        let verdict = scanner.scan(&Target {
+            bytes: &[],
+            png: None,
+            pixels: None,
+        });
+        assert_eq!(verdict.confidence(), 0.75);
+        assert_eq!(verdict.summary(), "hidden data likely");
+    }
+}
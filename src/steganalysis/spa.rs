@@ -0,0 +1,69 @@
+//! Sample Pair Analysis (Dumitrescu, Wu and Wang).
+//!
+//! Horizontally adjacent samples `(u, v)` are sorted into sets by how they
+//! relate: `X` holds pairs where `v` is even and `u < v` or `v` is odd and
+//! `u > v`, `Y` the pairs with the opposite order, `Z` the equal pairs, and
+//! `W` the pairs of `Y` that differ only in the least significant bit. For a
+//! natural image `X` and `Y` are about equally large. LSB embedding moves
+//! pairs between the sets in a way that depends only on the embedding rate
+//! `p`, which makes `p` the smaller root of
+//! `(|W| + |Z|) / 2 * p^2 + (2|X| - |P|) * p + |Y| - |X| = 0`.
+
+use crate::pixels::PixelBuffer;
+use crate::steganalysis::channel_rows;
+use crate::steganalysis::rs::smaller_root;
+
+/// Estimates the fraction of samples of a channel that carry message bits.
+///
+/// Returns `None` when the channel has no sample pairs or the equation has
+/// no solution.
+pub fn estimate(pixels: &PixelBuffer, channel: u8) -> Option<f64> {
+    let (mut x, mut y, mut z, mut w, mut pairs) = (0usize, 0usize, 0usize, 0usize, 0usize);
+    for row in channel_rows(pixels, channel) {
+        for pair in row.windows(2) {
+            let (u, v) = (pair[0], pair[1]);
+            pairs += 1;
+            if u == v {
+                z += 1;
+            } else if (v % 2 == 0) == (u < v) {
+                x += 1;
+            } else {
+                y += 1;
+                if u >> 1 == v >> 1 {
+                    w += 1;
+                }
+            }
+        }
+    }
+    if pairs == 0 {
+        return None;
+    }
+
+    let a = (w + z) as f64 / 2.0;
+    let b = 2.0 * x as f64 - pairs as f64;
+    let c = y as f64 - x as f64;
+    let rate = smaller_root(a, b, c)?;
+    rate.is_finite().then(|| rate.clamp(0.0, 1.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::steganalysis::tests::{cover, embed_at_rate};
+
+    #[test]
+    fn test_recovers_embedding_rates() {
+        for rate in [0.0, 0.25, 0.5, 0.75] {
+            let mut pixels = cover();
+            embed_at_rate(&mut pixels, rate);
+            let estimate = estimate(&pixels, 0).unwrap();
+            assert!((estimate - rate).abs() < 0.06, "{} for {}", estimate, rate);
+        }
+    }
+
+    #[test]
+    fn test_no_pairs() {
+        let pixels = PixelBuffer::new(1, 4, 1, 255, vec![1, 2, 3, 4]).unwrap();
+        assert_eq!(estimate(&pixels, 0), None);
+    }
+}
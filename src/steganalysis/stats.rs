@@ -0,0 +1,362 @@
+//! Byte statistics for every chunk of a PNG and for its inflated image data.
+//!
+//! Encrypted or compressed payloads look like random bytes: close to 8 bits
+//! of entropy per byte, a flat histogram and no gain from compression. Image
+//! metadata rarely does, so such ancillary chunks are flagged. The image data
+//! stream is checked for bytes that decoders never read, which is where
+//! payloads hide without changing the pixels.
+
+use crate::png::idat::ImageData;
+use crate::png::{ImageHeader, Png};
+use crate::steganalysis::{entropy, shade};
+
+/// Ancillary chunk types whose data is compressed by definition.
+pub const COMPRESSED_CHUNKS: [&str; 4] = ["zTXt", "iTXt", "iCCP", "fdAT"];
+
+/// Shortest data whose entropy is meaningful.
+const MIN_RANDOM_LEN: usize = 64;
+
+/// Number of cells in a drawn histogram, each covering 8 byte values.
+const HISTOGRAM_CELLS: usize = 32;
+
+/// Statistics of a run of bytes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stats {
+    pub length: usize,
+    /// Shannon entropy in bits per byte.
+    pub entropy: f64,
+    /// Number of occurrences of every byte value.
+    pub histogram: [usize; 256],
+    /// Size after deflate compression relative to the original size.
+    pub compression_ratio: f64,
+}
+
+impl Stats {
+    /// Computes the statistics of the bytes.
+    pub fn of(bytes: &[u8]) -> Self {
+        let mut histogram = [0; 256];
+        for &byte in bytes {
+            histogram[byte as usize] += 1;
+        }
+        let compression_ratio = if bytes.is_empty() {
+            1.0
+        } else {
+            miniz_oxide::deflate::compress_to_vec(bytes, 9).len() as f64 / bytes.len() as f64
+        };
+        Self {
+            length: bytes.len(),
+            entropy: entropy(bytes),
+            histogram,
+            compression_ratio,
+        }
+    }
+
+    /// Returns the entropy relative to the most the length allows, from 0 to 1.
+    ///
+    /// Fewer than 256 bytes cannot reach 8 bits per byte, so short data is
+    /// compared with `log2(length)` instead.
+    pub fn entropy_ratio(&self) -> f64 {
+        match self.length {
+            0 | 1 => 0.0,
+            length => self.entropy / (length.min(256) as f64).log2(),
+        }
+    }
+
+    /// Returns true if the bytes are long enough and as random as ciphertext.
+    pub fn looks_random(&self) -> bool {
+        self.length >= MIN_RANDOM_LEN && self.entropy_ratio() >= 0.95
+    }
+
+    /// Draws the histogram in characters, one per 8 byte values.
+    pub fn histogram_line(&self) -> String {
+        let cells: Vec<usize> = self
+            .histogram
+            .chunks(256 / HISTOGRAM_CELLS)
+            .map(|cell| cell.iter().sum())
+            .collect();
+        let fullest = cells.iter().copied().max().unwrap_or(0).max(1);
+        cells
+            .iter()
+            .map(|&count| shade(count as f64 / fullest as f64))
+            .collect()
+    }
+}
+
+/// Statistics of one part of the file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Segment {
+    /// The chunk type, or a description for parts that are not chunks.
+    pub name: String,
+    /// Position in the file, if the segment is stored there as is.
+    pub offset: Option<usize>,
+    pub stats: Stats,
+    /// Reasons the segment looks suspicious.
+    pub anomalies: Vec<String>,
+}
+
+/// Statistics of a whole PNG.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report {
+    pub segments: Vec<Segment>,
+}
+
+impl Report {
+    /// Returns the number of anomalies in all segments.
+    pub fn anomalies(&self) -> usize {
+        self.segments.iter().map(|s| s.anomalies.len()).sum()
+    }
+
+    /// Renders the report as a table with histograms, anomalies below their segment.
+    pub fn to_text(&self) -> String {
+        let mut text = format!(
+            "{:>8}  {:<16} {:>9} {:>8} {:>6}  Histogram (00..ff)",
+            "Offset", "Segment", "Length", "Entropy", "Ratio"
+        );
+        for segment in &self.segments {
+            let offset = segment
+                .offset
+                .map_or("-".to_string(), |offset| offset.to_string());
+            text += &format!(
+                "\n{:>8}  {:<16} {:>9} {:>8.3} {:>6.2}  |{}|",
+                offset,
+                segment.name,
+                segment.stats.length,
+                segment.stats.entropy,
+                segment.stats.compression_ratio,
+                segment.stats.histogram_line()
+            );
+            for anomaly in &segment.anomalies {
+                text += &format!("\n{:>10}⚠ {}", "", anomaly);
+            }
+        }
+        text += &format!("\nAnomalies: {}", self.anomalies());
+        text
+    }
+}
+
+/// Computes the statistics of every chunk, the inflated image data and any
+/// bytes after `IEND`.
+pub fn analyze(png: &Png) -> Report {
+    let mut segments = Vec::new();
+    let mut offset = png.header().len();
+    for chunk in png.chunks() {
+        let chunk_type = chunk.chunk_type();
+        let name = chunk_type.to_string();
+        let stats = Stats::of(chunk.data());
+
+        let mut anomalies = Vec::new();
+        if !chunk_type.is_critical()
+            && !COMPRESSED_CHUNKS.contains(&name.as_str())
+            && stats.looks_random()
+        {
+            anomalies.push("Near-random data in an ancillary chunk, possibly encrypted".into());
+        }
+
+        segments.push(Segment {
+            name,
+            offset: Some(offset),
+            stats,
+            anomalies,
+        });
+        offset += chunk.as_bytes().len();
+    }
+
+    if png.chunk_by_type("IDAT").is_some() {
+        segments.push(image_data(png));
+    }
+
+    if !png.trailing_data().is_empty() {
+        segments.push(Segment {
+            name: "after IEND".into(),
+            offset: Some(offset),
+            stats: Stats::of(png.trailing_data()),
+            anomalies: vec!["Data after the end of the image".into()],
+        });
+    }
+    Report { segments }
+}
+
+/// Inflates the image data and looks for parts that decoders never read.
+fn image_data(png: &Png) -> Segment {
+    let sizes: Vec<usize> = png
+        .chunks()
+        .iter()
+        .filter(|chunk| chunk.chunk_type().to_string() == "IDAT")
+        .map(|chunk| chunk.data().len())
+        .collect();
+
+    let mut anomalies = Vec::new();
+    // Encoders split the stream into equal chunks, so only the last one may differ
+    if sizes[..sizes.len() - 1]
+        .iter()
+        .any(|&size| size != sizes[0])
+    {
+        anomalies.push(format!("Irregular IDAT chunk sizes: {:?}", sizes));
+    }
+
+    let image = match ImageData::from_png(png) {
+        Ok(image) => image,
+        Err(e) => {
+            anomalies.push(e.to_string());
+            return Segment {
+                name: "IDAT (inflated)".into(),
+                offset: None,
+                stats: Stats::of(&[]),
+                anomalies,
+            };
+        }
+    };
+
+    let stats = Stats::of(&image.inflated);
+    if let [cmf, flg, ..] = image.stream[..]
+        && (cmf & 0x0f != 8 || !(u16::from(cmf) << 8 | u16::from(flg)).is_multiple_of(31))
+    {
+        anomalies.push("Invalid zlib header".into());
+    }
+    if !image.trailing().is_empty() {
+        anomalies.push(format!(
+            "{} bytes after the end of the zlib stream",
+            image.trailing().len()
+        ));
+    }
+    if let Ok(header) = ImageHeader::from_png(png) {
+        let raw_len = header.raw_data_len();
+        if image.inflated.len() > raw_len {
+            anomalies.push(format!(
+                "{} more decompressed bytes than the image needs",
+                image.inflated.len() - raw_len
+            ));
+        }
+    }
+
+    // A stream far larger than a fresh compression hints at stored blocks or padding
+    let stored_ratio = image.stream_len as f64 / image.inflated.len().max(1) as f64;
+    if stored_ratio > 2.0 * stats.compression_ratio + 0.05 {
+        anomalies.push(format!(
+            "Deflate stream is {:.1}x larger than a fresh compression of its data",
+            stored_ratio / stats.compression_ratio
+        ));
+    }
+
+    Segment {
+        name: "IDAT (inflated)".into(),
+        offset: None,
+        stats,
+        anomalies,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::png::idat::{self, IdatMode};
+    use crate::png::{Chunk, ChunkType};
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+    use std::str::FromStr;
+
+    fn chunk(chunk_type: &str, data: &[u8]) -> Chunk {
+        Chunk::new(ChunkType::from_str(chunk_type).unwrap(), data.to_vec())
+    }
+
+    fn testing_png(extra: Vec<Chunk>) -> Png {
+        let header = ImageHeader {
+            width: 32,
+            height: 32,
+            bit_depth: 8,
+            color_type: ImageHeader::COLOR_GRAYSCALE,
+            compression_method: 0,
+            filter_method: 0,
+            interlace_method: 0,
+        };
+        let raw: Vec<u8> = (0..header.raw_data_len()).map(|i| (i % 33) as u8).collect();
+        let stream = miniz_oxide::deflate::compress_to_vec_zlib(&raw, 6);
+
+        let mut chunks = vec![chunk("IHDR", &header.as_bytes())];
+        chunks.extend(extra);
+        chunks.push(chunk("IDAT", &stream));
+        chunks.push(chunk("IEND", b""));
+        Png::from_chunks(chunks)
+    }
+
+    fn anomalies(report: &Report) -> Vec<(String, String)> {
+        report
+            .segments
+            .iter()
+            .flat_map(|s| s.anomalies.iter().map(|a| (s.name.clone(), a.clone())))
+            .collect()
+    }
+
+    #[test]
+    fn test_stats() {
+        let stats = Stats::of(b"aaaabbbb");
+        assert_eq!(stats.length, 8);
+        assert_eq!(stats.entropy, 1.0);
+        assert_eq!(stats.histogram[b'a' as usize], 4);
+        assert_eq!(stats.entropy_ratio(), 1.0 / 3.0);
+        assert_eq!(stats.histogram_line(), "            @                   ");
+
+        let repetitive = Stats::of(&[7; 1000]);
+        assert!(repetitive.compression_ratio < 0.05);
+        assert!(!repetitive.looks_random());
+    }
+
+    #[test]
+    fn test_clean_png() {
+        let png = testing_png(vec![chunk("tEXt", b"Software\0veil")]);
+        let report = analyze(&png);
+
+        let names: Vec<&str> = report.segments.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["IHDR", "tEXt", "IDAT", "IEND", "IDAT (inflated)"]);
+        assert_eq!(report.segments[1].offset, Some(33));
+        assert_eq!(report.anomalies(), 0);
+        assert!(report.to_text().ends_with("Anomalies: 0"));
+    }
+
+    #[test]
+    fn test_random_ancillary_chunk() {
+        let mut rng = ChaCha8Rng::seed_from_u64(3);
+        let random: Vec<u8> = (0..512).map(|_| rng.random()).collect();
+        let compressed = [b"Comment\0\0".as_slice(), &random].concat();
+        let png = testing_png(vec![chunk("prVt", &random), chunk("zTXt", &compressed)]);
+
+        assert_eq!(
+            anomalies(&analyze(&png)),
+            [(
+                "prVt".to_string(),
+                "Near-random data in an ancillary chunk, possibly encrypted".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_odd_image_data() {
+        let mut png = testing_png(Vec::new());
+        idat::hide(&mut png, b"after the stream", IdatMode::Trailing).unwrap();
+        png.set_trailing_data(b"appended".to_vec());
+        assert_eq!(
+            anomalies(&analyze(&png)),
+            [
+                (
+                    "IDAT (inflated)".to_string(),
+                    "24 bytes after the end of the zlib stream".to_string()
+                ),
+                (
+                    "after IEND".to_string(),
+                    "Data after the end of the image".to_string()
+                ),
+            ]
+        );
+
+        let mut png = testing_png(Vec::new());
+        idat::hide(&mut png, &[0x5a; 4000], IdatMode::StoredBlock).unwrap();
+        let found: Vec<String> = anomalies(&analyze(&png)).into_iter().map(|a| a.1).collect();
+        assert_eq!(
+            found,
+            [
+                "4008 more decompressed bytes than the image needs",
+                "Deflate stream is 56.6x larger than a fresh compression of its data"
+            ]
+        );
+    }
+}
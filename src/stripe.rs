@@ -0,0 +1,178 @@
+//! Striping one payload across several carriers.
+//!
+//! When a payload exceeds what a single carrier can hide, it is cut into
+//! consecutive stripes, one per carrier. Every stripe records a random set
+//! identifier, its sequence number and the total number of stripes, so the
+//! payload can be reassembled from the carriers in any order and missing
+//! stripes can be named.
+
+use crate::Result;
+use rand::Rng;
+
+/// Marker at the start of every serialized stripe.
+pub const MAGIC: [u8; 4] = *b"vSTR";
+
+/// Size of the serialized stripe header in bytes.
+pub const HEADER_LEN: usize = 12;
+
+/// One piece of a striped payload.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stripe {
+    /// Random identifier common to all stripes of one payload.
+    pub set_id: [u8; 4],
+    /// Position of this stripe, from 1 to `count`.
+    pub sequence: u16,
+    /// Total number of stripes in the set.
+    pub count: u16,
+    pub data: Vec<u8>,
+}
+
+impl Stripe {
+    /// Serializes the stripe for embedding.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_LEN + self.data.len());
+        bytes.extend_from_slice(&MAGIC);
+        bytes.extend_from_slice(&self.set_id);
+        bytes.extend_from_slice(&self.sequence.to_be_bytes());
+        bytes.extend_from_slice(&self.count.to_be_bytes());
+        bytes.extend_from_slice(&self.data);
+        bytes
+    }
+
+    /// Returns true if the bytes look like a serialized stripe.
+    pub fn is_stripe(bytes: &[u8]) -> bool {
+        Self::try_from(bytes).is_ok()
+    }
+}
+
+impl TryFrom<&[u8]> for Stripe {
+    type Error = crate::Error;
+
+    fn try_from(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() < HEADER_LEN || bytes[..4] != MAGIC {
+            return Err("Data is not a payload stripe".into());
+        }
+        let stripe = Stripe {
+            set_id: bytes[4..8].try_into()?,
+            sequence: u16::from_be_bytes([bytes[8], bytes[9]]),
+            count: u16::from_be_bytes([bytes[10], bytes[11]]),
+            data: bytes[HEADER_LEN..].to_vec(),
+        };
+        if stripe.sequence == 0 || stripe.sequence > stripe.count {
+            return Err("Invalid payload stripe header".into());
+        }
+        Ok(stripe)
+    }
+}
+
+/// Cuts data into one stripe per weight, sized in proportion to the weights
+/// (for example the capacities of the carriers).
+pub fn split(data: &[u8], weights: &[usize]) -> Result<Vec<Stripe>> {
+    let count = u16::try_from(weights.len()).map_err(|_| "Too many carriers to stripe across")?;
+    let total: u128 = weights.iter().map(|&w| w as u128).sum();
+    if total == 0 {
+        return Err("Carriers have no capacity to stripe across".into());
+    }
+
+    // Cumulative rounding keeps the lengths summing to the payload length
+    let set_id: [u8; 4] = rand::rng().random();
+    let mut stripes = Vec::with_capacity(weights.len());
+    let mut cumulative = 0u128;
+    let mut start = 0;
+    for (i, &weight) in weights.iter().enumerate() {
+        cumulative += weight as u128;
+        let end = (data.len() as u128 * cumulative / total) as usize;
+        stripes.push(Stripe {
+            set_id,
+            sequence: i as u16 + 1,
+            count,
+            data: data[start..end].to_vec(),
+        });
+        start = end;
+    }
+    Ok(stripes)
+}
+
+/// Returns the sequence numbers missing from a set of stripes.
+pub fn missing(stripes: &[Stripe]) -> Vec<u16> {
+    let Some(first) = stripes.first() else {
+        return Vec::new();
+    };
+    (1..=first.count)
+        .filter(|&sequence| !stripes.iter().any(|s| s.sequence == sequence))
+        .collect()
+}
+
+/// Reassembles a payload from its stripes, given in any order.
+pub fn join(stripes: &[Stripe]) -> Result<Vec<u8>> {
+    let first = stripes.first().ok_or("No payload stripes given")?;
+    if stripes
+        .iter()
+        .any(|s| s.set_id != first.set_id || s.count != first.count)
+    {
+        return Err("Payload stripes belong to different sets".into());
+    }
+
+    let missing = missing(stripes);
+    if !missing.is_empty() {
+        let list: Vec<String> = missing.iter().map(|s| s.to_string()).collect();
+        return Err(format!("Missing stripe {} of {}", list.join(", "), first.count).into());
+    }
+
+    let mut data = Vec::new();
+    for sequence in 1..=first.count {
+        if let Some(stripe) = stripes.iter().find(|s| s.sequence == sequence) {
+            data.extend_from_slice(&stripe.data);
+        }
+    }
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_in_proportion() {
+        let data: Vec<u8> = (0..100).collect();
+        let stripes = split(&data, &[1, 3, 0, 1]).unwrap();
+
+        let lengths: Vec<usize> = stripes.iter().map(|s| s.data.len()).collect();
+        assert_eq!(lengths, [20, 60, 0, 20]);
+        assert!(stripes.iter().all(|s| s.count == 4));
+        assert!(split(&data, &[0, 0]).is_err());
+    }
+
+    #[test]
+    fn test_join_in_any_order() {
+        let data = b"a payload too large for any single carrier".to_vec();
+        let mut stripes = split(&data, &[5, 2, 7]).unwrap();
+        stripes.reverse();
+        stripes.swap(0, 1);
+
+        let bytes: Vec<Vec<u8>> = stripes.iter().map(Stripe::to_bytes).collect();
+        let parsed: Vec<Stripe> = bytes
+            .iter()
+            .map(|b| Stripe::try_from(b.as_slice()).unwrap())
+            .collect();
+        assert_eq!(join(&parsed).unwrap(), data);
+    }
+
+    #[test]
+    fn test_missing_stripes() {
+        let stripes = split(b"0123456789", &[1, 1, 1, 1]).unwrap();
+        let partial = [stripes[2].clone(), stripes[0].clone()];
+
+        assert_eq!(missing(&partial), [2, 4]);
+        let error = join(&partial).unwrap_err().to_string();
+        assert_eq!(error, "Missing stripe 2, 4 of 4");
+    }
+
+    #[test]
+    fn test_mixed_sets() {
+        let a = split(b"first", &[1, 1]).unwrap();
+        let b = split(b"second", &[1, 1]).unwrap();
+        assert!(join(&[a[0].clone(), b[1].clone()]).is_err());
+        assert!(!Stripe::is_stripe(b"vSHR"));
+    }
+}
@@ -0,0 +1,4 @@
+#[allow(clippy::module_inception)]
+mod text;
+
+pub use text::{ONE_BIT, Text, TextMode, TextReport, ZERO_BIT, ZERO_WIDTH_CHARS};
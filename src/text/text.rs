@@ -0,0 +1,316 @@
+use crate::envelope;
+use crate::{Error, Result};
+use std::fmt;
+
+/// Zero-width character encoding a 0 bit.
+pub const ZERO_BIT: char = '\u{200B}';
+/// Zero-width character encoding a 1 bit.
+pub const ONE_BIT: char = '\u{200C}';
+
+/// Invisible characters that are flagged by [`Text::analyze`].
+pub const ZERO_WIDTH_CHARS: [char; 6] = [
+    '\u{200B}', '\u{200C}', '\u{200D}', '\u{2060}', '\u{FEFF}', '\u{180E}',
+];
+
+/// How payload bits are encoded into text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum TextMode {
+    /// Zero-width Unicode characters inserted after the first character.
+    #[default]
+    ZeroWidth,
+    /// Trailing spaces (0) and tabs (1) at the end of lines, SNOW-style.
+    Whitespace,
+}
+
+/// Summary of invisible content found in a text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TextReport {
+    /// Number of zero-width characters (excluding a leading byte order mark).
+    pub zero_width_chars: usize,
+    /// Number of lines ending in spaces or tabs.
+    pub trailing_whitespace_lines: usize,
+}
+
+impl TextReport {
+    /// Returns true if the text contains invisible content that may hide data.
+    pub fn is_suspicious(&self) -> bool {
+        self.zero_width_chars > 0 || self.trailing_whitespace_lines > 0
+    }
+}
+
+/// Represents a plain-text carrier.
+#[derive(Debug, Clone)]
+pub struct Text {
+    content: String,
+    mode: TextMode,
+}
+
+impl Text {
+    /// Number of whitespace-encoded bits appended to each line.
+    pub const BITS_PER_LINE: usize = 8;
+
+    /// Reads a UTF-8 text file from the given path.
+    pub fn from_file<P: AsRef<std::path::Path>>(path: P) -> Result<Self> {
+        let data = std::fs::read(path)?;
+        Self::try_from(data.as_slice())
+    }
+
+    /// Returns the text content.
+    pub fn content(&self) -> &str {
+        &self.content
+    }
+
+    /// Returns the encoding used by [`Text::hide`].
+    pub fn mode(&self) -> TextMode {
+        self.mode
+    }
+
+    /// Sets the encoding used by [`Text::hide`].
+    pub fn set_mode(&mut self, mode: TextMode) {
+        self.mode = mode;
+    }
+
+    /// Writes the text to a file at the given path.
+    pub fn to_file<P: AsRef<std::path::Path>>(&self, path: P) -> Result<()> {
+        std::fs::write(path, &self.content)?;
+        Ok(())
+    }
+
+    /// Hides data in the text, replacing any payload hidden earlier.
+    ///
+    /// Only an earlier payload is removed, so zero-width non-joiners that the
+    /// text needs (as in Persian or Indic scripts) are kept. Trailing
+    /// whitespace, such as Markdown line breaks, is only cleared when it
+    /// carries a payload or the whitespace encoding needs the line ends.
+    pub fn hide(&mut self, data: &[u8]) -> Result<()> {
+        let bits: Vec<u8> = envelope::bits(&envelope::seal(data)).collect();
+        let mut cover = strip_zero_width_payload(&self.content);
+        if self.mode == TextMode::Whitespace
+            || envelope::open(&envelope::pack_bits(whitespace_bits(&cover))).is_ok()
+        {
+            cover = strip_trailing_whitespace(&cover);
+        }
+
+        self.content = match self.mode {
+            TextMode::ZeroWidth => {
+                let hidden: String = bits
+                    .iter()
+                    .map(|&bit| if bit == 1 { ONE_BIT } else { ZERO_BIT })
+                    .collect();
+                let split = cover.chars().next().map_or(0, char::len_utf8);
+                format!("{}{}{}", &cover[..split], hidden, &cover[split..])
+            }
+            TextMode::Whitespace => embed_whitespace(&cover, &bits),
+        };
+        Ok(())
+    }
+
+    /// Extracts data hidden with either encoding.
+    pub fn extract(&self) -> Result<Vec<u8>> {
+        let zero_width = self.content.chars().filter_map(|c| match c {
+            ZERO_BIT => Some(0),
+            ONE_BIT => Some(1),
+            _ => None,
+        });
+        envelope::open(&envelope::pack_bits(zero_width))
+            .or_else(|_| envelope::open(&envelope::pack_bits(whitespace_bits(&self.content))))
+    }
+
+    /// Counts invisible characters and trailing whitespace.
+    pub fn analyze(&self) -> TextReport {
+        let zero_width_chars = self
+            .content
+            .chars()
+            .enumerate()
+            .filter(|&(i, c)| ZERO_WIDTH_CHARS.contains(&c) && !(i == 0 && c == '\u{FEFF}'))
+            .count();
+        let trailing_whitespace_lines = lines(&self.content)
+            .filter(|(line, _)| line.ends_with([' ', '\t']))
+            .count();
+
+        TextReport {
+            zero_width_chars,
+            trailing_whitespace_lines,
+        }
+    }
+}
+
+/// Implements conversion from bytes to a Text, requiring valid UTF-8.
+impl TryFrom<&[u8]> for Text {
+    type Error = Error;
+
+    fn try_from(value: &[u8]) -> Result<Self> {
+        Ok(Self {
+            content: String::from_utf8(value.to_vec())?,
+            mode: TextMode::default(),
+        })
+    }
+}
+
+impl From<&str> for Text {
+    fn from(value: &str) -> Self {
+        Self {
+            content: value.to_string(),
+            mode: TextMode::default(),
+        }
+    }
+}
+
+impl fmt::Display for Text {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.content)
+    }
+}
+
+/// Splits text into lines, yielding each line with its original terminator.
+fn lines(text: &str) -> impl Iterator<Item = (&str, &str)> {
+    text.split_inclusive('\n').map(|line| {
+        let body = line.trim_end_matches(['\n', '\r']);
+        (body, &line[body.len()..])
+    })
+}
+
+/// Removes a zero-width payload from after the first character, leaving any
+/// other zero-width characters in place.
+fn strip_zero_width_payload(text: &str) -> String {
+    let split = text.chars().next().map_or(0, char::len_utf8);
+    let (first, rest) = text.split_at(split);
+    let run: Vec<u8> = rest
+        .chars()
+        .map_while(|c| match c {
+            ZERO_BIT => Some(0),
+            ONE_BIT => Some(1),
+            _ => None,
+        })
+        .collect();
+
+    match envelope::message_length(&envelope::pack_bits(run.iter().copied())) {
+        Ok(length) if (envelope::HEADER_LEN + length) * 8 <= run.len() => {
+            // Both bit characters take the same number of bytes in UTF-8
+            let end = (envelope::HEADER_LEN + length) * 8 * ZERO_BIT.len_utf8();
+            format!("{}{}", first, &rest[end..])
+        }
+        _ => text.to_string(),
+    }
+}
+
+fn strip_trailing_whitespace(text: &str) -> String {
+    lines(text)
+        .map(|(line, ending)| format!("{}{}", line.trim_end_matches([' ', '\t']), ending))
+        .collect()
+}
+
+/// Appends up to [`Text::BITS_PER_LINE`] whitespace characters to each line,
+/// adding whitespace-only lines at the end when the text is too short.
+fn embed_whitespace(cover: &str, bits: &[u8]) -> String {
+    let encode = |bits: &[u8]| -> String {
+        bits.iter()
+            .map(|&bit| if bit == 1 { '\t' } else { ' ' })
+            .collect()
+    };
+
+    let mut groups = bits.chunks(Text::BITS_PER_LINE);
+    let mut result = String::with_capacity(cover.len() + bits.len() * 2);
+    let mut newline = "\n";
+    for (line, ending) in lines(cover) {
+        result.push_str(line);
+        if let Some(group) = groups.next() {
+            result.push_str(&encode(group));
+        }
+        if !ending.is_empty() {
+            newline = ending;
+        }
+        result.push_str(ending);
+    }
+
+    for group in groups {
+        if !result.is_empty() && !result.ends_with('\n') {
+            result.push_str(newline);
+        }
+        result.push_str(&encode(group));
+        result.push_str(newline);
+    }
+    result
+}
+
+fn whitespace_bits(text: &str) -> impl Iterator<Item = u8> + '_ {
+    lines(text).flat_map(|(line, _)| {
+        let trailing = &line[line.trim_end_matches([' ', '\t']).len()..];
+        trailing.chars().map(|c| if c == '\t' { 1 } else { 0 })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COVER: &str = "Hello team,\r\nthe meeting moved to 3pm.\r\nThanks!\r\n";
+
+    #[test]
+    fn test_zero_width_roundtrip() {
+        let mut text = Text::from(COVER);
+        text.hide(b"meet at dawn").unwrap();
+
+        assert!(text.content().starts_with('H'));
+        assert_eq!(strip_zero_width_payload(text.content()), COVER);
+        assert_eq!(text.extract().unwrap(), b"meet at dawn");
+    }
+
+    #[test]
+    fn test_whitespace_roundtrip_adds_lines() {
+        let mut text = Text::from(COVER);
+        text.set_mode(TextMode::Whitespace);
+        text.hide(b"meet at dawn").unwrap();
+
+        let content = text.content();
+        assert!(content.lines().count() > 3);
+        assert!(content.starts_with("Hello team,"));
+        assert!(content.contains("\r\n"));
+        assert_eq!(text.extract().unwrap(), b"meet at dawn");
+    }
+
+    #[test]
+    fn test_whitespace_without_final_newline() {
+        let mut text = Text::from("one line");
+        text.set_mode(TextMode::Whitespace);
+        text.hide(b"x").unwrap();
+
+        assert!(text.content().starts_with("one line"));
+        assert_eq!(text.extract().unwrap(), b"x");
+    }
+
+    #[test]
+    fn test_hide_replaces_previous_payload() {
+        let mut text = Text::from(COVER);
+        text.hide(b"first").unwrap();
+        text.set_mode(TextMode::Whitespace);
+        text.hide(b"second").unwrap();
+
+        assert_eq!(text.analyze().zero_width_chars, 0);
+        assert_eq!(text.extract().unwrap(), b"second");
+    }
+
+    #[test]
+    fn test_zero_width_keeps_cover_formatting() {
+        // A zero-width non-joiner inside a Persian word and a Markdown line break
+        let cover = "می\u{200C}خواهم  \nnext line\n";
+        let mut text = Text::from(cover);
+        text.hide(b"first").unwrap();
+        text.hide(b"second").unwrap();
+
+        assert_eq!(text.extract().unwrap(), b"second");
+        assert_eq!(strip_zero_width_payload(text.content()), cover);
+    }
+
+    #[test]
+    fn test_analyze() {
+        let clean = Text::from("\u{FEFF}plain text\n");
+        assert!(!clean.analyze().is_suspicious());
+
+        let marked = Text::from("plain\u{200D} text \nnext\n");
+        let report = marked.analyze();
+        assert_eq!(report.zero_width_chars, 1);
+        assert_eq!(report.trailing_whitespace_lines, 1);
+        assert!(marked.extract().is_err());
+    }
+}
@@ -0,0 +1,201 @@
+use crate::{Error, Result};
+use std::fmt::{self, Display, Formatter};
+use std::io::{BufReader, Read};
+use std::str::FromStr;
+
+/// Represents a RIFF FourCC chunk identifier, stored as four ASCII bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FourCC {
+    bytes: [u8; 4],
+}
+
+impl FourCC {
+    /// Returns the four bytes representing the chunk identifier.
+    pub fn bytes(&self) -> [u8; 4] {
+        self.bytes
+    }
+
+    /// Returns true if the given byte is valid inside a FourCC (printable ASCII or space).
+    pub fn is_valid_byte(byte: u8) -> bool {
+        byte.is_ascii_graphic() || byte == b' '
+    }
+}
+
+/// Implements conversion from a 4-byte array to a FourCC, validating the bytes.
+impl TryFrom<[u8; 4]> for FourCC {
+    type Error = Error;
+
+    fn try_from(bytes: [u8; 4]) -> Result<Self> {
+        if !bytes.iter().all(|b| Self::is_valid_byte(*b)) {
+            return Err("Invalid FourCC bytes".into());
+        }
+
+        Ok(Self { bytes })
+    }
+}
+
+/// Implements conversion from a string to a FourCC, validating the string length and bytes.
+impl FromStr for FourCC {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let array: [u8; 4] = s
+            .as_bytes()
+            .try_into()
+            .map_err(|_| "String must be exactly 4 bytes")?;
+        Self::try_from(array)
+    }
+}
+
+impl Display for FourCC {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            self.bytes
+                .iter()
+                .map(|b| char::from(*b))
+                .collect::<String>()
+        )
+    }
+}
+
+/// Represents a RIFF chunk inside a WebP container, including its FourCC and data.
+///
+/// Unlike PNG chunks, RIFF chunks carry no checksum, use a little-endian
+/// length and are padded to an even size with a single zero byte.
+#[derive(Debug, Clone)]
+pub struct Chunk {
+    fourcc: FourCC,
+    data: Vec<u8>,
+}
+
+impl Chunk {
+    /// Creates a new chunk with the given FourCC and data.
+    pub fn new(fourcc: FourCC, data: Vec<u8>) -> Self {
+        Self { fourcc, data }
+    }
+
+    /// Returns the length of the chunk data (without padding).
+    pub fn length(&self) -> u32 {
+        self.data.len() as u32
+    }
+
+    /// Returns a reference to the chunk FourCC.
+    pub fn fourcc(&self) -> &FourCC {
+        &self.fourcc
+    }
+
+    /// Returns a slice of the chunk data bytes.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Returns a mutable reference to the chunk data bytes.
+    pub fn data_mut(&mut self) -> &mut Vec<u8> {
+        &mut self.data
+    }
+
+    /// Returns the number of bytes the chunk occupies when serialized, including padding.
+    pub fn padded_size(&self) -> usize {
+        8 + self.data.len() + (self.data.len() & 1)
+    }
+
+    /// Serializes the chunk to a vector of bytes, adding the padding byte if needed.
+    pub fn as_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.padded_size());
+        bytes.extend_from_slice(&self.fourcc.bytes());
+        bytes.extend_from_slice(&self.length().to_le_bytes());
+        bytes.extend_from_slice(&self.data);
+        if self.data.len() % 2 == 1 {
+            bytes.push(0);
+        }
+        bytes
+    }
+}
+
+/// Implements conversion from a byte slice to a Chunk.
+///
+/// The trailing padding byte is optional so that the last chunk of a
+/// slightly truncated file can still be read.
+impl TryFrom<&[u8]> for Chunk {
+    type Error = Error;
+
+    fn try_from(value: &[u8]) -> Result<Self> {
+        // Chunk is at least 8 bytes: 4 bytes for the FourCC and 4 bytes for the length
+        if value.len() < 8 {
+            return Err("Chunk is too short".into());
+        }
+
+        let mut reader = BufReader::new(value);
+        let mut buffer: [u8; 4] = [0; 4];
+
+        reader.read_exact(&mut buffer)?;
+        let fourcc = FourCC::try_from(buffer).map_err(|_| "Invalid chunk FourCC")?;
+
+        reader.read_exact(&mut buffer)?;
+        let length = u32::from_le_bytes(buffer);
+
+        let mut data = vec![0u8; length as usize];
+        reader
+            .read_exact(&mut data)
+            .map_err(|_| format!("Chunk '{}' is truncated", fourcc))?;
+
+        Ok(Self { fourcc, data })
+    }
+}
+
+impl fmt::Display for Chunk {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Chunk {{",)?;
+        writeln!(f, "  FourCC: {}", self.fourcc())?;
+        writeln!(f, "  Length: {}", self.length())?;
+        writeln!(f, "}}",)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fourcc_from_str() {
+        let fourcc = FourCC::from_str("VP8 ").unwrap();
+        assert_eq!(fourcc.bytes(), *b"VP8 ");
+        assert_eq!(fourcc.to_string(), "VP8 ");
+    }
+
+    #[test]
+    fn test_invalid_fourcc() {
+        assert!(FourCC::from_str("VP8").is_err());
+        assert!(FourCC::try_from([0, b'P', b'8', b'X']).is_err());
+    }
+
+    #[test]
+    fn test_odd_chunk_is_padded() {
+        let chunk = Chunk::new(FourCC::from_str("EXIF").unwrap(), b"abc".to_vec());
+        let bytes = chunk.as_bytes();
+
+        assert_eq!(bytes.len(), 12);
+        assert_eq!(chunk.padded_size(), 12);
+        assert_eq!(&bytes[4..8], &3u32.to_le_bytes());
+        assert_eq!(bytes[11], 0);
+    }
+
+    #[test]
+    fn test_chunk_roundtrip() {
+        let chunk = Chunk::new(FourCC::from_str("XMP ").unwrap(), b"hello".to_vec());
+        let parsed = Chunk::try_from(chunk.as_bytes().as_slice()).unwrap();
+
+        assert_eq!(parsed.fourcc(), chunk.fourcc());
+        assert_eq!(parsed.data(), b"hello");
+    }
+
+    #[test]
+    fn test_truncated_chunk() {
+        let mut bytes = Chunk::new(FourCC::from_str("XMP ").unwrap(), b"hello".to_vec()).as_bytes();
+        bytes.truncate(10);
+        assert!(Chunk::try_from(bytes.as_slice()).is_err());
+    }
+}
@@ -0,0 +1,6 @@
+mod chunk;
+#[allow(clippy::module_inception)]
+mod webp;
+
+pub use chunk::{Chunk, FourCC};
+pub use webp::{Webp, WebpKind};
@@ -0,0 +1,391 @@
+use crate::webp::{Chunk, FourCC};
+use crate::{Error, Result};
+use std::fmt;
+use std::str::FromStr;
+
+/// The bitstream layout of a WebP file, determined by its first chunk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WebpKind {
+    /// Simple file format with a single `VP8 ` chunk.
+    Lossy,
+    /// Simple file format with a single `VP8L` chunk.
+    Lossless,
+    /// Extended file format starting with a `VP8X` chunk.
+    Extended,
+}
+
+/// Represents a WebP image as a RIFF container and its chunks.
+#[derive(Debug, Clone)]
+pub struct Webp {
+    chunks: Vec<Chunk>,
+}
+
+impl Webp {
+    pub const RIFF_HEADER: [u8; 4] = *b"RIFF";
+    pub const WEBP_SIGNATURE: [u8; 4] = *b"WEBP";
+    pub const STANDARD_CHUNKS: [&str; 9] = [
+        "VP8 ", "VP8L", "VP8X", "ALPH", "ANIM", "ANMF", "ICCP", "EXIF", "XMP ",
+    ];
+
+    /* VP8X feature flags (first byte of the VP8X payload) */
+    pub const FLAG_ICC: u8 = 0b0010_0000;
+    pub const FLAG_ALPHA: u8 = 0b0001_0000;
+    pub const FLAG_EXIF: u8 = 0b0000_1000;
+    pub const FLAG_XMP: u8 = 0b0000_0100;
+    pub const FLAG_ANIMATION: u8 = 0b0000_0010;
+
+    /// Reads a WebP from a file at the given path.
+    pub fn from_file<P: AsRef<std::path::Path>>(path: P) -> Result<Self> {
+        let data = std::fs::read(path)?;
+        Self::try_from(data.as_slice())
+    }
+
+    /// Creates a WebP from a vector of chunks.
+    pub fn from_chunks(chunks: Vec<Chunk>) -> Self {
+        Self { chunks }
+    }
+
+    /// Appends a chunk to the WebP.
+    ///
+    /// Simple (`VP8 `/`VP8L`) files may only contain a single chunk, so call
+    /// [`Webp::ensure_extended`] first when adding anything besides image data.
+    pub fn append_chunk(&mut self, chunk: Chunk) {
+        self.chunks.push(chunk);
+    }
+
+    /// Removes and returns the first chunk with the given FourCC, or returns an error if not found.
+    pub fn remove_first_chunk(&mut self, fourcc: &str) -> Result<Chunk> {
+        match self
+            .chunks
+            .iter()
+            .position(|chunk| chunk.fourcc().to_string() == fourcc)
+        {
+            Some(index) => Ok(self.chunks.remove(index)),
+            None => Err(format!("Chunk type '{}' not found", fourcc).into()),
+        }
+    }
+
+    /// Returns a slice of all chunks in the WebP.
+    pub fn chunks(&self) -> &[Chunk] {
+        &self.chunks
+    }
+
+    /// Returns the first chunk with the given FourCC, if any.
+    pub fn chunk_by_fourcc(&self, fourcc: &str) -> Option<&Chunk> {
+        self.chunks
+            .iter()
+            .find(|chunk| chunk.fourcc().to_string() == fourcc)
+    }
+
+    /// Returns the file layout as indicated by the first chunk.
+    pub fn kind(&self) -> Result<WebpKind> {
+        let first = self.chunks.first().ok_or("WebP contains no chunks")?;
+        match &first.fourcc().bytes() {
+            b"VP8 " => Ok(WebpKind::Lossy),
+            b"VP8L" => Ok(WebpKind::Lossless),
+            b"VP8X" => Ok(WebpKind::Extended),
+            _ => Err(format!("Unexpected first WebP chunk '{}'", first.fourcc()).into()),
+        }
+    }
+
+    /// Returns the canvas width and height in pixels.
+    pub fn canvas_size(&self) -> Result<(u32, u32)> {
+        let kind = self.kind()?;
+        let data = self.chunks[0].data();
+        match kind {
+            WebpKind::Lossy => vp8_size(data),
+            WebpKind::Lossless => vp8l_header(data).map(|(w, h, _)| (w, h)),
+            WebpKind::Extended => {
+                if data.len() < 10 {
+                    return Err("VP8X chunk is too short".into());
+                }
+                Ok((1 + read_u24(&data[4..7]), 1 + read_u24(&data[7..10])))
+            }
+        }
+    }
+
+    /// Returns the VP8X feature flags, or `None` for simple files.
+    pub fn vp8x_flags(&self) -> Option<u8> {
+        self.chunk_by_fourcc("VP8X")
+            .and_then(|chunk| chunk.data().first().copied())
+    }
+
+    /// Converts a simple file into the extended format by prepending a `VP8X` chunk.
+    ///
+    /// Extended files are left as they are apart from refreshing their flags.
+    pub fn ensure_extended(&mut self) -> Result<()> {
+        if self.kind()? != WebpKind::Extended {
+            let (width, height) = self.canvas_size()?;
+            let mut data = vec![0u8; 10];
+            write_u24(&mut data[4..7], width - 1);
+            write_u24(&mut data[7..10], height - 1);
+            self.chunks
+                .insert(0, Chunk::new(FourCC::from_str("VP8X")?, data));
+        }
+        self.update_vp8x_flags()
+    }
+
+    /// Recomputes the `VP8X` feature flags from the chunks present in the file.
+    ///
+    /// Metadata flags are set and cleared to match their chunks; the alpha and
+    /// animation flags are only ever set, since they cannot always be derived
+    /// from the chunk list alone.
+    pub fn update_vp8x_flags(&mut self) -> Result<()> {
+        let has = |name: &str| self.chunk_by_fourcc(name).is_some();
+        let lossless_alpha = self
+            .chunk_by_fourcc("VP8L")
+            .and_then(|chunk| vp8l_header(chunk.data()).ok())
+            .is_some_and(|(_, _, alpha)| alpha);
+
+        let mut set = 0;
+        if has("ICCP") {
+            set |= Self::FLAG_ICC;
+        }
+        if has("ALPH") || lossless_alpha {
+            set |= Self::FLAG_ALPHA;
+        }
+        if has("EXIF") {
+            set |= Self::FLAG_EXIF;
+        }
+        if has("XMP ") {
+            set |= Self::FLAG_XMP;
+        }
+        if has("ANIM") {
+            set |= Self::FLAG_ANIMATION;
+        }
+
+        let vp8x = self
+            .chunks
+            .iter_mut()
+            .find(|chunk| &chunk.fourcc().bytes() == b"VP8X")
+            .ok_or("WebP is not in the extended format")?;
+        let data = vp8x.data_mut();
+        if data.is_empty() {
+            return Err("VP8X chunk is too short".into());
+        }
+        let metadata = Self::FLAG_ICC | Self::FLAG_EXIF | Self::FLAG_XMP;
+        data[0] = (data[0] & !metadata) | set;
+        Ok(())
+    }
+
+    /// Serializes the WebP to a vector of bytes, recomputing the RIFF size.
+    pub fn as_bytes(&self) -> Vec<u8> {
+        let body_size: usize = 4 + self.chunks.iter().map(Chunk::padded_size).sum::<usize>();
+        let mut result = Vec::with_capacity(8 + body_size);
+        result.extend_from_slice(&Self::RIFF_HEADER);
+        result.extend_from_slice(&(body_size as u32).to_le_bytes());
+        result.extend_from_slice(&Self::WEBP_SIGNATURE);
+        for chunk in &self.chunks {
+            result.extend(chunk.as_bytes());
+        }
+        result
+    }
+
+    /// Writes the WebP to a file at the given path.
+    pub fn to_file<P: AsRef<std::path::Path>>(&self, path: P) -> Result<()> {
+        std::fs::write(path, self.as_bytes())?;
+        Ok(())
+    }
+
+    /// Get all custom (non-standard) chunks in the WebP
+    pub fn custom_chunks(&self) -> Vec<&Chunk> {
+        self.chunks
+            .iter()
+            .filter(|chunk| {
+                let fourcc = chunk.fourcc().to_string();
+                !Self::STANDARD_CHUNKS.contains(&fourcc.as_str())
+            })
+            .collect()
+    }
+}
+
+/// Implements conversion from a byte slice to a WebP, validating the RIFF header and parsing all chunks.
+impl TryFrom<&[u8]> for Webp {
+    type Error = Error;
+
+    fn try_from(value: &[u8]) -> Result<Self> {
+        if value.len() < 12
+            || value[0..4] != Self::RIFF_HEADER
+            || value[8..12] != Self::WEBP_SIGNATURE
+        {
+            return Err("Invalid WebP header".into());
+        }
+
+        let riff_size = u32::from_le_bytes(value[4..8].try_into()?) as usize;
+        if riff_size < 4 || riff_size > value.len() - 8 {
+            return Err("RIFF size does not match file length".into());
+        }
+        let end = 8 + riff_size;
+
+        let mut chunks = Vec::new();
+        let mut offset = 12;
+        while offset < end {
+            let chunk = Chunk::try_from(&value[offset..end])?;
+            offset += chunk.padded_size();
+            chunks.push(chunk);
+        }
+
+        let webp = Self { chunks };
+        webp.kind()?;
+        Ok(webp)
+    }
+}
+
+impl fmt::Display for Webp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "WebP {{")?;
+        writeln!(f, "  Chunks: {} chunks", self.chunks.len())?;
+        for (i, chunk) in self.chunks.iter().enumerate() {
+            writeln!(
+                f,
+                "    Chunk {}: FourCC: {}, Length: {}",
+                i,
+                chunk.fourcc(),
+                chunk.length()
+            )?;
+        }
+        writeln!(f, "}}")?;
+        Ok(())
+    }
+}
+
+/// Reads the frame size from a lossy `VP8 ` key frame header.
+fn vp8_size(data: &[u8]) -> Result<(u32, u32)> {
+    if data.len() < 10 || data[3..6] != [0x9d, 0x01, 0x2a] {
+        return Err("Invalid VP8 frame header".into());
+    }
+    let width = u16::from_le_bytes([data[6], data[7]]) & 0x3fff;
+    let height = u16::from_le_bytes([data[8], data[9]]) & 0x3fff;
+    if width == 0 || height == 0 {
+        return Err("VP8 frame has a zero dimension".into());
+    }
+    Ok((width as u32, height as u32))
+}
+
+/// Reads the image size and alpha hint from a lossless `VP8L` header.
+fn vp8l_header(data: &[u8]) -> Result<(u32, u32, bool)> {
+    if data.len() < 5 || data[0] != 0x2f {
+        return Err("Invalid VP8L signature".into());
+    }
+    let bits = u32::from_le_bytes([data[1], data[2], data[3], data[4]]);
+    let width = (bits & 0x3fff) + 1;
+    let height = ((bits >> 14) & 0x3fff) + 1;
+    let alpha = (bits >> 28) & 1 == 1;
+    Ok((width, height, alpha))
+}
+
+fn read_u24(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], 0])
+}
+
+fn write_u24(bytes: &mut [u8], value: u32) {
+    bytes.copy_from_slice(&value.to_le_bytes()[..3]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(fourcc: &str, data: &[u8]) -> Chunk {
+        Chunk::new(FourCC::from_str(fourcc).unwrap(), data.to_vec())
+    }
+
+    fn lossless_webp() -> Webp {
+        Webp::try_from(&WEBP_LOSSLESS_FILE[..]).unwrap()
+    }
+
+    #[test]
+    fn test_webp_from_file_bytes() {
+        let webp = lossless_webp();
+        assert_eq!(webp.chunks().len(), 1);
+        assert_eq!(webp.kind().unwrap(), WebpKind::Lossless);
+        assert_eq!(webp.canvas_size().unwrap(), (1, 1));
+    }
+
+    #[test]
+    fn test_as_bytes_roundtrip() {
+        let webp = lossless_webp();
+        assert_eq!(webp.as_bytes(), WEBP_LOSSLESS_FILE.to_vec());
+    }
+
+    #[test]
+    fn test_invalid_header() {
+        let mut bytes = WEBP_LOSSLESS_FILE.to_vec();
+        bytes[8] = b'X';
+        assert!(Webp::try_from(bytes.as_slice()).is_err());
+    }
+
+    #[test]
+    fn test_riff_size_mismatch() {
+        let mut bytes = WEBP_LOSSLESS_FILE.to_vec();
+        bytes[4] = 0xff;
+        assert!(Webp::try_from(bytes.as_slice()).is_err());
+    }
+
+    #[test]
+    fn test_ensure_extended() {
+        let mut webp = lossless_webp();
+        webp.ensure_extended().unwrap();
+
+        assert_eq!(webp.kind().unwrap(), WebpKind::Extended);
+        assert_eq!(webp.canvas_size().unwrap(), (1, 1));
+        assert_eq!(webp.chunks()[1].fourcc().to_string(), "VP8L");
+    }
+
+    #[test]
+    fn test_custom_chunk_roundtrip_with_padding() {
+        let mut webp = lossless_webp();
+        webp.ensure_extended().unwrap();
+        webp.append_chunk(chunk("vEiL", b"odd"));
+
+        let bytes = webp.as_bytes();
+        assert_eq!(bytes.len() % 2, 0);
+        assert_eq!(
+            u32::from_le_bytes(bytes[4..8].try_into().unwrap()) as usize,
+            bytes.len() - 8
+        );
+
+        let parsed = Webp::try_from(bytes.as_slice()).unwrap();
+        let custom = parsed.custom_chunks();
+        assert_eq!(custom.len(), 1);
+        assert_eq!(custom[0].data(), b"odd");
+    }
+
+    #[test]
+    fn test_update_vp8x_flags() {
+        let mut vp8x = vec![0u8; 10];
+        vp8x[0] = Webp::FLAG_XMP;
+        let mut webp = Webp::from_chunks(vec![
+            chunk("VP8X", &vp8x),
+            chunk("ALPH", b"a"),
+            chunk("VP8 ", &[0, 0, 0, 0x9d, 0x01, 0x2a, 1, 0, 1, 0]),
+            chunk("EXIF", b"exif"),
+        ]);
+
+        webp.update_vp8x_flags().unwrap();
+
+        assert_eq!(webp.vp8x_flags(), Some(Webp::FLAG_ALPHA | Webp::FLAG_EXIF));
+    }
+
+    #[test]
+    fn test_lossy_canvas_size() {
+        let webp = Webp::from_chunks(vec![chunk(
+            "VP8 ",
+            &[0, 0, 0, 0x9d, 0x01, 0x2a, 0x40, 0x01, 0xf0, 0x00],
+        )]);
+        assert_eq!(webp.canvas_size().unwrap(), (320, 240));
+
+        let mut empty = Webp::from_chunks(vec![chunk(
+            "VP8 ",
+            &[0, 0, 0, 0x9d, 0x01, 0x2a, 0x00, 0x00, 0xf0, 0x00],
+        )]);
+        assert!(empty.canvas_size().is_err());
+        assert!(empty.ensure_extended().is_err());
+    }
+
+    // A 1x1 lossless WebP image
+    const WEBP_LOSSLESS_FILE: [u8; 34] = [
+        82, 73, 70, 70, 26, 0, 0, 0, 87, 69, 66, 80, 86, 80, 56, 76, 13, 0, 0, 0, 47, 0, 0, 0, 16,
+        7, 16, 17, 17, 136, 136, 254, 7, 0,
+    ];
+}